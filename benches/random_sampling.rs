@@ -0,0 +1,179 @@
+//! Compares the ways of drawing random songs against a large in-memory catalog.
+//!
+//! Run with `cargo bench --bench random_sampling`.
+
+use std::{
+    collections::HashSet,
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rusqlite::{Connection, params};
+
+#[allow(dead_code, unused_imports)]
+#[path = "../src/sampling.rs"]
+mod sampling;
+
+const CATALOG_SIZE: i32 = 100_000;
+const ALBUMS: [&str; 4] = ["Singles/B-Sides", "Live Songs", "Covers", "Demos"];
+const AMOUNT: usize = 5;
+const ITERATIONS: u32 = 200;
+
+fn setup_catalog() -> Connection {
+    let conn = Connection::open_in_memory().expect("Failed to open database");
+
+    conn.execute_batch(
+        "CREATE TABLE songs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            title TEXT,
+            album TEXT
+        );
+        CREATE INDEX songs_album ON songs (album);",
+    )
+    .unwrap();
+
+    let mut rng = ChaCha8Rng::seed_from_u64(0);
+    let tx = conn.unchecked_transaction().unwrap();
+    {
+        let mut insert = tx
+            .prepare("INSERT INTO songs (title, album) VALUES (?1, ?2)")
+            .unwrap();
+        for i in 0..CATALOG_SIZE {
+            // Skew the albums so the filtered benchmarks hit a sparse album too
+            let album = match rng.gen_range(0..100) {
+                0..=59 => ALBUMS[0],
+                60..=89 => ALBUMS[1],
+                90..=98 => ALBUMS[2],
+                _ => ALBUMS[3],
+            };
+            insert
+                .execute(params![format!("Song {}", i), album])
+                .unwrap();
+        }
+    }
+    tx.commit().unwrap();
+
+    // Leave gaps in the ID range like deletions would
+    conn.execute("DELETE FROM songs WHERE id % 7 = 0", [])
+        .unwrap();
+
+    conn
+}
+
+/// The original approach: let SQLite shuffle the whole (filtered) table
+fn order_by_random(conn: &Connection, album: &str, _rng: &mut ChaCha8Rng) -> Vec<i32> {
+    let mut stmt = conn
+        .prepare_cached(
+            "SELECT id FROM songs WHERE ?1 = '' OR album = ?1 ORDER BY RANDOM() LIMIT ?2",
+        )
+        .unwrap();
+    stmt.query_map(params![album, AMOUNT as i64], |row| row.get(0))
+        .unwrap()
+        .map(Result::unwrap)
+        .collect()
+}
+
+/// Random points in the ID range, taking the next matching row and rejecting duplicates
+fn id_range_rejection(conn: &Connection, album: &str, rng: &mut ChaCha8Rng) -> Vec<i32> {
+    let (min, max): (i32, i32) = conn
+        .prepare_cached("SELECT MIN(id), MAX(id) FROM songs WHERE ?1 = '' OR album = ?1")
+        .unwrap()
+        .query_row(params![album], |row| Ok((row.get(0)?, row.get(1)?)))
+        .unwrap();
+
+    let mut stmt = conn
+        .prepare_cached(
+            "SELECT id FROM songs WHERE id >= ?1 AND (?2 = '' OR album = ?2) ORDER BY id LIMIT 1",
+        )
+        .unwrap();
+
+    let mut picked = Vec::new();
+    let mut attempts = 0;
+    while picked.len() < AMOUNT && attempts < AMOUNT * 10 {
+        attempts += 1;
+        let start = rng.gen_range(min..=max);
+        let id: i32 = stmt
+            .query_row(params![start, album], |row| row.get(0))
+            .unwrap();
+        if !picked.contains(&id) {
+            picked.push(id);
+        }
+    }
+
+    picked
+}
+
+/// The bot's approach: sample positions in the cached, sorted ID list
+fn cached_ids(
+    conn: &Connection,
+    cache: &sampling::SongIdCache,
+    album: &str,
+    rng: &mut ChaCha8Rng,
+) -> Vec<i32> {
+    let ids = cache.get(conn, album).unwrap();
+    sampling::uniform_sample(&ids, AMOUNT, &HashSet::new(), rng)
+}
+
+/// Weighted draw over the cached ID list, as used by the weighted `/random` modes
+fn cached_weighted(
+    conn: &Connection,
+    cache: &sampling::SongIdCache,
+    album: &str,
+    rng: &mut ChaCha8Rng,
+) -> Vec<i32> {
+    let ids = cache.get(conn, album).unwrap();
+    let weights: Vec<f64> = ids.iter().map(|id| 1.0 + f64::from(id % 10)).collect();
+    sampling::weighted_sample(&weights, AMOUNT, rng)
+        .into_iter()
+        .map(|index| ids[index])
+        .collect()
+}
+
+fn bench(name: &str, mut run: impl FnMut(&mut ChaCha8Rng) -> Vec<i32>) {
+    let mut rng = ChaCha8Rng::seed_from_u64(42);
+
+    // Warm up caches and prepared statements
+    black_box(run(&mut rng));
+
+    let mut total = Duration::ZERO;
+    let mut worst = Duration::ZERO;
+    for _ in 0..ITERATIONS {
+        let start = Instant::now();
+        black_box(run(&mut rng));
+        let elapsed = start.elapsed();
+        total += elapsed;
+        worst = worst.max(elapsed);
+    }
+
+    println!(
+        "{:<40} mean {:>10.3?}   worst {:>10.3?}",
+        name,
+        total / ITERATIONS,
+        worst
+    );
+}
+
+fn main() {
+    let conn = setup_catalog();
+    let cache = sampling::SongIdCache::default();
+
+    for album in ["", ALBUMS[0], ALBUMS[3]] {
+        let label = if album.is_empty() { "all songs" } else { album };
+        println!("\n{} ({} songs drawn per call)", label, AMOUNT);
+
+        bench("ORDER BY RANDOM()", |rng| {
+            order_by_random(&conn, album, rng)
+        });
+        bench("id range + rejection", |rng| {
+            id_range_rejection(&conn, album, rng)
+        });
+        bench("cached id list (uniform)", |rng| {
+            cached_ids(&conn, &cache, album, rng)
+        });
+        bench("cached id list (weighted)", |rng| {
+            cached_weighted(&conn, &cache, album, rng)
+        });
+    }
+}
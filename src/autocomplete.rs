@@ -0,0 +1,47 @@
+use poise::serenity_prelude as serenity;
+use rusqlite::params;
+
+use crate::Context;
+
+/// Suggests songs whose title contains `partial`, submitting the song ID
+pub async fn autocomplete_song(
+    ctx: Context<'_>,
+    partial: &str,
+) -> Vec<serenity::AutocompleteChoice> {
+    let db = ctx.data().db.clone();
+    let pattern = format!("%{}%", partial);
+
+    let songs = tokio::task::spawn_blocking(
+        move || -> Result<Vec<(i32, String, String)>, rusqlite::Error> {
+            let db_lock = db.blocking_lock();
+
+            let mut stmt = db_lock.prepare(
+                "SELECT id, title, album FROM songs WHERE title LIKE ?1 ORDER BY title LIMIT 25",
+            )?;
+            let song_iter = stmt.query_map(params![pattern], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })?;
+
+            song_iter.collect()
+        },
+    )
+    .await;
+
+    match songs {
+        Ok(Ok(songs)) => songs
+            .into_iter()
+            .map(|(id, title, album)| {
+                let name = format!("{} ({})", title, album);
+                serenity::AutocompleteChoice::new(name.chars().take(100).collect::<String>(), id)
+            })
+            .collect(),
+        Ok(Err(err)) => {
+            eprintln!("Error while fetching songs: {}", err);
+            Vec::new()
+        }
+        Err(err) => {
+            eprintln!("Song autocomplete task failed: {}", err);
+            Vec::new()
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use poise::serenity_prelude as serenity;
 
-use crate::Context;
+use crate::{Context, Data};
 
 #[must_use]
 pub fn is_moderator(ctx: Context<'_>) -> bool {
@@ -43,3 +43,36 @@ pub async fn check_is_moderator(
 
     Ok(user_has_moderator_role)
 }
+
+/// Whether the member who pressed a button has the moderator role
+#[must_use]
+pub fn is_interaction_moderator(interaction: &serenity::ComponentInteraction, data: &Data) -> bool {
+    interaction
+        .member
+        .as_ref()
+        .is_some_and(|member| member.roles.contains(&data.mod_role_id))
+}
+
+/// Button counterpart of [`check_is_moderator`], telling anyone else the button isn't for them
+pub async fn check_interaction_moderator(
+    ctx: &serenity::Context,
+    interaction: &serenity::ComponentInteraction,
+    data: &Data,
+) -> Result<bool, serenity::Error> {
+    let user_has_moderator_role = is_interaction_moderator(interaction, data);
+
+    if !user_has_moderator_role {
+        interaction
+            .create_response(
+                ctx,
+                serenity::CreateInteractionResponse::Message(
+                    serenity::CreateInteractionResponseMessage::new()
+                        .content("This is only available to moderators.")
+                        .ephemeral(true),
+                ),
+            )
+            .await?;
+    }
+
+    Ok(user_has_moderator_role)
+}
@@ -1,7 +1,32 @@
 pub mod album;
+pub mod bracket;
+pub mod compare;
 pub mod delete;
+pub mod draft;
+pub mod emoji;
+pub mod emojiguess;
+pub mod fav;
 pub mod help;
+pub mod leaderboard;
 pub mod list;
+pub mod listened;
+pub mod lyricgame;
+pub mod lyrics;
 pub mod new;
 pub mod ping;
+pub mod playlist;
+pub mod poll;
+pub mod profile;
+pub mod propose;
+pub mod queue;
 pub mod random;
+pub mod rate;
+pub mod setlist;
+pub mod song;
+pub mod songle;
+pub mod sotd;
+pub mod suggest;
+pub mod tag;
+pub mod tierlist;
+pub mod trivia;
+pub mod versus;
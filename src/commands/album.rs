@@ -1,27 +1,30 @@
-use crate::{Context, Error};
+use crate::{Context, Error, commands::rate};
 use poise::serenity_prelude::{self as serenity, Color};
+use rusqlite::params;
 
 struct Album {
-    id: i32,
     name: String,
 }
 
 #[poise::command(
     prefix_command,
     slash_command,
-    subcommands("create", "delete", "list"),
-    subcommand_required,
-    check = "crate::checks::check_is_moderator"
+    subcommands("create", "delete", "list", "show"),
+    subcommand_required
 )]
 
-/// Create, delete or list an album
+/// Create, delete, list or show an album
 pub async fn album(_: Context<'_>) -> Result<(), Error> {
     // This will never be called, because `subcommand_required` parameter is set
     Ok(())
 }
 
 /// Create an album
-#[poise::command(prefix_command, slash_command)]
+#[poise::command(
+    prefix_command,
+    slash_command,
+    check = "crate::checks::check_is_moderator"
+)]
 pub async fn create(
     ctx: Context<'_>,
     #[description = "The name of the album"] album_name: String,
@@ -30,7 +33,7 @@ pub async fn create(
 
     let db = ctx.data().db.clone();
 
-    let _result = tokio::task::spawn_blocking(move || -> Result<(), rusqlite::Error> {
+    tokio::task::spawn_blocking(move || -> Result<(), rusqlite::Error> {
         let db_lock = db.blocking_lock();
 
         let mut stmt = db_lock.prepare("INSERT INTO albums (name) VALUES (?1)")?;
@@ -51,7 +54,11 @@ pub async fn create(
 }
 
 /// Delete an album
-#[poise::command(prefix_command, slash_command)]
+#[poise::command(
+    prefix_command,
+    slash_command,
+    check = "crate::checks::check_is_moderator"
+)]
 pub async fn delete(
     ctx: Context<'_>,
     #[description = "The name of the album"] album_name: String,
@@ -99,21 +106,20 @@ pub async fn delete(
 }
 
 /// List all albums
-#[poise::command(prefix_command, slash_command)]
+#[poise::command(
+    prefix_command,
+    slash_command,
+    check = "crate::checks::check_is_moderator"
+)]
 pub async fn list(ctx: Context<'_>) -> Result<(), Error> {
     let db = ctx.data().db.clone();
 
     let albums = tokio::task::spawn_blocking(move || -> Result<Vec<Album>, rusqlite::Error> {
         let db_lock = db.blocking_lock();
 
-        let mut stmt = db_lock.prepare("SELECT * FROM albums")?;
+        let mut stmt = db_lock.prepare("SELECT name FROM albums")?;
 
-        let album_iter = stmt.query_map([], |row| {
-            Ok(Album {
-                id: row.get(0)?,
-                name: row.get(1)?,
-            })
-        })?;
+        let album_iter = stmt.query_map([], |row| Ok(Album { name: row.get(0)? }))?;
 
         let mut albums = Vec::new();
         for album_result in album_iter {
@@ -150,3 +156,58 @@ pub async fn list(ctx: Context<'_>) -> Result<(), Error> {
 
     Ok(())
 }
+
+/// Show an album's song count and ratings
+#[poise::command(prefix_command, slash_command)]
+pub async fn show(
+    ctx: Context<'_>,
+    #[autocomplete = "crate::commands::new::autocomplete_album"]
+    #[description = "The name of the album"]
+    album_name: String,
+) -> Result<(), Error> {
+    let album_name_cloned = album_name.clone();
+
+    let db = ctx.data().db.clone();
+
+    let album = tokio::task::spawn_blocking(
+        move || -> Result<Option<(u32, rate::RatingSummary)>, rusqlite::Error> {
+            let db_lock = db.blocking_lock();
+
+            let songs: u32 = db_lock.query_row(
+                "SELECT COUNT(*) FROM songs WHERE album = ?1",
+                params![album_name_cloned],
+                |row| row.get(0),
+            )?;
+            if songs == 0 {
+                return Ok(None);
+            }
+
+            let summary = rate::load_summary(&db_lock, None, Some(&album_name_cloned))?;
+
+            Ok(Some((songs, summary)))
+        },
+    )
+    .await??;
+
+    let Some((songs, summary)) = album else {
+        let no_songs_embed = serenity::CreateEmbed::default()
+            .title("Error")
+            .color(Color::RED)
+            .description(format!("No songs found for {}.", album_name));
+
+        ctx.send(poise::CreateReply::default().embed(no_songs_embed))
+            .await?;
+
+        return Ok(());
+    };
+
+    let album_embed = serenity::CreateEmbed::default()
+        .title(album_name)
+        .color(Color::BLUE)
+        .field("Songs", songs.to_string(), true);
+
+    ctx.send(poise::CreateReply::default().embed(summary.add_fields(album_embed)))
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,1045 @@
+use std::{collections::HashSet, sync::Arc, time::Duration};
+
+use poise::serenity_prelude::{self as serenity, Color};
+use rusqlite::{Connection, OptionalExtension, params};
+use tokio::sync::Mutex;
+
+use crate::{Context, Data, Error, sampling};
+
+/// Prefix of the custom ID of vote buttons, followed by `<match id>:<a|b>`
+const VOTE_PREFIX: &str = "bracket-vote:";
+/// How often the scheduler checks for rounds whose voting time is up
+const SCHEDULER_INTERVAL: Duration = Duration::from_secs(30);
+/// Seconds a new round gets to be posted before the scheduler posts its missing matches again
+const REPOST_AFTER: i64 = 120;
+
+#[derive(Debug, Clone, Copy, poise::ChoiceParameter)]
+pub enum BracketSize {
+    #[name = "8"]
+    Eight,
+    #[name = "16"]
+    Sixteen,
+    #[name = "32"]
+    ThirtyTwo,
+    #[name = "64"]
+    SixtyFour,
+}
+
+impl BracketSize {
+    fn songs(self) -> usize {
+        match self {
+            BracketSize::Eight => 8,
+            BracketSize::Sixteen => 16,
+            BracketSize::ThirtyTwo => 32,
+            BracketSize::SixtyFour => 64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, poise::ChoiceParameter)]
+pub enum Seeding {
+    #[name = "Random"]
+    Random,
+    #[name = "By album"]
+    Album,
+    #[name = "Hand-picked"]
+    HandPicked,
+}
+
+struct Matchup {
+    id: i64,
+    position: i64,
+    song_a: i32,
+    song_b: i32,
+    /// Seeds of the songs, 1 being the best
+    seed_a: i64,
+    seed_b: i64,
+    title_a: String,
+    title_b: String,
+    message_id: Option<i64>,
+    votes_a: u32,
+    votes_b: u32,
+    winner: Option<i32>,
+}
+
+/// ID, first round and voting deadline of a newly created bracket
+type CreatedBracket = (i64, Vec<Matchup>, i64);
+
+/// What closing a round changed, so the messages can be updated afterwards
+struct ClosedRound {
+    bracket_id: i64,
+    channel_id: u64,
+    name: String,
+    closed: Vec<Matchup>,
+    next_round: Vec<Matchup>,
+    round_ends_at: i64,
+    champion: Option<String>,
+}
+
+/// Channel, name, matches and voting deadline of a round that still has matches to post
+type UnpostedRound = (u64, String, Vec<Matchup>, i64);
+
+/// State of the current round of an active bracket, as seen by the scheduler
+struct ActiveRound {
+    guild_id: i64,
+    bracket_id: i64,
+    round: i64,
+    /// The voting time is up
+    due: bool,
+    /// Some matches have no message yet
+    unposted: bool,
+    /// The round started long enough ago that its posting must have failed
+    stale: bool,
+}
+
+/// Display name of a round, based on how many matches it has
+fn round_name(matches: usize) -> String {
+    match matches {
+        1 => "Final".to_string(),
+        2 => "Semifinals".to_string(),
+        4 => "Quarterfinals".to_string(),
+        _ => format!("Round of {}", matches * 2),
+    }
+}
+
+fn load_matches(
+    conn: &Connection,
+    bracket_id: i64,
+    round: i64,
+) -> Result<Vec<Matchup>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT m.id, m.position, m.song_a, m.song_b,
+            COALESCE(a.title, 'Deleted song'), COALESCE(b.title, 'Deleted song'),
+            m.message_id,
+            (SELECT COUNT(*) FROM bracket_votes v WHERE v.match_id = m.id AND v.song_id = m.song_a),
+            (SELECT COUNT(*) FROM bracket_votes v WHERE v.match_id = m.id AND v.song_id = m.song_b),
+            m.winner_song_id, m.seed_a, m.seed_b
+         FROM bracket_matches m
+         LEFT JOIN songs a ON a.id = m.song_a
+         LEFT JOIN songs b ON b.id = m.song_b
+         WHERE m.bracket_id = ?1 AND m.round = ?2
+         ORDER BY m.position",
+    )?;
+
+    let match_iter = stmt.query_map(params![bracket_id, round], |row| {
+        Ok(Matchup {
+            id: row.get(0)?,
+            position: row.get(1)?,
+            song_a: row.get(2)?,
+            song_b: row.get(3)?,
+            title_a: row.get(4)?,
+            title_b: row.get(5)?,
+            message_id: row.get(6)?,
+            votes_a: row.get(7)?,
+            votes_b: row.get(8)?,
+            winner: row.get(9)?,
+            seed_a: row.get(10)?,
+            seed_b: row.get(11)?,
+        })
+    })?;
+
+    match_iter.collect()
+}
+
+/// Orders songs given best seed first into first round slots, each with its seed.
+///
+/// The top seed meets the bottom seed, and the two best seeds can only meet in the final.
+fn first_round(seeds: &[i32]) -> Vec<(i32, i64)> {
+    let mut slots = vec![1];
+    while slots.len() < seeds.len() {
+        let size = slots.len() * 2 + 1;
+        slots = slots.iter().flat_map(|seed| [*seed, size - seed]).collect();
+    }
+
+    slots
+        .into_iter()
+        .map(|seed| (seeds[seed - 1], seed as i64))
+        .collect()
+}
+
+/// Inserts the matches of a round, pairing up `songs` and their seeds in order
+fn insert_round(
+    conn: &Connection,
+    bracket_id: i64,
+    round: i64,
+    songs: &[(i32, i64)],
+) -> Result<(), rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "INSERT INTO bracket_matches (bracket_id, round, position, song_a, song_b, seed_a, seed_b)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+    )?;
+
+    for (position, pair) in songs.chunks(2).enumerate() {
+        stmt.execute(params![
+            bracket_id,
+            round,
+            position as i64,
+            pair[0].0,
+            pair[1].0,
+            pair[0].1,
+            pair[1].1
+        ])?;
+    }
+
+    Ok(())
+}
+
+/// Decides the winners of the current round and sets up the next one, or finishes the bracket.
+///
+/// Returns `None` if the bracket isn't active or already moved past `round`.
+fn close_round_in_db(
+    conn: &Connection,
+    guild_id: i64,
+    bracket_id: i64,
+    round: i64,
+) -> Result<Option<ClosedRound>, rusqlite::Error> {
+    let bracket: Option<(u64, String, i64, i64)> = conn
+        .query_row(
+            "SELECT channel_id, name, round, round_minutes FROM brackets
+             WHERE id = ?1 AND guild_id = ?2 AND status = 'active'",
+            params![bracket_id, guild_id],
+            |row| {
+                Ok((
+                    row.get::<_, i64>(0)? as u64,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                ))
+            },
+        )
+        .optional()?;
+
+    let Some((channel_id, name, current_round, round_minutes)) = bracket else {
+        return Ok(None);
+    };
+    if current_round != round {
+        return Ok(None);
+    }
+
+    let mut closed = load_matches(conn, bracket_id, round)?;
+
+    let mut winners = Vec::new();
+    for matchup in &mut closed {
+        // Ties go to the better seed
+        let winner = if (matchup.votes_b, -matchup.seed_b) > (matchup.votes_a, -matchup.seed_a) {
+            (matchup.song_b, matchup.seed_b)
+        } else {
+            (matchup.song_a, matchup.seed_a)
+        };
+        matchup.winner = Some(winner.0);
+        conn.execute(
+            "UPDATE bracket_matches SET winner_song_id = ?2 WHERE id = ?1",
+            params![matchup.id, winner.0],
+        )?;
+        winners.push(winner);
+    }
+
+    if winners.len() == 1 {
+        conn.execute(
+            "UPDATE brackets SET status = 'finished', winner_song_id = ?2 WHERE id = ?1",
+            params![bracket_id, winners[0].0],
+        )?;
+
+        let champion = closed
+            .first()
+            .map(|matchup| {
+                if matchup.winner == Some(matchup.song_a) {
+                    matchup.title_a.clone()
+                } else {
+                    matchup.title_b.clone()
+                }
+            })
+            .unwrap_or_default();
+
+        return Ok(Some(ClosedRound {
+            bracket_id,
+            channel_id,
+            name,
+            closed,
+            next_round: Vec::new(),
+            round_ends_at: 0,
+            champion: Some(champion),
+        }));
+    }
+
+    insert_round(conn, bracket_id, round + 1, &winners)?;
+    let round_ends_at: i64 = conn.query_row(
+        "UPDATE brackets SET round = ?2, round_ends_at = unixepoch() + ?3 * 60
+         WHERE id = ?1 RETURNING round_ends_at",
+        params![bracket_id, round + 1, round_minutes],
+        |row| row.get(0),
+    )?;
+
+    let next_round = load_matches(conn, bracket_id, round + 1)?;
+
+    Ok(Some(ClosedRound {
+        bracket_id,
+        channel_id,
+        name,
+        closed,
+        next_round,
+        round_ends_at,
+        champion: None,
+    }))
+}
+
+fn matchup_embed(
+    bracket_name: &str,
+    matchup: &Matchup,
+    round_matches: usize,
+    round_ends_at: i64,
+) -> serenity::CreateEmbed {
+    serenity::CreateEmbed::new()
+        .title(format!(
+            "{} — {}, Match {}",
+            bracket_name,
+            round_name(round_matches),
+            matchup.position + 1
+        ))
+        .color(Color::ORANGE)
+        .description(format!(
+            "**{}**\nvs\n**{}**\n\nVoting closes <t:{}:R>.",
+            matchup.title_a, matchup.title_b, round_ends_at
+        ))
+}
+
+/// Vote buttons for a matchup; disabled ones show the final counts
+fn matchup_buttons(matchup: &Matchup, closed: bool) -> Vec<serenity::CreateActionRow> {
+    let button = |side: &str, song_id: i32, title: &str, votes: u32| {
+        let label = if closed {
+            format!("{} ({})", title, votes)
+        } else {
+            title.to_string()
+        };
+        let style = if closed && matchup.winner == Some(song_id) {
+            serenity::ButtonStyle::Success
+        } else {
+            serenity::ButtonStyle::Primary
+        };
+
+        serenity::CreateButton::new(format!("{}{}:{}", VOTE_PREFIX, matchup.id, side))
+            .label(label.chars().take(80).collect::<String>())
+            .style(style)
+            .disabled(closed)
+    };
+
+    vec![serenity::CreateActionRow::Buttons(vec![
+        button("a", matchup.song_a, &matchup.title_a, matchup.votes_a),
+        button("b", matchup.song_b, &matchup.title_b, matchup.votes_b),
+    ])]
+}
+
+/// Posts the matches of a round that don't have a message yet and remembers their messages.
+///
+/// Matches are marked as posted one by one, so after a failure the scheduler only posts
+/// the rest.
+async fn post_round(
+    http: &serenity::Http,
+    db: &Arc<Mutex<Connection>>,
+    channel_id: u64,
+    bracket_name: &str,
+    matches: &[Matchup],
+    round_ends_at: i64,
+) -> Result<(), Error> {
+    let channel_id = serenity::ChannelId::new(channel_id);
+
+    for matchup in matches
+        .iter()
+        .filter(|matchup| matchup.message_id.is_none())
+    {
+        let message = channel_id
+            .send_message(
+                http,
+                serenity::CreateMessage::new()
+                    .embed(matchup_embed(
+                        bracket_name,
+                        matchup,
+                        matches.len(),
+                        round_ends_at,
+                    ))
+                    .components(matchup_buttons(matchup, false)),
+            )
+            .await?;
+
+        let db = db.clone();
+        let match_id = matchup.id;
+        let message_id = message.id.get() as i64;
+        tokio::task::spawn_blocking(move || -> Result<usize, rusqlite::Error> {
+            let db_lock = db.blocking_lock();
+            db_lock.execute(
+                "UPDATE bracket_matches SET message_id = ?2 WHERE id = ?1",
+                params![match_id, message_id],
+            )
+        })
+        .await??;
+    }
+
+    Ok(())
+}
+
+/// Renders every round of a bracket as text
+fn render_bracket(conn: &Connection, bracket_id: i64) -> Result<String, rusqlite::Error> {
+    let rounds: i64 = conn.query_row(
+        "SELECT COALESCE(MAX(round), 0) FROM bracket_matches WHERE bracket_id = ?1",
+        params![bracket_id],
+        |row| row.get(0),
+    )?;
+
+    let mut rendered = Vec::new();
+    for round in 1..=rounds {
+        let matches = load_matches(conn, bracket_id, round)?;
+        rendered.push(format!("**{}**", round_name(matches.len())));
+
+        for matchup in &matches {
+            let result = match matchup.winner {
+                Some(winner) if winner == matchup.song_a => format!(" → {}", matchup.title_a),
+                Some(_) => format!(" → {}", matchup.title_b),
+                None => " (voting)".to_string(),
+            };
+            rendered.push(format!(
+                "{} ({}) vs {} ({}){}",
+                matchup.title_a, matchup.votes_a, matchup.title_b, matchup.votes_b, result
+            ));
+        }
+
+        rendered.push(String::new());
+    }
+
+    Ok(rendered.join("\n"))
+}
+
+/// Embed for a rendered bracket, with the bracket as a text file when it's too long for it
+fn rendered_embed(
+    title: String,
+    rendered: String,
+) -> (serenity::CreateEmbed, Option<serenity::CreateAttachment>) {
+    let embed = serenity::CreateEmbed::new().title(title).color(Color::GOLD);
+
+    if rendered.chars().count() > 4000 {
+        (
+            embed.description("The full bracket is attached."),
+            Some(serenity::CreateAttachment::bytes(
+                rendered.replace("**", ""),
+                "bracket.txt",
+            )),
+        )
+    } else {
+        (embed.description(rendered), None)
+    }
+}
+
+/// Closes the current round of a bracket, then posts the next round or the champion
+async fn close_round(
+    http: &serenity::Http,
+    db: &Arc<Mutex<Connection>>,
+    guild_id: i64,
+    bracket_id: i64,
+    round: i64,
+) -> Result<bool, Error> {
+    let db_cloned = db.clone();
+    let closed = tokio::task::spawn_blocking(move || {
+        let db_lock = db_cloned.blocking_lock();
+
+        let tx = db_lock.unchecked_transaction()?;
+        let closed = close_round_in_db(&tx, guild_id, bracket_id, round)?;
+        tx.commit()?;
+
+        Ok::<_, rusqlite::Error>(closed)
+    })
+    .await??;
+
+    let Some(closed) = closed else {
+        return Ok(false);
+    };
+
+    let channel_id = serenity::ChannelId::new(closed.channel_id);
+
+    for matchup in &closed.closed {
+        let Some(message_id) = matchup.message_id else {
+            continue;
+        };
+
+        let edit = serenity::EditMessage::new().components(matchup_buttons(matchup, true));
+        if let Err(err) = channel_id
+            .edit_message(http, serenity::MessageId::new(message_id as u64), edit)
+            .await
+        {
+            eprintln!("Error while closing bracket match {}: {}", matchup.id, err);
+        }
+    }
+
+    match &closed.champion {
+        Some(champion) => {
+            let db_cloned = db.clone();
+            let rendered = tokio::task::spawn_blocking(move || {
+                let db_lock = db_cloned.blocking_lock();
+                render_bracket(&db_lock, closed.bracket_id)
+            })
+            .await??;
+
+            let (embed, attachment) =
+                rendered_embed(format!("🏆 {} wins {}!", champion, closed.name), rendered);
+
+            let mut message = serenity::CreateMessage::new().embed(embed);
+            if let Some(attachment) = attachment {
+                message = message.add_file(attachment);
+            }
+            channel_id.send_message(http, message).await?;
+        }
+        None => {
+            channel_id
+                .say(
+                    http,
+                    format!(
+                        "**{}**: voting for the {} is open!",
+                        closed.name,
+                        round_name(closed.next_round.len())
+                    ),
+                )
+                .await?;
+
+            post_round(
+                http,
+                db,
+                closed.channel_id,
+                &closed.name,
+                &closed.next_round,
+                closed.round_ends_at,
+            )
+            .await?;
+        }
+    }
+
+    Ok(true)
+}
+
+/// Posts the matches of the current round that are still missing a message.
+///
+/// Members couldn't vote on those matches, so a round whose voting time already ran out
+/// gets a fresh one.
+async fn repost_round(
+    http: &serenity::Http,
+    db: &Arc<Mutex<Connection>>,
+    bracket_id: i64,
+    round: i64,
+) -> Result<(), Error> {
+    let db_cloned = db.clone();
+    let unposted =
+        tokio::task::spawn_blocking(move || -> Result<Option<UnpostedRound>, rusqlite::Error> {
+            let db_lock = db_cloned.blocking_lock();
+
+            let bracket: Option<(i64, String, i64)> = db_lock
+                .query_row(
+                    "UPDATE brackets SET round_ends_at = CASE
+                        WHEN round_ends_at <= unixepoch() THEN unixepoch() + round_minutes * 60
+                        ELSE round_ends_at
+                     END
+                     WHERE id = ?1 AND round = ?2 AND status = 'active'
+                     RETURNING channel_id, name, round_ends_at",
+                    params![bracket_id, round],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+                )
+                .optional()?;
+
+            let Some((channel_id, name, round_ends_at)) = bracket else {
+                return Ok(None);
+            };
+            let matches = load_matches(&db_lock, bracket_id, round)?;
+
+            Ok(Some((channel_id as u64, name, matches, round_ends_at)))
+        })
+        .await??;
+
+    let Some((channel_id, name, matches, round_ends_at)) = unposted else {
+        return Ok(());
+    };
+
+    post_round(http, db, channel_id, &name, &matches, round_ends_at).await
+}
+
+/// Background task that closes rounds once their voting time is up.
+///
+/// Brackets live in the database, so rounds that ran out while the bot was offline are
+/// closed right after a restart.
+pub async fn scheduler(http: Arc<serenity::Http>, db: Arc<Mutex<Connection>>) {
+    let mut interval = tokio::time::interval(SCHEDULER_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let db_cloned = db.clone();
+        let active =
+            tokio::task::spawn_blocking(move || -> Result<Vec<ActiveRound>, rusqlite::Error> {
+                let db_lock = db_cloned.blocking_lock();
+
+                let mut stmt = db_lock.prepare(
+                    "SELECT guild_id, id, round, round_ends_at <= unixepoch(),
+                        EXISTS (
+                            SELECT 1 FROM bracket_matches m
+                            WHERE m.bracket_id = brackets.id AND m.round = brackets.round
+                                AND m.message_id IS NULL
+                        ),
+                        round_ends_at - round_minutes * 60 <= unixepoch() - ?1
+                     FROM brackets WHERE status = 'active'",
+                )?;
+                stmt.query_map(params![REPOST_AFTER], |row| {
+                    Ok(ActiveRound {
+                        guild_id: row.get(0)?,
+                        bracket_id: row.get(1)?,
+                        round: row.get(2)?,
+                        due: row.get(3)?,
+                        unposted: row.get(4)?,
+                        stale: row.get(5)?,
+                    })
+                })?
+                .collect()
+            })
+            .await;
+
+        let active = match active {
+            Ok(Ok(active)) => active,
+            Ok(Err(err)) => {
+                eprintln!("Error while checking bracket rounds: {}", err);
+                continue;
+            }
+            Err(err) => {
+                eprintln!("Bracket scheduler task failed: {}", err);
+                continue;
+            }
+        };
+
+        for active_round in active {
+            // A round nobody could vote on yet is posted again instead of being closed 0-0.
+            // Fresh rounds are left alone while they're still being posted.
+            let result = if active_round.unposted {
+                if !active_round.stale {
+                    continue;
+                }
+                repost_round(&http, &db, active_round.bracket_id, active_round.round).await
+            } else if active_round.due {
+                close_round(
+                    &http,
+                    &db,
+                    active_round.guild_id,
+                    active_round.bracket_id,
+                    active_round.round,
+                )
+                .await
+                .map(|_| ())
+            } else {
+                continue;
+            };
+
+            if let Err(err) = result {
+                eprintln!(
+                    "Error while updating round of bracket {}: {}",
+                    active_round.bracket_id, err
+                );
+            }
+        }
+    }
+}
+
+/// Records a vote from a matchup button; votes can be changed until the round closes
+pub async fn handle_vote(
+    ctx: &serenity::Context,
+    interaction: &serenity::ComponentInteraction,
+    data: &Data,
+) -> Result<(), Error> {
+    let Some((match_id, side)) = interaction
+        .data
+        .custom_id
+        .strip_prefix(VOTE_PREFIX)
+        .and_then(|vote| vote.split_once(':'))
+    else {
+        return Ok(());
+    };
+    let Ok(match_id) = match_id.parse::<i64>() else {
+        return Ok(());
+    };
+    let side_b = side == "b";
+    let user_id = interaction.user.id.get() as i64;
+
+    let db = data.db.clone();
+    let voted_for =
+        tokio::task::spawn_blocking(move || -> Result<Option<String>, rusqlite::Error> {
+            let db_lock = db.blocking_lock();
+
+            // Only matches of the current round of an active bracket take votes
+            let song: Option<(i32, String)> = db_lock
+                .query_row(
+                    "SELECT CASE WHEN ?2 THEN m.song_b ELSE m.song_a END,
+                    COALESCE(s.title, 'Deleted song')
+                 FROM bracket_matches m
+                 JOIN brackets b ON b.id = m.bracket_id
+                 LEFT JOIN songs s ON s.id = CASE WHEN ?2 THEN m.song_b ELSE m.song_a END
+                 WHERE m.id = ?1 AND b.status = 'active' AND b.round = m.round
+                    AND m.winner_song_id IS NULL",
+                    params![match_id, side_b],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .optional()?;
+
+            let Some((song_id, title)) = song else {
+                return Ok(None);
+            };
+
+            db_lock.execute(
+                "INSERT INTO bracket_votes (match_id, user_id, song_id) VALUES (?1, ?2, ?3)
+             ON CONFLICT(match_id, user_id) DO UPDATE SET song_id = excluded.song_id",
+                params![match_id, user_id, song_id],
+            )?;
+
+            Ok(Some(title))
+        })
+        .await??;
+
+    let content = match voted_for {
+        Some(title) => format!(
+            "You voted for **{}**. You can change your vote until the round closes.",
+            title
+        ),
+        None => "Voting for this match has closed.".to_string(),
+    };
+
+    interaction
+        .create_response(
+            ctx,
+            serenity::CreateInteractionResponse::Message(
+                serenity::CreateInteractionResponseMessage::new()
+                    .content(content)
+                    .ephemeral(true),
+            ),
+        )
+        .await?;
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    subcommands("create", "close_round_command", "show"),
+    subcommand_required
+)]
+
+/// Run a song bracket tournament
+pub async fn bracket(_: Context<'_>) -> Result<(), Error> {
+    // This will never be called, because `subcommand_required` parameter is set
+    Ok(())
+}
+
+/// Create a bracket and post the first round in this channel
+#[poise::command(
+    prefix_command,
+    slash_command,
+    check = "crate::checks::check_is_moderator"
+)]
+#[allow(clippy::too_many_arguments)]
+pub async fn create(
+    ctx: Context<'_>,
+    #[description = "Name of the bracket"] name: String,
+    #[description = "Amount of songs in the bracket"] size: BracketSize,
+    #[description = "How songs are picked (random by default)"] seeding: Option<Seeding>,
+    #[autocomplete = "crate::commands::new::autocomplete_album"]
+    #[description = "Album to pick songs from when seeding by album"]
+    album: Option<String>,
+    #[description = "Comma-separated song IDs in seed order when hand-picking"] songs: Option<
+        String,
+    >,
+    #[description = "How long each round is open for voting, in minutes (1 day by default)"]
+    round_minutes: Option<u32>,
+) -> Result<(), Error> {
+    let seeding = seeding.unwrap_or(Seeding::Random);
+    let size = size.songs();
+    let round_minutes = i64::from(round_minutes.unwrap_or(24 * 60).max(1));
+
+    let hand_picked: Vec<i32> = match (&seeding, &songs) {
+        (Seeding::HandPicked, Some(songs)) => {
+            match songs
+                .split(',')
+                .map(|id| id.trim().parse::<i32>())
+                .collect::<Result<Vec<_>, _>>()
+            {
+                Ok(ids) => ids,
+                Err(_) => {
+                    let invalid_songs_embed = serenity::CreateEmbed::new()
+                        .title("Error")
+                        .color(Color::RED)
+                        .description("`songs` must be a comma-separated list of song IDs.");
+                    ctx.send(poise::CreateReply::default().embed(invalid_songs_embed))
+                        .await?;
+                    return Ok(());
+                }
+            }
+        }
+        _ => Vec::new(),
+    };
+
+    let seeding_error = match seeding {
+        Seeding::HandPicked if hand_picked.len() != size => Some(format!(
+            "Hand-picking needs exactly {} song IDs in `songs`.",
+            size
+        )),
+        Seeding::HandPicked if hand_picked.iter().collect::<HashSet<_>>().len() != size => {
+            Some("Each song can only be in the bracket once.".to_string())
+        }
+        Seeding::Album if album.is_none() => Some("Seeding by album needs an `album`.".to_string()),
+        _ => None,
+    };
+
+    if let Some(seeding_error) = seeding_error {
+        let seeding_error_embed = serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description(seeding_error);
+        ctx.send(poise::CreateReply::default().embed(seeding_error_embed))
+            .await?;
+        return Ok(());
+    }
+
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get() as i64);
+    let channel_id = ctx.channel_id().get();
+    let db = ctx.data().db.clone();
+    let song_ids = ctx.data().song_ids.clone();
+    let name_cloned = name.clone();
+
+    let created = tokio::task::spawn_blocking(
+        move || -> Result<Result<CreatedBracket, String>, rusqlite::Error> {
+            let db_lock = db.blocking_lock();
+
+            let seeds = match seeding {
+                Seeding::HandPicked => {
+                    let mut stmt = db_lock.prepare("SELECT COUNT(*) FROM songs WHERE id = ?1")?;
+                    for id in &hand_picked {
+                        if stmt.query_row(params![id], |row| row.get::<_, i64>(0))? == 0 {
+                            return Ok(Err(format!("No song found with ID: {}", id)));
+                        }
+                    }
+                    hand_picked
+                }
+                Seeding::Random | Seeding::Album => {
+                    let ids = song_ids.get(&db_lock, album.as_deref().unwrap_or(""))?;
+                    sampling::uniform_sample(&ids, size, &HashSet::new(), &mut rand::thread_rng())
+                }
+            };
+
+            if seeds.len() < size {
+                return Ok(Err(format!(
+                    "Not enough songs for a bracket of {}; only {} available.",
+                    size,
+                    seeds.len()
+                )));
+            }
+
+            let (bracket_id, round_ends_at): (i64, i64) = db_lock.query_row(
+                "INSERT INTO brackets
+                    (guild_id, channel_id, name, size, round, round_minutes, round_ends_at, status)
+                 VALUES (?1, ?2, ?3, ?4, 1, ?5, unixepoch() + ?5 * 60, 'active')
+                 RETURNING id, round_ends_at",
+                params![
+                    guild_id,
+                    channel_id as i64,
+                    name_cloned,
+                    size as i64,
+                    round_minutes
+                ],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?;
+
+            insert_round(&db_lock, bracket_id, 1, &first_round(&seeds))?;
+            let matches = load_matches(&db_lock, bracket_id, 1)?;
+
+            Ok(Ok((bracket_id, matches, round_ends_at)))
+        },
+    )
+    .await??;
+
+    let (bracket_id, matches, round_ends_at) = match created {
+        Ok(created) => created,
+        Err(message) => {
+            let create_fail_embed = serenity::CreateEmbed::new()
+                .title("Error")
+                .color(Color::RED)
+                .description(message);
+            ctx.send(poise::CreateReply::default().embed(create_fail_embed))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let created_embed = serenity::CreateEmbed::new()
+        .title(name.clone())
+        .color(Color::ORANGE)
+        .description(format!(
+            "A {}-song bracket has started! Vote on each match below; the {} closes <t:{}:R>.",
+            size,
+            round_name(matches.len()),
+            round_ends_at
+        ))
+        .footer(serenity::CreateEmbedFooter::new(format!(
+            "Bracket ID: {}",
+            bracket_id
+        )));
+    ctx.send(poise::CreateReply::default().embed(created_embed))
+        .await?;
+
+    post_round(
+        ctx.http(),
+        &ctx.data().db,
+        channel_id,
+        &name,
+        &matches,
+        round_ends_at,
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Close the current round of a bracket right away
+#[poise::command(
+    prefix_command,
+    slash_command,
+    rename = "close-round",
+    check = "crate::checks::check_is_moderator"
+)]
+pub async fn close_round_command(
+    ctx: Context<'_>,
+    #[description = "The ID of the bracket"] bracket_id: i64,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get() as i64);
+    let db = ctx.data().db.clone();
+    let round = tokio::task::spawn_blocking(move || -> Result<Option<i64>, rusqlite::Error> {
+        let db_lock = db.blocking_lock();
+        db_lock
+            .query_row(
+                "SELECT round FROM brackets WHERE id = ?1 AND guild_id = ?2 AND status = 'active'",
+                params![bracket_id, guild_id],
+                |row| row.get(0),
+            )
+            .optional()
+    })
+    .await??;
+
+    let closed = match round {
+        Some(round) => close_round(ctx.http(), &ctx.data().db, guild_id, bracket_id, round).await?,
+        None => false,
+    };
+
+    let embed = if closed {
+        serenity::CreateEmbed::new()
+            .title("Success")
+            .color(Color::DARK_GREEN)
+            .description(format!("Closed the round of bracket {}.", bracket_id))
+    } else {
+        serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description(format!("No active bracket found with ID: {}", bracket_id))
+    };
+
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+
+    Ok(())
+}
+
+/// Show the current state of a bracket
+#[poise::command(prefix_command, slash_command)]
+pub async fn show(
+    ctx: Context<'_>,
+    #[description = "The ID of the bracket (the latest one by default)"] bracket_id: Option<i64>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get() as i64);
+    let db = ctx.data().db.clone();
+
+    let rendered = tokio::task::spawn_blocking(
+        move || -> Result<Option<(String, Option<u64>, String)>, rusqlite::Error> {
+            let db_lock = db.blocking_lock();
+
+            let bracket: Option<(i64, String, Option<i64>)> = db_lock
+                .query_row(
+                    "SELECT id, name, winner_song_id FROM brackets
+                     WHERE guild_id = ?1 AND (?2 IS NULL OR id = ?2)
+                     ORDER BY id DESC LIMIT 1",
+                    params![guild_id, bracket_id],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+                )
+                .optional()?;
+
+            let Some((bracket_id, name, winner)) = bracket else {
+                return Ok(None);
+            };
+
+            Ok(Some((
+                name,
+                winner.map(|winner| winner as u64),
+                render_bracket(&db_lock, bracket_id)?,
+            )))
+        },
+    )
+    .await??;
+
+    let Some((name, winner, rendered)) = rendered else {
+        let no_bracket_embed = serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description("No bracket found.");
+        ctx.send(poise::CreateReply::default().embed(no_bracket_embed))
+            .await?;
+        return Ok(());
+    };
+
+    let title = if winner.is_some() {
+        format!("{} (finished)", name)
+    } else {
+        name
+    };
+
+    let (embed, attachment) = rendered_embed(title, rendered);
+
+    let mut reply = poise::CreateReply::default().embed(embed);
+    if let Some(attachment) = attachment {
+        reply = reply.attachment(attachment);
+    }
+    ctx.send(reply).await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_round_pairs_the_top_seed_with_the_bottom_seed() {
+        let seeds: Vec<i32> = (101..=108).collect();
+
+        assert_eq!(
+            first_round(&seeds),
+            vec![
+                (101, 1),
+                (108, 8),
+                (104, 4),
+                (105, 5),
+                (102, 2),
+                (107, 7),
+                (103, 3),
+                (106, 6)
+            ]
+        );
+    }
+
+    #[test]
+    fn every_song_gets_a_first_round_slot() {
+        let seeds: Vec<i32> = (1..=64).collect();
+        let mut placed: Vec<i32> = first_round(&seeds)
+            .into_iter()
+            .map(|(song, _)| song)
+            .collect();
+        placed.sort_unstable();
+
+        assert_eq!(placed, seeds);
+    }
+}
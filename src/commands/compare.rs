@@ -0,0 +1,344 @@
+use std::collections::{HashMap, HashSet};
+
+use poise::serenity_prelude::{self as serenity, Color};
+use rusqlite::{Connection, params};
+
+use crate::{Context, Error};
+
+/// Songs two members need to have both rated before their ratings are compared
+const MIN_SHARED_RATINGS: usize = 2;
+/// Midpoint of the 1–10 scale; ratings are centered on it so cosine similarity can go negative
+const NEUTRAL_SCORE: f64 = 5.5;
+/// Score at which a rated song counts as loved
+const LOVED_SCORE: u8 = 8;
+/// Rating gap that counts as a disagreement
+const MIN_DISAGREEMENT: u8 = 3;
+/// Entries shown per list
+const MAX_LISTED: usize = 5;
+
+/// A member's ratings and favorites, keyed by song ID
+struct Taste {
+    ratings: HashMap<i32, u8>,
+    favorites: HashSet<i32>,
+}
+
+fn load_taste(conn: &Connection, user_id: i64) -> Result<Taste, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT r.song_id, r.score FROM ratings r
+         JOIN songs s ON s.id = r.song_id
+         WHERE r.user_id = ?1",
+    )?;
+    let ratings = stmt
+        .query_map(params![user_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<_, _>>()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT f.song_id FROM favorites f
+         JOIN songs s ON s.id = f.song_id
+         WHERE f.user_id = ?1",
+    )?;
+    let favorites = stmt
+        .query_map(params![user_id], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+
+    Ok(Taste { ratings, favorites })
+}
+
+/// Cosine similarity of the centered ratings of songs both members rated, from -1 to 1
+fn rating_similarity(a: &Taste, b: &Taste) -> Option<f64> {
+    let shared: Vec<(f64, f64)> = a
+        .ratings
+        .iter()
+        .filter_map(|(song_id, score_a)| {
+            b.ratings.get(song_id).map(|score_b| {
+                (
+                    f64::from(*score_a) - NEUTRAL_SCORE,
+                    f64::from(*score_b) - NEUTRAL_SCORE,
+                )
+            })
+        })
+        .collect();
+
+    if shared.len() < MIN_SHARED_RATINGS {
+        return None;
+    }
+
+    let dot: f64 = shared.iter().map(|(a, b)| a * b).sum();
+    let norm_a = shared.iter().map(|(a, _)| a * a).sum::<f64>().sqrt();
+    let norm_b = shared.iter().map(|(_, b)| b * b).sum::<f64>().sqrt();
+
+    Some(dot / (norm_a * norm_b))
+}
+
+/// Jaccard index of two members' favorites, from 0 to 1
+fn favorite_similarity(a: &Taste, b: &Taste) -> Option<f64> {
+    let union = a.favorites.union(&b.favorites).count();
+    if union == 0 {
+        return None;
+    }
+
+    Some(a.favorites.intersection(&b.favorites).count() as f64 / union as f64)
+}
+
+/// Songs `from` loves (favorited or rated highly) that `to` hasn't rated or favorited, best first
+fn recommendations(from: &Taste, to: &Taste) -> Vec<i32> {
+    let mut loved: Vec<(i32, u8)> = from
+        .ratings
+        .iter()
+        .filter(|(_, score)| **score >= LOVED_SCORE)
+        .map(|(song_id, score)| (*song_id, *score))
+        .chain(
+            from.favorites
+                .iter()
+                .filter(|song_id| !from.ratings.contains_key(song_id))
+                .map(|song_id| (*song_id, LOVED_SCORE)),
+        )
+        .filter(|(song_id, _)| !to.ratings.contains_key(song_id) && !to.favorites.contains(song_id))
+        .collect();
+
+    // Favorites that are also rated highly come first
+    loved.sort_by_key(|(song_id, score)| {
+        (
+            std::cmp::Reverse(*score),
+            !from.favorites.contains(song_id),
+            *song_id,
+        )
+    });
+
+    loved
+        .into_iter()
+        .map(|(song_id, _)| song_id)
+        .take(MAX_LISTED)
+        .collect()
+}
+
+/// Titles of the given songs in the same order
+fn song_titles(conn: &Connection, song_ids: &[i32]) -> Result<Vec<String>, rusqlite::Error> {
+    let mut stmt = conn.prepare("SELECT title FROM songs WHERE id = ?1")?;
+
+    song_ids
+        .iter()
+        .map(|song_id| stmt.query_row(params![song_id], |row| row.get(0)))
+        .collect()
+}
+
+struct Comparison {
+    ratings: Option<f64>,
+    favorites: Option<f64>,
+    shared_ratings: usize,
+    shared_favorites: Vec<String>,
+    /// Title with the score from each member
+    disagreements: Vec<(String, u8, u8)>,
+    for_a: Vec<String>,
+    for_b: Vec<String>,
+}
+
+fn compare_tastes(
+    conn: &Connection,
+    user_a: i64,
+    user_b: i64,
+) -> Result<Comparison, rusqlite::Error> {
+    let a = load_taste(conn, user_a)?;
+    let b = load_taste(conn, user_b)?;
+
+    let mut shared_favorites: Vec<i32> = a.favorites.intersection(&b.favorites).copied().collect();
+    shared_favorites.sort_unstable();
+    shared_favorites.truncate(MAX_LISTED);
+
+    let mut disagreements: Vec<(i32, u8, u8)> = a
+        .ratings
+        .iter()
+        .filter_map(|(song_id, score_a)| {
+            b.ratings
+                .get(song_id)
+                .map(|score_b| (*song_id, *score_a, *score_b))
+        })
+        .filter(|(_, score_a, score_b)| score_a.abs_diff(*score_b) >= MIN_DISAGREEMENT)
+        .collect();
+    disagreements.sort_by_key(|(song_id, score_a, score_b)| {
+        (std::cmp::Reverse(score_a.abs_diff(*score_b)), *song_id)
+    });
+    disagreements.truncate(MAX_LISTED);
+
+    let disagreement_ids: Vec<i32> = disagreements.iter().map(|(id, _, _)| *id).collect();
+
+    Ok(Comparison {
+        ratings: rating_similarity(&a, &b),
+        favorites: favorite_similarity(&a, &b),
+        shared_ratings: a
+            .ratings
+            .keys()
+            .filter(|song_id| b.ratings.contains_key(song_id))
+            .count(),
+        shared_favorites: song_titles(conn, &shared_favorites)?,
+        disagreements: song_titles(conn, &disagreement_ids)?
+            .into_iter()
+            .zip(&disagreements)
+            .map(|(title, (_, score_a, score_b))| (title, *score_a, *score_b))
+            .collect(),
+        for_a: song_titles(conn, &recommendations(&b, &a))?,
+        for_b: song_titles(conn, &recommendations(&a, &b))?,
+    })
+}
+
+fn bullet_list(items: &[String], empty: &str) -> String {
+    if items.is_empty() {
+        empty.to_string()
+    } else {
+        items
+            .iter()
+            .map(|item| format!("• {}", item))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Compare the music taste of two members
+#[poise::command(prefix_command, slash_command)]
+pub async fn compare(
+    ctx: Context<'_>,
+    #[description = "The member to compare"] user_a: serenity::User,
+    #[description = "Who to compare them with (you by default)"] user_b: Option<serenity::User>,
+) -> Result<(), Error> {
+    let user_b = user_b.as_ref().unwrap_or_else(|| ctx.author());
+
+    if user_a.id == user_b.id {
+        let same_user_embed = serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description("Pick two different members to compare.");
+        ctx.send(poise::CreateReply::default().embed(same_user_embed))
+            .await?;
+        return Ok(());
+    }
+
+    let (id_a, id_b) = (user_a.id.get() as i64, user_b.id.get() as i64);
+    let db = ctx.data().db.clone();
+
+    let comparison = tokio::task::spawn_blocking(move || {
+        let db_lock = db.blocking_lock();
+        compare_tastes(&db_lock, id_a, id_b)
+    })
+    .await??;
+
+    // Cosine similarity goes from -1 to 1, so map it onto 0–100% like the Jaccard index
+    let rating_match = comparison
+        .ratings
+        .map(|similarity| (similarity + 1.0) / 2.0);
+    let scores: Vec<f64> = [rating_match, comparison.favorites]
+        .into_iter()
+        .flatten()
+        .collect();
+
+    let overall = if scores.is_empty() {
+        "Not enough data yet. Rate songs with `/rate` and add favorites with `/fav add`."
+            .to_string()
+    } else {
+        format!(
+            "**{:.0}%** taste match",
+            scores.iter().sum::<f64>() / scores.len() as f64 * 100.0
+        )
+    };
+
+    let ratings = match rating_match {
+        Some(rating_match) => format!(
+            "{:.0}% across {} song(s)",
+            rating_match * 100.0,
+            comparison.shared_ratings
+        ),
+        None => format!("Needs at least {} songs rated by both", MIN_SHARED_RATINGS),
+    };
+    let favorites = match comparison.favorites {
+        Some(favorites) => format!("{:.0}% overlap", favorites * 100.0),
+        None => "No favorites yet".to_string(),
+    };
+
+    let disagreements = comparison
+        .disagreements
+        .iter()
+        .map(|(title, score_a, score_b)| format!("{} ({} vs {})", title, score_a, score_b))
+        .collect::<Vec<_>>();
+
+    let compare_embed = serenity::CreateEmbed::new()
+        .title(format!("{} vs {}", user_a.name, user_b.name))
+        .color(Color::FABLED_PINK)
+        .description(overall)
+        .field("Ratings", ratings, true)
+        .field("Favorites", favorites, true)
+        .field(
+            "Shared favorites",
+            bullet_list(&comparison.shared_favorites, "None"),
+            false,
+        )
+        .field(
+            "Biggest disagreements",
+            bullet_list(&disagreements, "None"),
+            false,
+        )
+        .field(
+            format!("{} might like", user_a.name),
+            bullet_list(&comparison.for_a, "Nothing new"),
+            true,
+        )
+        .field(
+            format!("{} might like", user_b.name),
+            bullet_list(&comparison.for_b, "Nothing new"),
+            true,
+        );
+
+    ctx.send(poise::CreateReply::default().embed(compare_embed))
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn taste(ratings: &[(i32, u8)], favorites: &[i32]) -> Taste {
+        Taste {
+            ratings: ratings.iter().copied().collect(),
+            favorites: favorites.iter().copied().collect(),
+        }
+    }
+
+    #[test]
+    fn identical_tastes_are_fully_similar() {
+        let a = taste(&[(1, 10), (2, 1), (3, 8)], &[1, 3]);
+        let b = taste(&[(1, 10), (2, 1), (3, 8)], &[1, 3]);
+
+        assert!((rating_similarity(&a, &b).unwrap() - 1.0).abs() < 1e-9);
+        assert_eq!(favorite_similarity(&a, &b), Some(1.0));
+    }
+
+    #[test]
+    fn opposite_ratings_are_fully_dissimilar() {
+        let a = taste(&[(1, 10), (2, 1), (3, 8)], &[1]);
+        let b = taste(&[(1, 1), (2, 10), (3, 3)], &[2]);
+
+        assert!((rating_similarity(&a, &b).unwrap() + 1.0).abs() < 1e-9);
+        assert_eq!(favorite_similarity(&a, &b), Some(0.0));
+    }
+
+    #[test]
+    fn disjoint_tastes_have_nothing_to_compare() {
+        let a = taste(&[(1, 10), (2, 1)], &[1]);
+        let b = taste(&[(3, 10), (4, 1)], &[3]);
+
+        assert_eq!(rating_similarity(&a, &b), None);
+        assert_eq!(favorite_similarity(&a, &b), Some(0.0));
+        assert_eq!(
+            favorite_similarity(&taste(&[], &[]), &taste(&[], &[])),
+            None
+        );
+    }
+
+    #[test]
+    fn recommendations_skip_songs_the_other_member_knows() {
+        let from = taste(&[(1, 9), (2, 10), (3, 4)], &[4, 5]);
+        let to = taste(&[(1, 2)], &[4]);
+
+        assert_eq!(recommendations(&from, &to), vec![2, 5]);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{Context, Error};
+use crate::{Context, Error, commands};
 use poise::serenity_prelude::{self as serenity, Color};
 use rusqlite::params;
 
@@ -17,14 +17,37 @@ pub async fn delete(
     let result = tokio::task::spawn_blocking(move || -> Result<usize, rusqlite::Error> {
         let db_lock = db.blocking_lock();
 
-        let mut stmt = db_lock.prepare("DELETE FROM songs WHERE id = ?1")?;
+        // Everything referencing the song goes with it, or nothing does
+        let tx = db_lock.unchecked_transaction()?;
 
-        let affected_rows = stmt.execute(params![song_id])?;
+        let affected_rows = tx.execute("DELETE FROM songs WHERE id = ?1", params![song_id])?;
+
+        tx.execute("DELETE FROM song_tags WHERE song_id = ?1", params![song_id])?;
+        tx.execute("DELETE FROM lyrics WHERE song_id = ?1", params![song_id])?;
+        tx.execute("DELETE FROM song_elo WHERE song_id = ?1", params![song_id])?;
+        tx.execute(
+            "DELETE FROM tier_entries WHERE song_id = ?1",
+            params![song_id],
+        )?;
+        tx.execute(
+            "DELETE FROM emoji_clues WHERE song_id = ?1",
+            params![song_id],
+        )?;
+        tx.execute("DELETE FROM favorites WHERE song_id = ?1", params![song_id])?;
+        tx.execute("DELETE FROM ratings WHERE song_id = ?1", params![song_id])?;
+        tx.execute("DELETE FROM listens WHERE song_id = ?1", params![song_id])?;
+        commands::queue::remove_song_requests(&tx, song_id)?;
+
+        tx.commit()?;
 
         Ok(affected_rows)
     })
     .await??;
 
+    if result > 0 {
+        ctx.data().song_ids.invalidate();
+    }
+
     // Check if a song was actually deleted
     if result == 0 {
         let embed = serenity::CreateEmbed::new()
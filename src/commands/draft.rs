@@ -0,0 +1,698 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+    time::Duration,
+};
+
+use futures::StreamExt;
+use poise::serenity_prelude::{self as serenity, Color, Mentionable};
+use rand::seq::SliceRandom;
+use rusqlite::{OptionalExtension, params};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::{
+    Context, Error,
+    points::{self, Game},
+    sampling,
+};
+
+/// How long the lobby stays open when the host doesn't start the draft
+const LOBBY_TIMEOUT: Duration = Duration::from_secs(120);
+/// How long members can vote on the best roster
+const VOTE_TIMEOUT: Duration = Duration::from_secs(120);
+const MAX_PLAYERS: usize = 10;
+const MAX_ROUNDS: u32 = 10;
+
+/// A `/draft pick` waiting to be accepted or rejected by the running draft
+struct PickRequest {
+    user_id: serenity::UserId,
+    song_id: i32,
+    reply: oneshot::Sender<Result<String, String>>,
+}
+
+struct DraftHandle {
+    picks: mpsc::Sender<PickRequest>,
+    album: Option<String>,
+    taken: HashSet<i32>,
+}
+
+/// Drafts in progress per channel, so `/draft pick` can reach the draft it belongs to
+#[derive(Default)]
+pub struct Drafts {
+    channels: Mutex<HashMap<serenity::ChannelId, DraftHandle>>,
+}
+
+/// Removes the channel's draft from [`Drafts`] when dropped
+struct DraftGuard<'a> {
+    drafts: &'a Drafts,
+    channel_id: serenity::ChannelId,
+}
+
+impl Drafts {
+    fn open(
+        &self,
+        channel_id: serenity::ChannelId,
+        album: Option<String>,
+    ) -> (DraftGuard<'_>, mpsc::Receiver<PickRequest>) {
+        let (picks, receiver) = mpsc::channel(8);
+
+        self.channels.lock().unwrap().insert(
+            channel_id,
+            DraftHandle {
+                picks,
+                album,
+                taken: HashSet::new(),
+            },
+        );
+
+        (
+            DraftGuard {
+                drafts: self,
+                channel_id,
+            },
+            receiver,
+        )
+    }
+
+    fn mark_taken(&self, channel_id: serenity::ChannelId, song_id: i32) {
+        if let Some(draft) = self.channels.lock().unwrap().get_mut(&channel_id) {
+            draft.taken.insert(song_id);
+        }
+    }
+}
+
+impl Drop for DraftGuard<'_> {
+    fn drop(&mut self) {
+        self.drafts
+            .channels
+            .lock()
+            .unwrap()
+            .remove(&self.channel_id);
+    }
+}
+
+/// Suggests songs that are still available in this channel's draft
+async fn autocomplete_available_song(
+    ctx: Context<'_>,
+    partial: &str,
+) -> Vec<serenity::AutocompleteChoice> {
+    let Some((album, taken)) = ctx
+        .data()
+        .drafts
+        .channels
+        .lock()
+        .unwrap()
+        .get(&ctx.channel_id())
+        .map(|draft| (draft.album.clone(), draft.taken.clone()))
+    else {
+        return Vec::new();
+    };
+
+    let db = ctx.data().db.clone();
+    let pattern = format!("%{}%", partial);
+
+    let songs = tokio::task::spawn_blocking(
+        move || -> Result<Vec<(i32, String, String)>, rusqlite::Error> {
+            let db_lock = db.blocking_lock();
+
+            let mut stmt = db_lock.prepare(
+                "SELECT id, title, album FROM songs
+                 WHERE title LIKE ?1 AND (?2 IS NULL OR album = ?2)
+                 ORDER BY title",
+            )?;
+            let song_iter = stmt.query_map(params![pattern, album], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })?;
+
+            song_iter.collect()
+        },
+    )
+    .await;
+
+    match songs {
+        Ok(Ok(songs)) => songs
+            .into_iter()
+            .filter(|(id, _, _)| !taken.contains(id))
+            .take(25)
+            .map(|(id, title, album)| {
+                let name = format!("{} ({})", title, album);
+                serenity::AutocompleteChoice::new(name.chars().take(100).collect::<String>(), id)
+            })
+            .collect(),
+        Ok(Err(err)) => {
+            eprintln!("Error while fetching songs: {}", err);
+            Vec::new()
+        }
+        Err(err) => {
+            eprintln!("Song autocomplete task failed: {}", err);
+            Vec::new()
+        }
+    }
+}
+
+/// Pick order for a round: forwards in odd rounds, backwards in even ones
+fn snake_order(players: &[serenity::UserId], round: usize) -> Vec<serenity::UserId> {
+    if round.is_multiple_of(2) {
+        players.to_vec()
+    } else {
+        players.iter().rev().copied().collect()
+    }
+}
+
+fn lobby_embed(
+    host: serenity::UserId,
+    players: &[serenity::UserId],
+    rounds: u32,
+) -> serenity::CreateEmbed {
+    let player_list = players
+        .iter()
+        .map(|player| player.mention().to_string())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    serenity::CreateEmbed::new()
+        .title("Song Draft Lobby")
+        .color(Color::BLURPLE)
+        .description(format!(
+            "{} round(s). Click **Join** to take part; {} starts the draft.\n\n**Players**\n{}",
+            rounds,
+            host.mention(),
+            player_list
+        ))
+}
+
+fn roster_list(roster: &[(i32, String)]) -> String {
+    roster
+        .iter()
+        .map(|(_, title)| title.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    subcommands("start", "pick"),
+    subcommand_required
+)]
+
+/// Draft songs against other members
+pub async fn draft(_: Context<'_>) -> Result<(), Error> {
+    // This will never be called, because `subcommand_required` parameter is set
+    Ok(())
+}
+
+/// Open a draft lobby in this channel
+#[poise::command(prefix_command, slash_command)]
+pub async fn start(
+    ctx: Context<'_>,
+    #[description = "Songs each player drafts (3 by default)"] rounds: Option<u32>,
+    #[autocomplete = "crate::commands::new::autocomplete_album"]
+    #[description = "Only draft songs from this album"]
+    album: Option<String>,
+    #[description = "Seconds per pick before a song is picked automatically (60 by default)"]
+    pick_seconds: Option<u64>,
+    #[description = "Let members vote on the best roster afterwards"] vote: Option<bool>,
+) -> Result<(), Error> {
+    let rounds = rounds.unwrap_or(3).clamp(1, MAX_ROUNDS);
+    let pick_timeout = Duration::from_secs(pick_seconds.unwrap_or(60).clamp(15, 300));
+
+    let _game = match ctx.data().active_games.start(ctx.channel_id(), "draft") {
+        Ok(game) => game,
+        Err(running) => {
+            let busy_embed = serenity::CreateEmbed::new()
+                .title("Error")
+                .color(Color::RED)
+                .description(format!("A game of {} is already running here.", running));
+            ctx.send(
+                poise::CreateReply::default()
+                    .embed(busy_embed)
+                    .ephemeral(true),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    let host = ctx.author().id;
+    let mut players = vec![host];
+
+    let join_id = format!("draft-join-{}", ctx.id());
+    let begin_id = format!("draft-begin-{}", ctx.id());
+    let lobby_buttons = |disabled: bool| {
+        vec![serenity::CreateActionRow::Buttons(vec![
+            serenity::CreateButton::new(&join_id)
+                .label("Join")
+                .style(serenity::ButtonStyle::Primary)
+                .disabled(disabled),
+            serenity::CreateButton::new(&begin_id)
+                .label("Start")
+                .style(serenity::ButtonStyle::Success)
+                .disabled(disabled),
+        ])]
+    };
+
+    let lobby = ctx
+        .send(
+            poise::CreateReply::default()
+                .embed(lobby_embed(host, &players, rounds))
+                .components(lobby_buttons(false)),
+        )
+        .await?;
+    let lobby_message = lobby.message().await?;
+
+    let mut interactions = serenity::ComponentInteractionCollector::new(ctx)
+        .message_id(lobby_message.id)
+        .timeout(LOBBY_TIMEOUT)
+        .stream();
+
+    while let Some(interaction) = interactions.next().await {
+        let content = if interaction.data.custom_id == join_id {
+            if players.contains(&interaction.user.id) {
+                Some("You already joined this draft.")
+            } else if players.len() >= MAX_PLAYERS {
+                Some("This draft is full.")
+            } else {
+                players.push(interaction.user.id);
+                None
+            }
+        } else if interaction.data.custom_id == begin_id {
+            if interaction.user.id == host {
+                interaction
+                    .create_response(ctx, serenity::CreateInteractionResponse::Acknowledge)
+                    .await?;
+                break;
+            }
+            Some("Only the host can start the draft.")
+        } else {
+            continue;
+        };
+
+        let response = match content {
+            Some(content) => serenity::CreateInteractionResponse::Message(
+                serenity::CreateInteractionResponseMessage::new()
+                    .content(content)
+                    .ephemeral(true),
+            ),
+            None => serenity::CreateInteractionResponse::UpdateMessage(
+                serenity::CreateInteractionResponseMessage::new()
+                    .embed(lobby_embed(host, &players, rounds)),
+            ),
+        };
+        interaction.create_response(ctx, response).await?;
+    }
+
+    lobby
+        .edit(
+            ctx,
+            poise::CreateReply::default()
+                .embed(lobby_embed(host, &players, rounds))
+                .components(lobby_buttons(true)),
+        )
+        .await?;
+
+    if players.len() < 2 {
+        let not_enough_embed = serenity::CreateEmbed::new()
+            .title("Draft Cancelled")
+            .color(Color::RED)
+            .description("A draft needs at least two players.");
+        ctx.channel_id()
+            .send_message(ctx, serenity::CreateMessage::new().embed(not_enough_embed))
+            .await?;
+        return Ok(());
+    }
+
+    let db = ctx.data().db.clone();
+    let song_ids = ctx.data().song_ids.clone();
+    let album_cloned = album.clone().unwrap_or_default();
+    let pool = tokio::task::spawn_blocking(move || {
+        let db_lock = db.blocking_lock();
+        song_ids.get(&db_lock, &album_cloned)
+    })
+    .await??;
+
+    let total_picks = players.len() * rounds as usize;
+    if pool.len() < total_picks {
+        let small_pool_embed = serenity::CreateEmbed::new()
+            .title("Draft Cancelled")
+            .color(Color::RED)
+            .description(format!(
+                "{} picks are needed, but only {} songs are available.",
+                total_picks,
+                pool.len()
+            ));
+        ctx.channel_id()
+            .send_message(ctx, serenity::CreateMessage::new().embed(small_pool_embed))
+            .await?;
+        return Ok(());
+    }
+
+    players.shuffle(&mut rand::thread_rng());
+
+    let (_draft, mut pick_requests) = ctx.data().drafts.open(ctx.channel_id(), album.clone());
+    let mut taken: HashSet<i32> = HashSet::new();
+    let mut rosters: HashMap<serenity::UserId, Vec<(i32, String)>> = HashMap::new();
+
+    let order = players
+        .iter()
+        .map(|player| player.mention().to_string())
+        .collect::<Vec<_>>()
+        .join(" → ");
+    let begin_embed = serenity::CreateEmbed::new()
+        .title("The Draft Begins")
+        .color(Color::BLURPLE)
+        .description(format!(
+            "Pick order: {}\nThe order reverses every round. Use `/draft pick` when it's your turn; you have {} seconds per pick.",
+            order,
+            pick_timeout.as_secs()
+        ));
+    ctx.channel_id()
+        .send_message(ctx, serenity::CreateMessage::new().embed(begin_embed))
+        .await?;
+
+    let mut pick_number = 0;
+    for round in 0..rounds as usize {
+        for player in snake_order(&players, round) {
+            pick_number += 1;
+            let deadline = tokio::time::Instant::now() + pick_timeout;
+            let deadline_timestamp = chrono::Utc::now().timestamp() + pick_timeout.as_secs() as i64;
+
+            ctx.channel_id()
+                .say(
+                    ctx,
+                    format!(
+                        "Pick {}/{}: {} is on the clock, <t:{}:R>.",
+                        pick_number,
+                        total_picks,
+                        player.mention(),
+                        deadline_timestamp
+                    ),
+                )
+                .await?;
+
+            let mut picked = None;
+            while let Ok(Some(request)) =
+                tokio::time::timeout_at(deadline, pick_requests.recv()).await
+            {
+                if request.user_id != player {
+                    let _ = request
+                        .reply
+                        .send(Err(format!("It's {}'s turn to pick.", player.mention())));
+                    continue;
+                }
+                if taken.contains(&request.song_id) {
+                    let _ = request
+                        .reply
+                        .send(Err("That song has already been drafted.".to_string()));
+                    continue;
+                }
+
+                let db = ctx.data().db.clone();
+                let song_id = request.song_id;
+                let album_cloned = album.clone();
+                let title = tokio::task::spawn_blocking(
+                    move || -> Result<Option<String>, rusqlite::Error> {
+                        let db_lock = db.blocking_lock();
+                        db_lock
+                        .query_row(
+                            "SELECT title FROM songs WHERE id = ?1 AND (?2 IS NULL OR album = ?2)",
+                            params![song_id, album_cloned],
+                            |row| row.get(0),
+                        )
+                        .optional()
+                    },
+                )
+                .await??;
+
+                let Some(title) = title else {
+                    let _ = request
+                        .reply
+                        .send(Err("That song isn't part of this draft.".to_string()));
+                    continue;
+                };
+
+                let _ =
+                    request
+                        .reply
+                        .send(Ok(format!("{} drafts **{}**!", player.mention(), title)));
+                picked = Some((song_id, title));
+                break;
+            }
+
+            // Running out of time picks a random available song instead
+            let (song_id, title) = match picked {
+                Some(picked) => picked,
+                None => {
+                    let song_id =
+                        sampling::uniform_sample(&pool, 1, &taken, &mut rand::thread_rng())[0];
+
+                    let db = ctx.data().db.clone();
+                    let title =
+                        tokio::task::spawn_blocking(move || -> Result<String, rusqlite::Error> {
+                            let db_lock = db.blocking_lock();
+                            db_lock.query_row(
+                                "SELECT title FROM songs WHERE id = ?1",
+                                params![song_id],
+                                |row| row.get(0),
+                            )
+                        })
+                        .await??;
+
+                    ctx.channel_id()
+                        .say(
+                            ctx,
+                            format!(
+                                "⏰ Time's up! {} is auto-drafted **{}**.",
+                                player.mention(),
+                                title
+                            ),
+                        )
+                        .await?;
+
+                    (song_id, title)
+                }
+            };
+
+            taken.insert(song_id);
+            ctx.data().drafts.mark_taken(ctx.channel_id(), song_id);
+            rosters.entry(player).or_default().push((song_id, title));
+        }
+    }
+
+    let summary = players
+        .iter()
+        .enumerate()
+        .map(|(i, player)| {
+            format!(
+                "{}. {} — {}",
+                i + 1,
+                player.mention(),
+                roster_list(rosters.get(player).map_or(&[], Vec::as_slice))
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let summary_embed = serenity::CreateEmbed::new()
+        .title("Draft Results")
+        .color(Color::GOLD)
+        .description(summary);
+
+    if !vote.unwrap_or(false) {
+        ctx.channel_id()
+            .send_message(ctx, serenity::CreateMessage::new().embed(summary_embed))
+            .await?;
+        return Ok(());
+    }
+
+    let vote_prefix = format!("draft-vote-{}-", ctx.id());
+    let vote_buttons = |disabled: bool| {
+        players
+            .chunks(5)
+            .enumerate()
+            .map(|(row, chunk)| {
+                serenity::CreateActionRow::Buttons(
+                    chunk
+                        .iter()
+                        .enumerate()
+                        .map(|(column, _)| {
+                            let index = row * 5 + column;
+                            serenity::CreateButton::new(format!("{}{}", vote_prefix, index))
+                                .label(format!("Roster {}", index + 1))
+                                .style(serenity::ButtonStyle::Secondary)
+                                .disabled(disabled)
+                        })
+                        .collect(),
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let mut vote_message = ctx
+        .channel_id()
+        .send_message(
+            ctx,
+            serenity::CreateMessage::new()
+                .embed(summary_embed.clone().footer(serenity::CreateEmbedFooter::new(
+                    "Vote for the best roster! You can change your vote, but not vote for your own.",
+                )))
+                .components(vote_buttons(false)),
+        )
+        .await?;
+
+    let mut votes: HashMap<serenity::UserId, usize> = HashMap::new();
+    let mut interactions = serenity::ComponentInteractionCollector::new(ctx)
+        .message_id(vote_message.id)
+        .timeout(VOTE_TIMEOUT)
+        .stream();
+
+    while let Some(interaction) = interactions.next().await {
+        let Some(index) = interaction
+            .data
+            .custom_id
+            .strip_prefix(&vote_prefix)
+            .and_then(|index| index.parse::<usize>().ok())
+            .filter(|index| *index < players.len())
+        else {
+            continue;
+        };
+
+        let content = if players[index] == interaction.user.id {
+            "You can't vote for your own roster.".to_string()
+        } else {
+            votes.insert(interaction.user.id, index);
+            format!("You voted for roster {}.", index + 1)
+        };
+
+        interaction
+            .create_response(
+                ctx,
+                serenity::CreateInteractionResponse::Message(
+                    serenity::CreateInteractionResponseMessage::new()
+                        .content(content)
+                        .ephemeral(true),
+                ),
+            )
+            .await?;
+    }
+
+    vote_message
+        .edit(
+            ctx,
+            serenity::EditMessage::new().components(vote_buttons(true)),
+        )
+        .await?;
+
+    let mut tally = vec![0u32; players.len()];
+    for index in votes.values() {
+        tally[*index] += 1;
+    }
+    let most_votes = tally.iter().copied().max().unwrap_or_default();
+
+    let result = if most_votes == 0 {
+        "Nobody voted.".to_string()
+    } else {
+        let winners = players
+            .iter()
+            .zip(&tally)
+            .filter(|(_, votes)| **votes == most_votes)
+            .map(|(player, _)| player.mention().to_string())
+            .collect::<Vec<_>>()
+            .join(" and ");
+        format!(
+            "{} drafted the best roster with {} vote(s)!",
+            winners, most_votes
+        )
+    };
+
+    let vote_result_embed = serenity::CreateEmbed::new()
+        .title("Best Roster")
+        .color(Color::GOLD)
+        .description(result);
+
+    ctx.channel_id()
+        .send_message(ctx, serenity::CreateMessage::new().embed(vote_result_embed))
+        .await?;
+
+    // Every vote a roster received is worth a point
+    let awards = players
+        .iter()
+        .zip(&tally)
+        .map(|(player, votes)| points::Award {
+            user_id: *player,
+            points: *votes,
+            won: most_votes > 0 && *votes == most_votes,
+            albums: Vec::new(),
+        })
+        .collect::<Vec<_>>();
+
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get() as i64);
+    let db = ctx.data().db.clone();
+    let unlocked = tokio::task::spawn_blocking(move || {
+        let db_lock = db.blocking_lock();
+        points::record_game(&db_lock, guild_id, Game::Draft, &awards)
+    })
+    .await??;
+
+    points::announce(ctx, ctx.channel_id(), &unlocked).await?;
+
+    Ok(())
+}
+
+/// Draft a song when it's your turn
+#[poise::command(prefix_command, slash_command)]
+pub async fn pick(
+    ctx: Context<'_>,
+    #[autocomplete = "autocomplete_available_song"]
+    #[description = "The song to draft"]
+    song: i32,
+) -> Result<(), Error> {
+    let picks = ctx
+        .data()
+        .drafts
+        .channels
+        .lock()
+        .unwrap()
+        .get(&ctx.channel_id())
+        .map(|draft| draft.picks.clone());
+
+    let outcome = match picks {
+        Some(picks) => {
+            let (reply, response) = oneshot::channel();
+            let request = PickRequest {
+                user_id: ctx.author().id,
+                song_id: song,
+                reply,
+            };
+
+            match picks.send(request).await {
+                // The draft drops the request without answering once the pick is over
+                Ok(()) => response
+                    .await
+                    .unwrap_or_else(|_| Err("The draft isn't taking picks right now.".to_string())),
+                Err(_) => Err("The draft has ended.".to_string()),
+            }
+        }
+        None => Err("There is no draft running in this channel.".to_string()),
+    };
+
+    match outcome {
+        Ok(announcement) => {
+            ctx.say(announcement).await?;
+        }
+        Err(message) => {
+            let pick_fail_embed = serenity::CreateEmbed::new()
+                .title("Error")
+                .color(Color::RED)
+                .description(message);
+            ctx.send(
+                poise::CreateReply::default()
+                    .embed(pick_fail_embed)
+                    .ephemeral(true),
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
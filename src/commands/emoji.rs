@@ -0,0 +1,120 @@
+use crate::{Context, Error};
+use poise::serenity_prelude::{self as serenity, Color};
+use rusqlite::{OptionalExtension, params};
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    subcommands("set", "clear"),
+    subcommand_required,
+    check = "crate::checks::check_is_moderator"
+)]
+
+/// Set or clear the emoji clue of a song
+pub async fn emoji(_: Context<'_>) -> Result<(), Error> {
+    // This will never be called, because `subcommand_required` parameter is set
+    Ok(())
+}
+
+/// Set the emoji clue of a song for /emojiguess
+#[poise::command(prefix_command, slash_command)]
+pub async fn set(
+    ctx: Context<'_>,
+    #[autocomplete = "crate::autocomplete::autocomplete_song"]
+    #[description = "The song"]
+    song_id: i32,
+    #[description = "The emoji that hint at the title"] clue: String,
+) -> Result<(), Error> {
+    let clue = clue.trim().to_string();
+
+    if clue.is_empty() || clue.chars().any(|c| c.is_ascii_alphabetic()) {
+        let invalid_clue_embed = serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description("A clue can only be made of emoji, without any letters.");
+        ctx.send(poise::CreateReply::default().embed(invalid_clue_embed))
+            .await?;
+        return Ok(());
+    }
+
+    let db = ctx.data().db.clone();
+    let clue_cloned = clue.clone();
+
+    let title = tokio::task::spawn_blocking(move || -> Result<Option<String>, rusqlite::Error> {
+        let db_lock = db.blocking_lock();
+
+        let title: Option<String> = db_lock
+            .query_row(
+                "SELECT title FROM songs WHERE id = ?1",
+                params![song_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        if title.is_some() {
+            db_lock.execute(
+                "INSERT INTO emoji_clues (song_id, clue) VALUES (?1, ?2)
+                 ON CONFLICT(song_id) DO UPDATE SET clue = excluded.clue",
+                params![song_id, clue_cloned],
+            )?;
+        }
+
+        Ok(title)
+    })
+    .await??;
+
+    let embed = match title {
+        Some(title) => serenity::CreateEmbed::new()
+            .title("Success")
+            .color(Color::DARK_GREEN)
+            .description(format!("The clue for '{}' is now {}", title, clue)),
+        None => serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description(format!("No song found with ID: {}", song_id)),
+    };
+
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+    Ok(())
+}
+
+/// Remove the emoji clue of a song
+#[poise::command(prefix_command, slash_command)]
+pub async fn clear(
+    ctx: Context<'_>,
+    #[autocomplete = "crate::autocomplete::autocomplete_song"]
+    #[description = "The song"]
+    song_id: i32,
+) -> Result<(), Error> {
+    let db = ctx.data().db.clone();
+
+    let affected_rows = tokio::task::spawn_blocking(move || -> Result<usize, rusqlite::Error> {
+        let db_lock = db.blocking_lock();
+
+        db_lock.execute(
+            "DELETE FROM emoji_clues WHERE song_id = ?1",
+            params![song_id],
+        )
+    })
+    .await??;
+
+    let embed = if affected_rows == 0 {
+        serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description(format!("Song with ID: {} has no emoji clue.", song_id))
+    } else {
+        serenity::CreateEmbed::new()
+            .title("Success")
+            .color(Color::DARK_GREEN)
+            .description(format!(
+                "Removed the emoji clue of song with ID: {}.",
+                song_id
+            ))
+    };
+
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+    Ok(())
+}
@@ -0,0 +1,223 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
+use futures::StreamExt;
+use poise::serenity_prelude::{self as serenity, Color, Mentionable};
+use rusqlite::params;
+
+use crate::{
+    Context, Error,
+    commands::leaderboard::send_leaderboard,
+    games,
+    points::{self, Game, Period},
+};
+
+/// How long players have to name the song
+const ROUND_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_ROUNDS: u32 = 10;
+
+struct Round {
+    title: String,
+    album: String,
+    clue: String,
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    subcommands("start", "scores"),
+    subcommand_required
+)]
+
+/// Name the song from its emoji clue
+pub async fn emojiguess(_: Context<'_>) -> Result<(), Error> {
+    // This will never be called, because `subcommand_required` parameter is set
+    Ok(())
+}
+
+/// Start a game of emoji guessing in this channel
+#[poise::command(prefix_command, slash_command)]
+pub async fn start(
+    ctx: Context<'_>,
+    #[description = "Amount of rounds to play (5 by default)"] rounds: Option<u32>,
+) -> Result<(), Error> {
+    let rounds = rounds.unwrap_or(5).clamp(1, MAX_ROUNDS);
+
+    let _game = match ctx
+        .data()
+        .active_games
+        .start(ctx.channel_id(), "emoji guessing")
+    {
+        Ok(game) => game,
+        Err(running) => {
+            let busy_embed = serenity::CreateEmbed::new()
+                .title("Error")
+                .color(Color::RED)
+                .description(format!("A game of {} is already running here.", running));
+            ctx.send(
+                poise::CreateReply::default()
+                    .embed(busy_embed)
+                    .ephemeral(true),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    let db = ctx.data().db.clone();
+
+    let prepared = tokio::task::spawn_blocking(move || -> Result<Vec<Round>, rusqlite::Error> {
+        let db_lock = db.blocking_lock();
+
+        let mut stmt = db_lock.prepare(
+            "SELECT s.title, s.album, e.clue FROM emoji_clues e
+             JOIN songs s ON s.id = e.song_id
+             ORDER BY RANDOM() LIMIT ?1",
+        )?;
+        let round_iter = stmt.query_map(params![rounds], |row| {
+            Ok(Round {
+                title: row.get(0)?,
+                album: row.get(1)?,
+                clue: row.get(2)?,
+            })
+        })?;
+
+        round_iter.collect()
+    })
+    .await??;
+
+    if prepared.is_empty() {
+        let no_clues_embed = serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description("No songs have emoji clues yet. Add some with `/emoji set`.");
+        ctx.send(poise::CreateReply::default().embed(no_clues_embed))
+            .await?;
+        return Ok(());
+    }
+
+    let start_embed = serenity::CreateEmbed::new()
+        .title("Emoji Guess")
+        .color(Color::PURPLE)
+        .description(format!(
+            "{} round(s), {} seconds each. Type the song title in chat!",
+            prepared.len(),
+            ROUND_TIMEOUT.as_secs()
+        ));
+    ctx.send(poise::CreateReply::default().embed(start_embed))
+        .await?;
+
+    let mut scores: HashMap<serenity::UserId, u32> = HashMap::new();
+    let mut players: HashSet<serenity::UserId> = HashSet::new();
+    let mut guessed_albums: HashMap<serenity::UserId, Vec<String>> = HashMap::new();
+
+    for (round_index, round) in prepared.iter().enumerate() {
+        let round_embed = serenity::CreateEmbed::new()
+            .title(format!("Round {}/{}", round_index + 1, prepared.len()))
+            .color(Color::PURPLE)
+            .description(format!("# {}\nWhich song is this?", round.clue));
+
+        ctx.channel_id()
+            .send_message(ctx, serenity::CreateMessage::new().embed(round_embed))
+            .await?;
+
+        let mut guesses = serenity::MessageCollector::new(ctx)
+            .channel_id(ctx.channel_id())
+            .filter(|message| !message.author.bot)
+            .timeout(ROUND_TIMEOUT)
+            .stream();
+
+        // The collector ends the stream once the round times out
+        let mut winner = None;
+        while let Some(guess) = guesses.next().await {
+            players.insert(guess.author.id);
+            if games::is_close_match(&guess.content, &round.title) {
+                winner = Some(guess.author.id);
+                break;
+            }
+        }
+
+        let outcome = match winner {
+            Some(user_id) => {
+                *scores.entry(user_id).or_default() += 1;
+                guessed_albums
+                    .entry(user_id)
+                    .or_default()
+                    .push(round.album.clone());
+                format!("{} got it and scores a point!", user_id.mention())
+            }
+            None => "Time's up, nobody got it.".to_string(),
+        };
+
+        let result_embed = serenity::CreateEmbed::new()
+            .title(format!("Round {}/{}", round_index + 1, prepared.len()))
+            .color(if winner.is_some() {
+                Color::DARK_GREEN
+            } else {
+                Color::RED
+            })
+            .description(format!(
+                "{} was **{}** from {}. {}",
+                round.clue, round.title, round.album, outcome
+            ));
+
+        ctx.channel_id()
+            .send_message(ctx, serenity::CreateMessage::new().embed(result_embed))
+            .await?;
+    }
+
+    // Everyone who guessed took part, even without scoring
+    for player in &players {
+        scores.entry(*player).or_default();
+    }
+
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get() as i64);
+    let db = ctx.data().db.clone();
+    let awards = points::ranked_awards(&scores, guessed_albums);
+
+    let unlocked = tokio::task::spawn_blocking(move || {
+        let db_lock = db.blocking_lock();
+        points::record_game(&db_lock, guild_id, Game::Emoji, &awards)
+    })
+    .await??;
+
+    let mut ranking: Vec<(serenity::UserId, u32)> = scores
+        .into_iter()
+        .filter(|(_, points)| *points > 0)
+        .collect();
+    ranking.sort_by_key(|(_, points)| std::cmp::Reverse(*points));
+
+    let results = if ranking.is_empty() {
+        "Nobody scored this time.".to_string()
+    } else {
+        ranking
+            .iter()
+            .enumerate()
+            .map(|(i, (user_id, points))| {
+                format!("{}. {} — {} point(s)", i + 1, user_id.mention(), points)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let results_embed = serenity::CreateEmbed::new()
+        .title("Emoji Guess Results")
+        .color(Color::GOLD)
+        .description(results);
+
+    ctx.channel_id()
+        .send_message(ctx, serenity::CreateMessage::new().embed(results_embed))
+        .await?;
+
+    points::announce(ctx, ctx.channel_id(), &unlocked).await?;
+
+    Ok(())
+}
+
+/// Show the emoji guessing scoreboard
+#[poise::command(prefix_command, slash_command)]
+pub async fn scores(ctx: Context<'_>) -> Result<(), Error> {
+    send_leaderboard(ctx, Some(Game::Emoji), Period::AllTime).await
+}
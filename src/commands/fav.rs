@@ -0,0 +1,282 @@
+use crate::{Context, Error};
+use poise::serenity_prelude::{self as serenity, Color, Mentionable};
+use rusqlite::{Connection, OptionalExtension, params};
+
+/// Most favorites shown by `/fav list`, so the embed stays under Discord's limit
+const MAX_LISTED: usize = 50;
+
+/// Number of users who have favorited a song
+pub fn favorite_count(conn: &Connection, song_id: i32) -> Result<u32, rusqlite::Error> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM favorites WHERE song_id = ?1",
+        params![song_id],
+        |row| row.get(0),
+    )
+}
+
+/// Song IDs a user has favorited
+pub fn user_favorites(conn: &Connection, user_id: i64) -> Result<Vec<i32>, rusqlite::Error> {
+    let mut stmt = conn.prepare("SELECT song_id FROM favorites WHERE user_id = ?1")?;
+    let id_iter = stmt.query_map(params![user_id], |row| row.get(0))?;
+
+    id_iter.collect()
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    subcommands("add", "remove", "list", "top"),
+    subcommand_required
+)]
+
+/// Keep a list of your favorite songs
+pub async fn fav(_: Context<'_>) -> Result<(), Error> {
+    // This will never be called, because `subcommand_required` parameter is set
+    Ok(())
+}
+
+/// Add a song to your favorites
+#[poise::command(prefix_command, slash_command)]
+pub async fn add(
+    ctx: Context<'_>,
+    #[autocomplete = "crate::autocomplete::autocomplete_song"]
+    #[description = "The song"]
+    song_id: i32,
+) -> Result<(), Error> {
+    let user_id = ctx.author().id.get() as i64;
+    let db = ctx.data().db.clone();
+
+    let added = tokio::task::spawn_blocking(
+        move || -> Result<Option<(String, bool, u32)>, rusqlite::Error> {
+            let db_lock = db.blocking_lock();
+
+            let title: Option<String> = db_lock
+                .query_row(
+                    "SELECT title FROM songs WHERE id = ?1",
+                    params![song_id],
+                    |row| row.get(0),
+                )
+                .optional()?;
+
+            let Some(title) = title else {
+                return Ok(None);
+            };
+
+            let inserted = db_lock.execute(
+                "INSERT OR IGNORE INTO favorites (user_id, song_id, created_at)
+                 VALUES (?1, ?2, unixepoch())",
+                params![user_id, song_id],
+            )? > 0;
+
+            Ok(Some((title, inserted, favorite_count(&db_lock, song_id)?)))
+        },
+    )
+    .await??;
+
+    let embed = match added {
+        Some((title, true, count)) => serenity::CreateEmbed::new()
+            .title("Success")
+            .color(Color::DARK_GREEN)
+            .description(format!("Added '{}' to your favorites.", title))
+            .footer(serenity::CreateEmbedFooter::new(format!(
+                "♥ {} favorite(s)",
+                count
+            ))),
+        Some((title, false, _)) => serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description(format!("'{}' is already one of your favorites.", title)),
+        None => serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description(format!("No song found with ID: {}", song_id)),
+    };
+
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+    Ok(())
+}
+
+/// Remove a song from your favorites
+#[poise::command(prefix_command, slash_command)]
+pub async fn remove(
+    ctx: Context<'_>,
+    #[autocomplete = "crate::autocomplete::autocomplete_song"]
+    #[description = "The song"]
+    song_id: i32,
+) -> Result<(), Error> {
+    let user_id = ctx.author().id.get() as i64;
+    let db = ctx.data().db.clone();
+
+    let affected_rows = tokio::task::spawn_blocking(move || -> Result<usize, rusqlite::Error> {
+        let db_lock = db.blocking_lock();
+
+        db_lock.execute(
+            "DELETE FROM favorites WHERE user_id = ?1 AND song_id = ?2",
+            params![user_id, song_id],
+        )
+    })
+    .await??;
+
+    let embed = if affected_rows == 0 {
+        serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description(format!(
+                "Song with ID: {} isn't one of your favorites.",
+                song_id
+            ))
+    } else {
+        serenity::CreateEmbed::new()
+            .title("Success")
+            .color(Color::DARK_GREEN)
+            .description(format!(
+                "Removed song with ID: {} from your favorites.",
+                song_id
+            ))
+    };
+
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+    Ok(())
+}
+
+/// Show someone's favorite songs
+#[poise::command(prefix_command, slash_command)]
+pub async fn list(
+    ctx: Context<'_>,
+    #[description = "Whose favorites to show (yours by default)"] user: Option<serenity::User>,
+) -> Result<(), Error> {
+    let user = user.as_ref().unwrap_or_else(|| ctx.author());
+    let user_id = user.id.get() as i64;
+    let db = ctx.data().db.clone();
+
+    let favorites = tokio::task::spawn_blocking(
+        move || -> Result<Vec<(i32, String, String)>, rusqlite::Error> {
+            let db_lock = db.blocking_lock();
+
+            let mut stmt = db_lock.prepare(
+                "SELECT s.id, s.title, s.album FROM favorites f
+                 JOIN songs s ON s.id = f.song_id
+                 WHERE f.user_id = ?1
+                 ORDER BY f.created_at DESC",
+            )?;
+            let song_iter = stmt.query_map(params![user_id], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })?;
+
+            song_iter.collect()
+        },
+    )
+    .await??;
+
+    if favorites.is_empty() {
+        let no_favorites_embed = serenity::CreateEmbed::new()
+            .title(format!("{}'s Favorites", user.name))
+            .description(format!(
+                "{} hasn't favorited any songs yet. Add some with `/fav add`.",
+                user.mention()
+            ));
+
+        ctx.send(poise::CreateReply::default().embed(no_favorites_embed))
+            .await?;
+
+        return Ok(());
+    }
+
+    let favorite_list = favorites
+        .iter()
+        .take(MAX_LISTED)
+        .map(|(id, title, album)| format!("♥ {} — {} [ID: {}]", title, album, id))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let favorites_embed = serenity::CreateEmbed::new()
+        .title(format!("{}'s Favorites", user.name))
+        .color(Color::FABLED_PINK)
+        .description(favorite_list)
+        .footer(serenity::CreateEmbedFooter::new(
+            if favorites.len() > MAX_LISTED {
+                format!(
+                    "{} favorite(s), showing the {} most recent",
+                    favorites.len(),
+                    MAX_LISTED
+                )
+            } else {
+                format!("{} favorite(s)", favorites.len())
+            },
+        ));
+
+    ctx.send(poise::CreateReply::default().embed(favorites_embed))
+        .await?;
+
+    Ok(())
+}
+
+/// Show the most-favorited songs
+#[poise::command(prefix_command, slash_command)]
+pub async fn top(
+    ctx: Context<'_>,
+    #[autocomplete = "crate::commands::new::autocomplete_album"]
+    #[description = "Only include songs from this album"]
+    album: Option<String>,
+) -> Result<(), Error> {
+    let db = ctx.data().db.clone();
+    let album_cloned = album.clone();
+
+    let songs = tokio::task::spawn_blocking(
+        move || -> Result<Vec<(i32, String, String, u32)>, rusqlite::Error> {
+            let db_lock = db.blocking_lock();
+
+            let mut stmt = db_lock.prepare(
+                "SELECT s.id, s.title, s.album, COUNT(*) AS total FROM favorites f
+                 JOIN songs s ON s.id = f.song_id
+                 WHERE ?1 IS NULL OR s.album = ?1
+                 GROUP BY s.id
+                 ORDER BY total DESC, s.title
+                 LIMIT 10",
+            )?;
+            let song_iter = stmt.query_map(params![album_cloned], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })?;
+
+            song_iter.collect()
+        },
+    )
+    .await??;
+
+    let title = match &album {
+        Some(album) => format!("Most Favorited: {}", album),
+        None => "Most Favorited".to_string(),
+    };
+
+    if songs.is_empty() {
+        let no_favorites_embed = serenity::CreateEmbed::new()
+            .title(title)
+            .description("Nobody has favorited any songs yet. Add some with `/fav add`.");
+
+        ctx.send(poise::CreateReply::default().embed(no_favorites_embed))
+            .await?;
+
+        return Ok(());
+    }
+
+    let ranking = songs
+        .iter()
+        .enumerate()
+        .map(|(i, (id, title, album, count))| {
+            format!("{}. {} — {} [ID: {}] ♥ {}", i + 1, title, album, id, count)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let top_embed = serenity::CreateEmbed::new()
+        .title(title)
+        .color(Color::FABLED_PINK)
+        .description(ranking);
+
+    ctx.send(poise::CreateReply::default().embed(top_embed))
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,133 @@
+use poise::{
+    ChoiceParameter,
+    serenity_prelude::{self as serenity, Color, Mentionable},
+};
+
+use crate::{
+    Context, Error,
+    points::{self, Achievement, Game, Period},
+};
+
+/// Replies with the top players of `game`, or of every game combined
+pub async fn send_leaderboard(
+    ctx: Context<'_>,
+    game: Option<Game>,
+    period: Period,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get() as i64);
+    let db = ctx.data().db.clone();
+
+    let entries = tokio::task::spawn_blocking(move || {
+        let db_lock = db.blocking_lock();
+        points::top_players(&db_lock, guild_id, game, period)
+    })
+    .await??;
+
+    let title = match game {
+        Some(game) => format!("{} Leaderboard", game.name()),
+        None => "Leaderboard".to_string(),
+    };
+
+    if entries.is_empty() {
+        let no_scores_embed = serenity::CreateEmbed::new()
+            .title(title)
+            .description("Nobody has scored any points in this period yet.");
+
+        ctx.send(poise::CreateReply::default().embed(no_scores_embed))
+            .await?;
+
+        return Ok(());
+    }
+
+    let leaderboard = entries
+        .iter()
+        .enumerate()
+        .map(|(i, (user_id, points, games))| {
+            format!(
+                "{}. {} — {} point(s) in {} game(s)",
+                i + 1,
+                serenity::UserId::new(*user_id).mention(),
+                points,
+                games
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let leaderboard_embed = serenity::CreateEmbed::new()
+        .title(title)
+        .color(Color::GOLD)
+        .description(leaderboard)
+        .footer(serenity::CreateEmbedFooter::new(period.name()));
+
+    ctx.send(poise::CreateReply::default().embed(leaderboard_embed))
+        .await?;
+
+    Ok(())
+}
+
+/// Show the players with the most points across games
+#[poise::command(prefix_command, slash_command)]
+pub async fn leaderboard(
+    ctx: Context<'_>,
+    #[description = "Only count points from this game"] game: Option<Game>,
+    #[description = "Time window (all time by default)"] period: Option<Period>,
+) -> Result<(), Error> {
+    send_leaderboard(ctx, game, period.unwrap_or(Period::AllTime)).await
+}
+
+/// Show the achievements someone has unlocked
+#[poise::command(prefix_command, slash_command)]
+pub async fn achievements(
+    ctx: Context<'_>,
+    #[description = "Whose achievements to show (yours by default)"] user: Option<serenity::User>,
+) -> Result<(), Error> {
+    let user = user.as_ref().unwrap_or_else(|| ctx.author());
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get() as i64);
+    let user_id = user.id.get() as i64;
+    let db = ctx.data().db.clone();
+
+    let unlocked = tokio::task::spawn_blocking(move || {
+        let db_lock = db.blocking_lock();
+        points::unlocked_achievements(&db_lock, guild_id, user_id)
+    })
+    .await??;
+
+    let achievement_list = Achievement::ALL
+        .iter()
+        .map(|achievement| {
+            match unlocked
+                .iter()
+                .find(|(unlocked, _)| unlocked == achievement)
+            {
+                Some((_, unlocked_at)) => format!(
+                    "🏆 **{}** — {} (<t:{}:d>)",
+                    achievement.name(),
+                    achievement.description(),
+                    unlocked_at
+                ),
+                None => format!(
+                    "🔒 **{}** — {}",
+                    achievement.name(),
+                    achievement.description()
+                ),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let achievements_embed = serenity::CreateEmbed::new()
+        .title(format!("{}'s Achievements", user.name))
+        .color(Color::GOLD)
+        .description(achievement_list)
+        .footer(serenity::CreateEmbedFooter::new(format!(
+            "{}/{} unlocked",
+            unlocked.len(),
+            Achievement::ALL.len()
+        )));
+
+    ctx.send(poise::CreateReply::default().embed(achievements_embed))
+        .await?;
+
+    Ok(())
+}
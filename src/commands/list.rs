@@ -44,7 +44,7 @@ pub async fn list(
         let mut stmt: Statement<'_>;
 
         // Prepare the query based on whether an album is selected or not
-        if album == "" {
+        if album.is_empty() {
             stmt = db_lock.prepare("SELECT * FROM songs").unwrap();
         } else {
             stmt = db_lock
@@ -60,7 +60,7 @@ pub async fn list(
             })
         };
 
-        let song_iter = if album == "" {
+        let song_iter = if album.is_empty() {
             stmt.query_map([], map_row).unwrap()
         } else {
             stmt.query_map([album], map_row).unwrap()
@@ -79,12 +79,11 @@ pub async fn list(
     .unwrap();
 
     if list_str.is_empty() {
-        let no_songs_found_str: String;
-        if album == "" {
-            no_songs_found_str = "No songs found.".to_string();
+        let no_songs_found_str = if album.is_empty() {
+            "No songs found.".to_string()
         } else {
-            no_songs_found_str = format!("No songs found for {}.", album);
-        }
+            format!("No songs found for {}.", album)
+        };
         let no_songs_embed = serenity::CreateEmbed::new()
             .title("Error")
             .color(Color::RED)
@@ -0,0 +1,330 @@
+use chrono::{NaiveDate, Utc};
+use poise::serenity_prelude::{self as serenity, Color};
+use rusqlite::{Connection, OptionalExtension, params};
+
+use crate::{Context, Error};
+
+/// Most-listened songs shown by `/listened stats`
+const TOP_SONGS: u32 = 5;
+/// Albums shown by `/listened stats`, so the field stays under Discord's limit
+const TOP_ALBUMS: usize = 10;
+
+struct AlbumListens {
+    album: String,
+    listens: u32,
+    /// Distinct songs of the album the member has logged
+    songs_heard: u32,
+    songs_total: u32,
+}
+
+struct ListenStats {
+    total: u32,
+    songs_heard: u32,
+    catalog_size: u32,
+    top_songs: Vec<(String, u32)>,
+    albums: Vec<AlbumListens>,
+    current_streak: u32,
+    best_streak: u32,
+}
+
+/// Current and best run of consecutive days, given distinct days newest first.
+///
+/// A streak is still current if the last listen was today or yesterday.
+fn streaks(days: &[NaiveDate], today: NaiveDate) -> (u32, u32) {
+    let mut best = 0;
+    let mut run = 0;
+    let mut current = None;
+    let mut previous: Option<NaiveDate> = None;
+
+    for day in days {
+        run = match previous {
+            Some(previous) if previous.pred_opt() == Some(*day) => run + 1,
+            _ => {
+                // The first run ends where the list first has a gap
+                if previous.is_some() && current.is_none() {
+                    current = Some(run);
+                }
+                1
+            }
+        };
+        best = best.max(run);
+        previous = Some(*day);
+    }
+    let first_run = current.unwrap_or(run);
+
+    let alive = days
+        .first()
+        .is_some_and(|last| *last == today || last.succ_opt() == Some(today));
+
+    (if alive { first_run } else { 0 }, best)
+}
+
+fn load_stats(
+    conn: &Connection,
+    user_id: i64,
+    today: NaiveDate,
+) -> Result<ListenStats, rusqlite::Error> {
+    let (total, songs_heard, catalog_size) = conn.query_row(
+        "SELECT COUNT(*), COUNT(DISTINCT l.song_id), (SELECT COUNT(*) FROM songs)
+         FROM listens l
+         JOIN songs s ON s.id = l.song_id
+         WHERE l.user_id = ?1",
+        params![user_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )?;
+
+    let mut stmt = conn.prepare(
+        "SELECT s.title, COUNT(*) AS plays FROM listens l
+         JOIN songs s ON s.id = l.song_id
+         WHERE l.user_id = ?1
+         GROUP BY s.id
+         ORDER BY plays DESC, MAX(l.listened_at) DESC
+         LIMIT ?2",
+    )?;
+    let top_songs = stmt
+        .query_map(params![user_id, TOP_SONGS], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT s.album, COUNT(*) AS plays, COUNT(DISTINCT l.song_id),
+            (SELECT COUNT(*) FROM songs a WHERE a.album = s.album)
+         FROM listens l
+         JOIN songs s ON s.id = l.song_id
+         WHERE l.user_id = ?1
+         GROUP BY s.album
+         ORDER BY plays DESC",
+    )?;
+    let albums = stmt
+        .query_map(params![user_id], |row| {
+            Ok(AlbumListens {
+                album: row.get(0)?,
+                listens: row.get(1)?,
+                songs_heard: row.get(2)?,
+                songs_total: row.get(3)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT DISTINCT date(listened_at, 'unixepoch') AS day FROM listens
+         WHERE user_id = ?1
+         ORDER BY day DESC",
+    )?;
+    let days = stmt
+        .query_map(params![user_id], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?
+        .iter()
+        .filter_map(|day| NaiveDate::parse_from_str(day, "%Y-%m-%d").ok())
+        .collect::<Vec<_>>();
+    let (current_streak, best_streak) = streaks(&days, today);
+
+    Ok(ListenStats {
+        total,
+        songs_heard,
+        catalog_size,
+        top_songs,
+        albums,
+        current_streak,
+        best_streak,
+    })
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    subcommands("log", "stats"),
+    subcommand_required
+)]
+
+/// Keep a log of the songs you listen to
+pub async fn listened(_: Context<'_>) -> Result<(), Error> {
+    // This will never be called, because `subcommand_required` parameter is set
+    Ok(())
+}
+
+/// Log that you listened to a song
+#[poise::command(prefix_command, slash_command)]
+pub async fn log(
+    ctx: Context<'_>,
+    #[autocomplete = "crate::autocomplete::autocomplete_song"]
+    #[description = "The song you listened to"]
+    song_id: i32,
+) -> Result<(), Error> {
+    let user_id = ctx.author().id.get() as i64;
+    let today = Utc::now().date_naive();
+    let db = ctx.data().db.clone();
+
+    let logged = tokio::task::spawn_blocking(
+        move || -> Result<Option<(String, u32, u32)>, rusqlite::Error> {
+            let db_lock = db.blocking_lock();
+
+            let title: Option<String> = db_lock
+                .query_row(
+                    "SELECT title FROM songs WHERE id = ?1",
+                    params![song_id],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            let Some(title) = title else {
+                return Ok(None);
+            };
+
+            db_lock.execute(
+                "INSERT INTO listens (user_id, song_id, listened_at) VALUES (?1, ?2, unixepoch())",
+                params![user_id, song_id],
+            )?;
+
+            let plays = db_lock.query_row(
+                "SELECT COUNT(*) FROM listens WHERE user_id = ?1 AND song_id = ?2",
+                params![user_id, song_id],
+                |row| row.get(0),
+            )?;
+            let stats = load_stats(&db_lock, user_id, today)?;
+
+            Ok(Some((title, plays, stats.current_streak)))
+        },
+    )
+    .await??;
+
+    let embed = match logged {
+        Some((title, plays, streak)) => serenity::CreateEmbed::new()
+            .title("Success")
+            .color(Color::DARK_GREEN)
+            .description(format!(
+                "Logged a listen of '{}'. That's {} time(s) so far.",
+                title, plays
+            ))
+            .footer(serenity::CreateEmbedFooter::new(format!(
+                "🔥 {} day streak",
+                streak
+            ))),
+        None => serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description(format!("No song found with ID: {}", song_id)),
+    };
+
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+    Ok(())
+}
+
+/// Show listening totals, streaks and catalog completion
+#[poise::command(prefix_command, slash_command)]
+pub async fn stats(
+    ctx: Context<'_>,
+    #[description = "Whose stats to show (yours by default)"] user: Option<serenity::User>,
+) -> Result<(), Error> {
+    let user = user.as_ref().unwrap_or_else(|| ctx.author());
+    let user_id = user.id.get() as i64;
+    let today = Utc::now().date_naive();
+    let db = ctx.data().db.clone();
+
+    let stats = tokio::task::spawn_blocking(move || {
+        let db_lock = db.blocking_lock();
+        load_stats(&db_lock, user_id, today)
+    })
+    .await??;
+
+    if stats.total == 0 {
+        let no_listens_embed = serenity::CreateEmbed::new()
+            .title(format!("{}'s Listening Stats", user.name))
+            .description("No listens logged yet. Log one with `/listened log`.");
+
+        ctx.send(poise::CreateReply::default().embed(no_listens_embed))
+            .await?;
+
+        return Ok(());
+    }
+
+    let completion = (stats.songs_heard * 100)
+        .checked_div(stats.catalog_size)
+        .unwrap_or_default();
+
+    let top_songs = stats
+        .top_songs
+        .iter()
+        .enumerate()
+        .map(|(i, (title, plays))| format!("{}. {} — {} listen(s)", i + 1, title, plays))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let albums = stats
+        .albums
+        .iter()
+        .take(TOP_ALBUMS)
+        .map(|album| {
+            format!(
+                "{} — {} listen(s), {}/{} songs",
+                album.album, album.listens, album.songs_heard, album.songs_total
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let stats_embed = serenity::CreateEmbed::new()
+        .title(format!("{}'s Listening Stats", user.name))
+        .color(Color::TEAL)
+        .field("Listens", stats.total.to_string(), true)
+        .field(
+            "Catalog completion",
+            format!(
+                "{}% ({}/{} songs)",
+                completion, stats.songs_heard, stats.catalog_size
+            ),
+            true,
+        )
+        .field(
+            "Streak",
+            format!(
+                "🔥 {} day(s) (best: {})",
+                stats.current_streak, stats.best_streak
+            ),
+            true,
+        )
+        .field("Most listened", top_songs, false)
+        .field("By album", albums, false);
+
+    ctx.send(poise::CreateReply::default().embed(stats_embed))
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, day).unwrap()
+    }
+
+    #[test]
+    fn no_listens_means_no_streak() {
+        assert_eq!(streaks(&[], day(10)), (0, 0));
+    }
+
+    #[test]
+    fn streak_ending_today_is_current() {
+        assert_eq!(streaks(&[day(10), day(9), day(8)], day(10)), (3, 3));
+    }
+
+    #[test]
+    fn streak_ending_yesterday_is_current() {
+        assert_eq!(streaks(&[day(9), day(8)], day(10)), (2, 2));
+    }
+
+    #[test]
+    fn broken_streak_is_not_current() {
+        assert_eq!(streaks(&[day(7), day(6), day(5)], day(10)), (0, 3));
+    }
+
+    #[test]
+    fn best_streak_can_be_an_older_one() {
+        let days = [day(10), day(9), day(6), day(5), day(4), day(3), day(1)];
+
+        assert_eq!(streaks(&days, day(10)), (2, 4));
+    }
+}
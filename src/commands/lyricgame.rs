@@ -0,0 +1,404 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
+use futures::StreamExt;
+use poise::serenity_prelude::{self as serenity, Color, Mentionable};
+use rand::{Rng, seq::SliceRandom};
+use rusqlite::params;
+
+use crate::{
+    Context, Error,
+    commands::lyrics::lyric_lines,
+    games,
+    points::{self, Achievement, Game},
+};
+
+/// How long players have to answer a round
+const ROUND_TIMEOUT: Duration = Duration::from_secs(45);
+/// When the hint is posted, counted from the start of a round
+const HINT_DELAY: Duration = Duration::from_secs(20);
+const MAX_ROUNDS: u32 = 10;
+/// Extra points for consecutive wins are capped at this amount
+const MAX_STREAK_BONUS: u32 = 3;
+
+struct Round {
+    title: String,
+    album: String,
+    line: String,
+    next_line: String,
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    subcommands("start", "scores"),
+    subcommand_required
+)]
+
+/// Finish the lyric or name the song
+pub async fn lyricgame(_: Context<'_>) -> Result<(), Error> {
+    // This will never be called, because `subcommand_required` parameter is set
+    Ok(())
+}
+
+/// Picks random lines from songs with lyrics, along with the line that follows
+fn prepare_rounds(
+    conn: &rusqlite::Connection,
+    rounds: usize,
+) -> Result<Vec<Round>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT s.title, s.album, l.body FROM lyrics l
+         JOIN songs s ON s.id = l.song_id
+         ORDER BY RANDOM() LIMIT ?1",
+    )?;
+
+    let lyric_iter = stmt.query_map(params![rounds as i64], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+        ))
+    })?;
+
+    let mut rng = rand::thread_rng();
+    let mut prepared = Vec::new();
+    for lyric in lyric_iter {
+        let (title, album, body) = lyric?;
+        let lines = lyric_lines(&body);
+
+        // There has to be a line to finish
+        if lines.len() < 2 {
+            continue;
+        }
+
+        let index = rng.gen_range(0..lines.len() - 1);
+        prepared.push(Round {
+            title,
+            album,
+            line: lines[index].to_string(),
+            next_line: lines[index + 1].to_string(),
+        });
+    }
+
+    prepared.shuffle(&mut rng);
+
+    Ok(prepared)
+}
+
+/// The first word of the next line and the initials of the song title
+fn hint(round: &Round) -> String {
+    let first_word = round
+        .next_line
+        .split_whitespace()
+        .next()
+        .unwrap_or_default();
+    let initials = round
+        .title
+        .split_whitespace()
+        .filter_map(|word| word.chars().next())
+        .map(|initial| format!("{}.", initial))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    format!(
+        "The next line starts with **{}**… and the song's initials are **{}**",
+        first_word, initials
+    )
+}
+
+/// Start a game of finish-the-lyric in this channel
+#[poise::command(prefix_command, slash_command)]
+pub async fn start(
+    ctx: Context<'_>,
+    #[description = "Amount of rounds to play (5 by default)"] rounds: Option<u32>,
+) -> Result<(), Error> {
+    let rounds = rounds.unwrap_or(5).clamp(1, MAX_ROUNDS) as usize;
+
+    let _game = match ctx
+        .data()
+        .active_games
+        .start(ctx.channel_id(), "finish-the-lyric")
+    {
+        Ok(game) => game,
+        Err(running) => {
+            let busy_embed = serenity::CreateEmbed::new()
+                .title("Error")
+                .color(Color::RED)
+                .description(format!("A game of {} is already running here.", running));
+            ctx.send(
+                poise::CreateReply::default()
+                    .embed(busy_embed)
+                    .ephemeral(true),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    let db = ctx.data().db.clone();
+
+    let prepared = tokio::task::spawn_blocking(move || {
+        let db_lock = db.blocking_lock();
+        prepare_rounds(&db_lock, rounds)
+    })
+    .await??;
+
+    if prepared.is_empty() {
+        let no_lyrics_embed = serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description("No songs with lyrics were found. Add some with `/lyrics set`.");
+        ctx.send(poise::CreateReply::default().embed(no_lyrics_embed))
+            .await?;
+        return Ok(());
+    }
+
+    let start_embed = serenity::CreateEmbed::new()
+        .title("Finish the Lyric")
+        .color(Color::PURPLE)
+        .description(format!(
+            "{} round(s). Type the next line or the song title in chat! Consecutive wins earn bonus points.",
+            prepared.len()
+        ));
+    ctx.send(poise::CreateReply::default().embed(start_embed))
+        .await?;
+
+    let mut scores: HashMap<serenity::UserId, u32> = HashMap::new();
+    let mut players: HashSet<serenity::UserId> = HashSet::new();
+    let mut best_streaks: HashMap<serenity::UserId, u32> = HashMap::new();
+    let mut guessed_albums: HashMap<serenity::UserId, Vec<String>> = HashMap::new();
+    let mut streak: Option<(serenity::UserId, u32)> = None;
+
+    for (round_index, round) in prepared.iter().enumerate() {
+        let round_embed = serenity::CreateEmbed::new()
+            .title(format!("Round {}/{}", round_index + 1, prepared.len()))
+            .color(Color::PURPLE)
+            .description(format!("> {}\n\nWhat comes next?", round.line));
+
+        ctx.channel_id()
+            .send_message(ctx, serenity::CreateMessage::new().embed(round_embed))
+            .await?;
+
+        let mut guesses = serenity::MessageCollector::new(ctx)
+            .channel_id(ctx.channel_id())
+            .filter(|message| !message.author.bot)
+            .timeout(ROUND_TIMEOUT)
+            .stream();
+
+        let hint_at = tokio::time::Instant::now() + HINT_DELAY;
+        let mut hinted = false;
+        let mut winner = None;
+
+        loop {
+            let guess = if hinted {
+                guesses.next().await
+            } else {
+                match tokio::time::timeout_at(hint_at, guesses.next()).await {
+                    Ok(guess) => guess,
+                    Err(_) => {
+                        hinted = true;
+                        ctx.channel_id()
+                            .say(ctx, format!("💡 Hint: {}", hint(round)))
+                            .await?;
+                        continue;
+                    }
+                }
+            };
+
+            // The collector ends the stream once the round times out
+            let Some(guess) = guess else {
+                break;
+            };
+            players.insert(guess.author.id);
+
+            if games::is_close_match(&guess.content, &round.next_line)
+                || games::is_close_match(&guess.content, &round.title)
+            {
+                winner = Some(guess.author.id);
+                break;
+            }
+        }
+
+        let outcome = match winner {
+            Some(user_id) => {
+                let current = match streak {
+                    Some((streak_user, count)) if streak_user == user_id => count + 1,
+                    _ => 1,
+                };
+                streak = Some((user_id, current));
+
+                let best = best_streaks.entry(user_id).or_default();
+                *best = (*best).max(current);
+
+                let points = 1 + (current - 1).min(MAX_STREAK_BONUS);
+                *scores.entry(user_id).or_default() += points;
+                guessed_albums
+                    .entry(user_id)
+                    .or_default()
+                    .push(round.album.clone());
+
+                if current > 1 {
+                    format!(
+                        "{} got it and scores {} points ({} in a row)!",
+                        user_id.mention(),
+                        points,
+                        current
+                    )
+                } else {
+                    format!("{} got it and scores a point!", user_id.mention())
+                }
+            }
+            None => {
+                streak = None;
+                "Nobody got it.".to_string()
+            }
+        };
+
+        let result_embed = serenity::CreateEmbed::new()
+            .title(format!("Round {}/{}", round_index + 1, prepared.len()))
+            .color(if winner.is_some() {
+                Color::DARK_GREEN
+            } else {
+                Color::RED
+            })
+            .description(format!(
+                "> {}\n> {}\n\nFrom **{}**. {}",
+                round.line, round.next_line, round.title, outcome
+            ));
+
+        ctx.channel_id()
+            .send_message(ctx, serenity::CreateMessage::new().embed(result_embed))
+            .await?;
+    }
+
+    // Everyone who guessed took part, even without scoring
+    for player in &players {
+        scores.entry(*player).or_default();
+    }
+
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get() as i64);
+    let db = ctx.data().db.clone();
+    let awards = points::ranked_awards(&scores, guessed_albums);
+    let saved_streaks: Vec<(i64, u32)> = best_streaks
+        .iter()
+        .map(|(user_id, best_streak)| (user_id.get() as i64, *best_streak))
+        .collect();
+
+    let unlocked = tokio::task::spawn_blocking(
+        move || -> Result<Vec<(serenity::UserId, Achievement)>, rusqlite::Error> {
+            let db_lock = db.blocking_lock();
+
+            let mut stmt = db_lock.prepare(
+                "INSERT INTO lyric_streaks (guild_id, user_id, best_streak)
+                 VALUES (?1, ?2, ?3)
+                 ON CONFLICT(guild_id, user_id) DO UPDATE SET
+                    best_streak = MAX(best_streak, excluded.best_streak)",
+            )?;
+            for (user_id, best_streak) in saved_streaks {
+                stmt.execute(params![guild_id, user_id, best_streak])?;
+            }
+
+            points::record_game(&db_lock, guild_id, Game::Lyrics, &awards)
+        },
+    )
+    .await??;
+
+    let mut ranking: Vec<(serenity::UserId, u32)> = scores
+        .into_iter()
+        .filter(|(_, points)| *points > 0)
+        .collect();
+    ranking.sort_by_key(|(_, points)| std::cmp::Reverse(*points));
+
+    let results = if ranking.is_empty() {
+        "Nobody scored this time.".to_string()
+    } else {
+        ranking
+            .iter()
+            .enumerate()
+            .map(|(i, (user_id, points))| {
+                format!("{}. {} — {} point(s)", i + 1, user_id.mention(), points)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let results_embed = serenity::CreateEmbed::new()
+        .title("Finish the Lyric Results")
+        .color(Color::GOLD)
+        .description(results);
+
+    ctx.channel_id()
+        .send_message(ctx, serenity::CreateMessage::new().embed(results_embed))
+        .await?;
+
+    points::announce(ctx, ctx.channel_id(), &unlocked).await?;
+
+    Ok(())
+}
+
+/// Show the finish-the-lyric scoreboard
+#[poise::command(prefix_command, slash_command)]
+pub async fn scores(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get() as i64);
+    let db = ctx.data().db.clone();
+
+    let entries =
+        tokio::task::spawn_blocking(move || -> Result<Vec<(u64, u32, u32)>, rusqlite::Error> {
+            let db_lock = db.blocking_lock();
+
+            let mut stmt = db_lock.prepare(
+                "SELECT l.user_id, SUM(l.points) AS total, COALESCE(MAX(s.best_streak), 0)
+                 FROM points_ledger l
+                 LEFT JOIN lyric_streaks s ON s.guild_id = l.guild_id AND s.user_id = l.user_id
+                 WHERE l.guild_id = ?1 AND l.game = ?2
+                 GROUP BY l.user_id
+                 HAVING total > 0
+                 ORDER BY total DESC LIMIT 10",
+            )?;
+
+            let entry_iter = stmt.query_map(params![guild_id, Game::Lyrics.key()], |row| {
+                Ok((row.get::<_, i64>(0)? as u64, row.get(1)?, row.get(2)?))
+            })?;
+
+            entry_iter.collect()
+        })
+        .await??;
+
+    if entries.is_empty() {
+        let no_scores_embed = serenity::CreateEmbed::new()
+            .title("No Scores")
+            .description("Nobody has played finish-the-lyric yet.");
+
+        ctx.send(poise::CreateReply::default().embed(no_scores_embed))
+            .await?;
+
+        return Ok(());
+    }
+
+    let scoreboard = entries
+        .iter()
+        .enumerate()
+        .map(|(i, (user_id, points, best_streak))| {
+            format!(
+                "{}. {} — {} point(s), best streak {}",
+                i + 1,
+                serenity::UserId::new(*user_id).mention(),
+                points,
+                best_streak
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let scoreboard_embed = serenity::CreateEmbed::new()
+        .title("Finish the Lyric Scoreboard")
+        .color(Color::GOLD)
+        .description(scoreboard);
+
+    ctx.send(poise::CreateReply::default().embed(scoreboard_embed))
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,169 @@
+use crate::{Context, Error};
+use poise::serenity_prelude::{self as serenity, Color};
+use rusqlite::{OptionalExtension, params};
+
+/// Splits stored or submitted lyrics into their non-empty lines
+pub fn lyric_lines(lyrics: &str) -> Vec<&str> {
+    lyrics
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    subcommands("set", "clear", "show"),
+    subcommand_required,
+    check = "crate::checks::check_is_moderator"
+)]
+
+/// Set, clear or show the lyrics of a song
+pub async fn lyrics(_: Context<'_>) -> Result<(), Error> {
+    // This will never be called, because `subcommand_required` parameter is set
+    Ok(())
+}
+
+/// Set the lyrics of a song from text or a .txt file
+#[poise::command(prefix_command, slash_command)]
+pub async fn set(
+    ctx: Context<'_>,
+    #[description = "The ID of the song"] song_id: i32,
+    #[description = "The lyrics, with lines separated by ' / '"] text: Option<String>,
+    #[description = "A text file with one lyric line per line"] file: Option<serenity::Attachment>,
+) -> Result<(), Error> {
+    let lyrics = match (text, file) {
+        (_, Some(file)) => String::from_utf8_lossy(&file.download().await?).into_owned(),
+        (Some(text), None) => text.split(" / ").collect::<Vec<_>>().join("\n"),
+        (None, None) => {
+            let missing_lyrics_embed = serenity::CreateEmbed::new()
+                .title("Error")
+                .color(Color::RED)
+                .description("Provide the lyrics as text or as a file.");
+            ctx.send(poise::CreateReply::default().embed(missing_lyrics_embed))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let lyrics = lyric_lines(&lyrics).join("\n");
+    let line_count = lyric_lines(&lyrics).len();
+
+    let db = ctx.data().db.clone();
+
+    let title = tokio::task::spawn_blocking(move || -> Result<Option<String>, rusqlite::Error> {
+        let db_lock = db.blocking_lock();
+
+        let title: Option<String> = db_lock
+            .query_row(
+                "SELECT title FROM songs WHERE id = ?1",
+                params![song_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        if title.is_some() {
+            db_lock.execute(
+                "INSERT INTO lyrics (song_id, body) VALUES (?1, ?2)
+                 ON CONFLICT(song_id) DO UPDATE SET body = excluded.body",
+                params![song_id, lyrics],
+            )?;
+        }
+
+        Ok(title)
+    })
+    .await??;
+
+    let embed = match title {
+        Some(title) => serenity::CreateEmbed::new()
+            .title("Success")
+            .color(Color::DARK_GREEN)
+            .description(format!(
+                "Saved {} lyric line(s) for '{}'.",
+                line_count, title
+            )),
+        None => serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description(format!("No song found with ID: {}", song_id)),
+    };
+
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+    Ok(())
+}
+
+/// Remove the lyrics of a song
+#[poise::command(prefix_command, slash_command)]
+pub async fn clear(
+    ctx: Context<'_>,
+    #[description = "The ID of the song"] song_id: i32,
+) -> Result<(), Error> {
+    let db = ctx.data().db.clone();
+
+    let affected_rows = tokio::task::spawn_blocking(move || -> Result<usize, rusqlite::Error> {
+        let db_lock = db.blocking_lock();
+
+        db_lock.execute("DELETE FROM lyrics WHERE song_id = ?1", params![song_id])
+    })
+    .await??;
+
+    let embed = if affected_rows == 0 {
+        serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description(format!("Song with ID: {} has no lyrics.", song_id))
+    } else {
+        serenity::CreateEmbed::new()
+            .title("Success")
+            .color(Color::DARK_GREEN)
+            .description(format!("Removed the lyrics of song with ID: {}.", song_id))
+    };
+
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+    Ok(())
+}
+
+/// Show the stored lyrics of a song
+#[poise::command(prefix_command, slash_command)]
+pub async fn show(
+    ctx: Context<'_>,
+    #[description = "The ID of the song"] song_id: i32,
+) -> Result<(), Error> {
+    let db = ctx.data().db.clone();
+
+    let stored = tokio::task::spawn_blocking(
+        move || -> Result<Option<(String, String)>, rusqlite::Error> {
+            let db_lock = db.blocking_lock();
+
+            db_lock
+                .query_row(
+                    "SELECT s.title, l.body FROM lyrics l
+                     JOIN songs s ON s.id = l.song_id
+                     WHERE l.song_id = ?1",
+                    params![song_id],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .optional()
+        },
+    )
+    .await??;
+
+    let embed = match stored {
+        Some((title, body)) => serenity::CreateEmbed::new()
+            .title(format!("Lyrics of {}", title))
+            .color(Color::MAGENTA)
+            .description(body.chars().take(4096).collect::<String>()),
+        None => serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description(format!("Song with ID: {} has no lyrics.", song_id)),
+    };
+
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+
+    Ok(())
+}
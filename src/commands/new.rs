@@ -1,19 +1,9 @@
-use crate::{Context, Error};
-use futures::{Stream, StreamExt};
+use crate::{Context, Error, commands::setlist::parse_song_length};
+use futures::Stream;
 use poise::serenity_prelude::{self as serenity, Color};
-use rusqlite::{Row, params};
-
-#[derive(Debug, poise::ChoiceParameter)]
-pub enum Album {
-    #[name = "Singles/B-Sides"]
-    SinglesBSides,
-    #[name = "Live Songs"]
-    LiveSongs,
-    #[name = "Covers"]
-    Covers,
-}
+use rusqlite::{Connection, Row, params};
 
-async fn autocomplete_album<'a>(
+pub async fn autocomplete_album<'a>(
     ctx: Context<'_>,
     partial: &'a str,
 ) -> impl Stream<Item = String> + 'a {
@@ -60,6 +50,38 @@ async fn autocomplete_album<'a>(
     futures::stream::iter(album_names)
 }
 
+/// Inserts a song unless one with the same title exists, returning its ID.
+///
+/// Callers must invalidate the song ID cache after a successful insert.
+pub fn insert_song(
+    conn: &Connection,
+    title: &str,
+    album: &str,
+    duration: Option<u32>,
+    added_by: i64,
+) -> Result<Option<i64>, rusqlite::Error> {
+    // Check if the song already exists
+    let exists: i32 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM songs WHERE UPPER(title) = UPPER(?1)",
+            params![title],
+            |row| row.get(0),
+        )
+        .unwrap_or(0); // Default to 0 if the query fails
+
+    if exists > 0 {
+        return Ok(None);
+    }
+
+    // If the song does not exist, insert it
+    conn.execute(
+        "INSERT INTO songs (title, album, duration, added_by) VALUES (?1, ?2, ?3, ?4)",
+        params![title, album, duration, added_by],
+    )?;
+
+    Ok(Some(conn.last_insert_rowid()))
+}
+
 /// Adds a new song to the list
 #[poise::command(
     slash_command,
@@ -72,25 +94,38 @@ pub async fn new(
     #[autocomplete = "autocomplete_album"]
     #[description = "Album of the song"]
     album: String,
+    #[description = "Length of the song, e.g. 3:45"] duration: Option<String>,
 ) -> Result<(), Error> {
-    // let album_str = match album {
-    //     Album::SinglesBSides => "Singles/B-Sides",
-    //     Album::LiveSongs => "Live Songs",
-    //     Album::Covers => "Covers",
-    // };
+    let duration = match duration {
+        Some(duration) => match parse_song_length(&duration) {
+            Some(seconds) => Some(seconds),
+            None => {
+                let invalid_duration_embed = serenity::CreateEmbed::default()
+                    .title("Error!")
+                    .color(Color::RED)
+                    .description(format!(
+                        "'{}' is not a valid length. Use `m:ss`, up to 24 hours.",
+                        duration
+                    ));
+
+                ctx.send(poise::CreateReply::default().embed(invalid_duration_embed))
+                    .await?;
+                return Ok(());
+            }
+        },
+        None => None,
+    };
 
     let db_lock = ctx.data().db.lock().await;
 
-    // Check if the song already exists
-    let exists: i32 = db_lock
-        .query_row(
-            "SELECT COUNT(*) FROM songs WHERE UPPER(title) = UPPER(?1)",
-            params![title],
-            |row| row.get(0),
-        )
-        .unwrap_or(0); // Default to 0 if the query fails
-
-    if exists > 0 {
+    let Some(song_id) = insert_song(
+        &db_lock,
+        &title,
+        &album,
+        duration,
+        ctx.author().id.get() as i64,
+    )?
+    else {
         let fail_embed = serenity::CreateEmbed::default()
             .title("Error!")
             .color(Color::RED)
@@ -102,22 +137,15 @@ pub async fn new(
         ctx.send(poise::CreateReply::default().embed(fail_embed))
             .await?;
         return Ok(());
-    }
-
-    // If the song does not exist, insert it
-    db_lock.execute(
-        "INSERT INTO songs (title, album) VALUES (?1, ?2)",
-        params![title, album],
-    )?;
+    };
+    ctx.data().song_ids.invalidate();
 
     let success_embed = serenity::CreateEmbed::default()
         .title("Success!")
         .color(Color::LIGHT_GREY)
         .description(format!(
             "Inserted song: '{}' with ID: {} in album '{}'.",
-            title,
-            db_lock.last_insert_rowid(),
-            album
+            title, song_id, album
         ));
 
     ctx.send(poise::CreateReply::default().embed(success_embed))
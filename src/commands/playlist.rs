@@ -0,0 +1,670 @@
+use crate::{Context, Error, positions};
+use poise::ChoiceParameter;
+use poise::serenity_prelude::{self as serenity, Color, Mentionable};
+use rusqlite::{Connection, OptionalExtension, params};
+
+/// Songs a playlist can hold, so `/playlist show` fits in one embed
+const MAX_SONGS: u32 = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, poise::ChoiceParameter)]
+pub enum Visibility {
+    #[name = "Private"]
+    Private,
+    #[name = "Server"]
+    Server,
+    #[name = "Collaborative"]
+    Collaborative,
+}
+
+impl Visibility {
+    fn key(self) -> &'static str {
+        match self {
+            Visibility::Private => "private",
+            Visibility::Server => "server",
+            Visibility::Collaborative => "collaborative",
+        }
+    }
+
+    fn from_key(key: &str) -> Visibility {
+        match key {
+            "server" => Visibility::Server,
+            "collaborative" => Visibility::Collaborative,
+            _ => Visibility::Private,
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            Visibility::Private => "only you can see it",
+            Visibility::Server => "everyone can see it, only you can edit it",
+            Visibility::Collaborative => "everyone can see and edit it",
+        }
+    }
+}
+
+/// What a command needs to be allowed to do with a playlist
+#[derive(Clone, Copy, PartialEq)]
+enum Access {
+    View,
+    Edit,
+    Own,
+}
+
+struct Playlist {
+    id: i64,
+    owner_id: u64,
+    name: String,
+    visibility: Visibility,
+}
+
+/// Loads a playlist if `user_id` has the requested access, or explains why not
+fn load_playlist(
+    conn: &Connection,
+    guild_id: i64,
+    playlist_id: i64,
+    user_id: u64,
+    access: Access,
+) -> Result<Result<Playlist, String>, rusqlite::Error> {
+    let playlist = conn
+        .query_row(
+            "SELECT id, owner_id, name, visibility FROM playlists WHERE id = ?1 AND guild_id = ?2",
+            params![playlist_id, guild_id],
+            |row| {
+                Ok(Playlist {
+                    id: row.get(0)?,
+                    owner_id: row.get::<_, i64>(1)? as u64,
+                    name: row.get(2)?,
+                    visibility: Visibility::from_key(&row.get::<_, String>(3)?),
+                })
+            },
+        )
+        .optional()?;
+
+    let Some(playlist) = playlist else {
+        return Ok(Err(format!("No playlist found with ID: {}", playlist_id)));
+    };
+
+    let is_owner = playlist.owner_id == user_id;
+    let allowed = match access {
+        Access::View => is_owner || playlist.visibility != Visibility::Private,
+        Access::Edit => is_owner || playlist.visibility == Visibility::Collaborative,
+        Access::Own => is_owner,
+    };
+
+    if allowed {
+        Ok(Ok(playlist))
+    } else if is_owner || playlist.visibility != Visibility::Private {
+        Ok(Err(format!(
+            "You don't have permission to change '{}'.",
+            playlist.name
+        )))
+    } else {
+        // Don't reveal that a private playlist exists
+        Ok(Err(format!("No playlist found with ID: {}", playlist_id)))
+    }
+}
+
+/// Number of entries in a playlist, including songs that have since been deleted
+fn song_count(conn: &Connection, playlist_id: i64) -> Result<u32, rusqlite::Error> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM playlist_songs WHERE playlist_id = ?1",
+        params![playlist_id],
+        |row| row.get(0),
+    )
+}
+
+/// Suggests playlists the user can see whose name contains `partial`
+async fn autocomplete_playlist(
+    ctx: Context<'_>,
+    partial: &str,
+) -> Vec<serenity::AutocompleteChoice> {
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get() as i64);
+    let user_id = ctx.author().id.get() as i64;
+    let db = ctx.data().db.clone();
+    let pattern = format!("%{}%", partial);
+
+    let playlists = tokio::task::spawn_blocking(
+        move || -> Result<Vec<(i64, String, String)>, rusqlite::Error> {
+            let db_lock = db.blocking_lock();
+
+            let mut stmt = db_lock.prepare(
+                "SELECT id, name, visibility FROM playlists
+                 WHERE guild_id = ?1 AND (owner_id = ?2 OR visibility != 'private')
+                    AND name LIKE ?3
+                 ORDER BY owner_id != ?2, name
+                 LIMIT 25",
+            )?;
+            let playlist_iter = stmt.query_map(params![guild_id, user_id, pattern], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })?;
+
+            playlist_iter.collect()
+        },
+    )
+    .await;
+
+    match playlists {
+        Ok(Ok(playlists)) => playlists
+            .into_iter()
+            .map(|(id, name, visibility)| {
+                let name = format!("{} ({})", name, visibility);
+                serenity::AutocompleteChoice::new(name.chars().take(100).collect::<String>(), id)
+            })
+            .collect(),
+        Ok(Err(err)) => {
+            eprintln!("Error while fetching playlists: {}", err);
+            Vec::new()
+        }
+        Err(err) => {
+            eprintln!("Playlist autocomplete task failed: {}", err);
+            Vec::new()
+        }
+    }
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    subcommands(
+        "create",
+        "add",
+        "remove",
+        "reorder",
+        "show",
+        "delete",
+        "clone_playlist"
+    ),
+    subcommand_required
+)]
+
+/// Build playlists of songs from the catalog
+pub async fn playlist(_: Context<'_>) -> Result<(), Error> {
+    // This will never be called, because `subcommand_required` parameter is set
+    Ok(())
+}
+
+/// Create a new playlist
+#[poise::command(prefix_command, slash_command)]
+pub async fn create(
+    ctx: Context<'_>,
+    #[description = "The name of the playlist"] name: String,
+    #[description = "Who can see and edit it (private by default)"] visibility: Option<Visibility>,
+) -> Result<(), Error> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        let missing_name_embed = serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description("Give the playlist a name.");
+        ctx.send(poise::CreateReply::default().embed(missing_name_embed))
+            .await?;
+        return Ok(());
+    }
+
+    let visibility = visibility.unwrap_or(Visibility::Private);
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get() as i64);
+    let owner_id = ctx.author().id.get() as i64;
+    let db = ctx.data().db.clone();
+    let name_cloned = name.clone();
+
+    let playlist_id =
+        tokio::task::spawn_blocking(move || -> Result<Option<i64>, rusqlite::Error> {
+            let db_lock = db.blocking_lock();
+
+            db_lock
+                .query_row(
+                    "INSERT INTO playlists (guild_id, owner_id, name, visibility, created_at)
+                 VALUES (?1, ?2, ?3, ?4, unixepoch())
+                 ON CONFLICT(guild_id, owner_id, name) DO NOTHING
+                 RETURNING id",
+                    params![guild_id, owner_id, name_cloned, visibility.key()],
+                    |row| row.get(0),
+                )
+                .optional()
+        })
+        .await??;
+
+    let embed = match playlist_id {
+        Some(playlist_id) => serenity::CreateEmbed::new()
+            .title("Success")
+            .color(Color::DARK_GREEN)
+            .description(format!(
+                "Created the playlist '{}' with ID: {}; {}.",
+                name,
+                playlist_id,
+                visibility.describe()
+            )),
+        None => serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description(format!("You already have a playlist called '{}'.", name)),
+    };
+
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+    Ok(())
+}
+
+/// Add a song to the end of a playlist
+#[poise::command(prefix_command, slash_command)]
+pub async fn add(
+    ctx: Context<'_>,
+    #[autocomplete = "autocomplete_playlist"]
+    #[description = "The playlist"]
+    playlist: i64,
+    #[autocomplete = "crate::autocomplete::autocomplete_song"]
+    #[description = "The song to add"]
+    song_id: i32,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get() as i64);
+    let user_id = ctx.author().id.get();
+    let db = ctx.data().db.clone();
+
+    let outcome = tokio::task::spawn_blocking(
+        move || -> Result<Result<(String, String), String>, rusqlite::Error> {
+            let db_lock = db.blocking_lock();
+
+            let playlist = match load_playlist(&db_lock, guild_id, playlist, user_id, Access::Edit)?
+            {
+                Ok(playlist) => playlist,
+                Err(message) => return Ok(Err(message)),
+            };
+
+            let title: Option<String> = db_lock
+                .query_row(
+                    "SELECT title FROM songs WHERE id = ?1",
+                    params![song_id],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            let Some(title) = title else {
+                return Ok(Err(format!("No song found with ID: {}", song_id)));
+            };
+
+            if song_count(&db_lock, playlist.id)? >= MAX_SONGS {
+                return Ok(Err(format!(
+                    "'{}' already holds the maximum of {} songs.",
+                    playlist.name, MAX_SONGS
+                )));
+            }
+
+            db_lock.execute(
+                "INSERT INTO playlist_songs (playlist_id, position, song_id, added_by)
+                 SELECT ?1, COALESCE(MAX(position), 0) + 1, ?2, ?3
+                 FROM playlist_songs WHERE playlist_id = ?1",
+                params![playlist.id, song_id, user_id as i64],
+            )?;
+
+            Ok(Ok((title, playlist.name)))
+        },
+    )
+    .await??;
+
+    let embed = match outcome {
+        Ok((title, name)) => serenity::CreateEmbed::new()
+            .title("Success")
+            .color(Color::DARK_GREEN)
+            .description(format!("Added '{}' to '{}'.", title, name)),
+        Err(message) => serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description(message),
+    };
+
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+    Ok(())
+}
+
+/// Remove the song at a position from a playlist
+#[poise::command(prefix_command, slash_command)]
+pub async fn remove(
+    ctx: Context<'_>,
+    #[autocomplete = "autocomplete_playlist"]
+    #[description = "The playlist"]
+    playlist: i64,
+    #[description = "Position of the song, as shown by /playlist show"] position: u32,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get() as i64);
+    let user_id = ctx.author().id.get();
+    let db = ctx.data().db.clone();
+
+    let outcome =
+        tokio::task::spawn_blocking(move || -> Result<Result<String, String>, rusqlite::Error> {
+            let db_lock = db.blocking_lock();
+
+            let playlist = match load_playlist(&db_lock, guild_id, playlist, user_id, Access::Edit)?
+            {
+                Ok(playlist) => playlist,
+                Err(message) => return Ok(Err(message)),
+            };
+
+            let tx = db_lock.unchecked_transaction()?;
+
+            let removed = tx.execute(
+                "DELETE FROM playlist_songs WHERE playlist_id = ?1 AND position = ?2",
+                params![playlist.id, position],
+            )?;
+            if removed == 0 {
+                return Ok(Err(format!(
+                    "'{}' has no song at position {}.",
+                    playlist.name, position
+                )));
+            }
+
+            tx.execute(
+                "UPDATE playlist_songs SET position = position - 1
+                 WHERE playlist_id = ?1 AND position > ?2",
+                params![playlist.id, position],
+            )?;
+            tx.commit()?;
+
+            Ok(Ok(playlist.name))
+        })
+        .await??;
+
+    let embed = match outcome {
+        Ok(name) => serenity::CreateEmbed::new()
+            .title("Success")
+            .color(Color::DARK_GREEN)
+            .description(format!(
+                "Removed the song at position {} from '{}'.",
+                position, name
+            )),
+        Err(message) => serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description(message),
+    };
+
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+    Ok(())
+}
+
+/// Move a song to another position in a playlist
+#[poise::command(prefix_command, slash_command)]
+pub async fn reorder(
+    ctx: Context<'_>,
+    #[autocomplete = "autocomplete_playlist"]
+    #[description = "The playlist"]
+    playlist: i64,
+    #[description = "Current position of the song"] from: u32,
+    #[description = "Position to move it to"] to: u32,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get() as i64);
+    let user_id = ctx.author().id.get();
+    let db = ctx.data().db.clone();
+
+    let outcome =
+        tokio::task::spawn_blocking(move || -> Result<Result<String, String>, rusqlite::Error> {
+            let db_lock = db.blocking_lock();
+
+            let playlist = match load_playlist(&db_lock, guild_id, playlist, user_id, Access::Edit)?
+            {
+                Ok(playlist) => playlist,
+                Err(message) => return Ok(Err(message)),
+            };
+
+            let count = song_count(&db_lock, playlist.id)?;
+            if from == 0 || to == 0 || from > count || to > count {
+                return Ok(Err(format!(
+                    "Positions in '{}' go from 1 to {}.",
+                    playlist.name, count
+                )));
+            }
+
+            let tx = db_lock.unchecked_transaction()?;
+
+            positions::move_position(&tx, "playlist_songs", "playlist_id", playlist.id, from, to)?;
+            tx.commit()?;
+
+            Ok(Ok(playlist.name))
+        })
+        .await??;
+
+    let embed = match outcome {
+        Ok(name) => serenity::CreateEmbed::new()
+            .title("Success")
+            .color(Color::DARK_GREEN)
+            .description(format!(
+                "Moved the song at position {} to position {} in '{}'.",
+                from, to, name
+            )),
+        Err(message) => serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description(message),
+    };
+
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+    Ok(())
+}
+
+/// Show the songs in a playlist
+#[poise::command(prefix_command, slash_command)]
+pub async fn show(
+    ctx: Context<'_>,
+    #[autocomplete = "autocomplete_playlist"]
+    #[description = "The playlist"]
+    playlist: i64,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get() as i64);
+    let user_id = ctx.author().id.get();
+    let db = ctx.data().db.clone();
+
+    type Entry = (u32, i32, Option<String>, Option<String>);
+
+    let outcome = tokio::task::spawn_blocking(
+        move || -> Result<Result<(Playlist, Vec<Entry>), String>, rusqlite::Error> {
+            let db_lock = db.blocking_lock();
+
+            let playlist = match load_playlist(&db_lock, guild_id, playlist, user_id, Access::View)?
+            {
+                Ok(playlist) => playlist,
+                Err(message) => return Ok(Err(message)),
+            };
+
+            // Deleted songs stay in place so positions don't shift under anyone
+            let mut stmt = db_lock.prepare(
+                "SELECT p.position, p.song_id, s.title, s.album FROM playlist_songs p
+                 LEFT JOIN songs s ON s.id = p.song_id
+                 WHERE p.playlist_id = ?1
+                 ORDER BY p.position",
+            )?;
+            let entries = stmt
+                .query_map(params![playlist.id], |row| {
+                    Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Ok(Ok((playlist, entries)))
+        },
+    )
+    .await??;
+
+    let (playlist, entries) = match outcome {
+        Ok(outcome) => outcome,
+        Err(message) => {
+            let not_found_embed = serenity::CreateEmbed::new()
+                .title("Error")
+                .color(Color::RED)
+                .description(message);
+            ctx.send(poise::CreateReply::default().embed(not_found_embed))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let song_list = if entries.is_empty() {
+        "This playlist is empty. Add songs with `/playlist add`.".to_string()
+    } else {
+        entries
+            .iter()
+            .map(|(position, song_id, title, album)| match (title, album) {
+                (Some(title), Some(album)) => {
+                    format!("{}. {} — {} [ID: {}]", position, title, album, song_id)
+                }
+                _ => format!("{}. ~~Deleted song~~ [ID: {}]", position, song_id),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let playlist_embed = serenity::CreateEmbed::new()
+        .title(&playlist.name)
+        .color(Color::BLUE)
+        .description(song_list)
+        .field(
+            "Owner",
+            serenity::UserId::new(playlist.owner_id)
+                .mention()
+                .to_string(),
+            true,
+        )
+        .field("Visibility", playlist.visibility.name(), true)
+        .footer(serenity::CreateEmbedFooter::new(format!(
+            "ID: {} • {} song(s)",
+            playlist.id,
+            entries.len()
+        )));
+
+    ctx.send(poise::CreateReply::default().embed(playlist_embed))
+        .await?;
+
+    Ok(())
+}
+
+/// Delete one of your playlists
+#[poise::command(prefix_command, slash_command)]
+pub async fn delete(
+    ctx: Context<'_>,
+    #[autocomplete = "autocomplete_playlist"]
+    #[description = "The playlist"]
+    playlist: i64,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get() as i64);
+    let user_id = ctx.author().id.get();
+    let db = ctx.data().db.clone();
+
+    let outcome =
+        tokio::task::spawn_blocking(move || -> Result<Result<String, String>, rusqlite::Error> {
+            let db_lock = db.blocking_lock();
+
+            let playlist = match load_playlist(&db_lock, guild_id, playlist, user_id, Access::Own)?
+            {
+                Ok(playlist) => playlist,
+                Err(message) => return Ok(Err(message)),
+            };
+
+            let tx = db_lock.unchecked_transaction()?;
+            tx.execute(
+                "DELETE FROM playlist_songs WHERE playlist_id = ?1",
+                params![playlist.id],
+            )?;
+            tx.execute("DELETE FROM playlists WHERE id = ?1", params![playlist.id])?;
+            tx.commit()?;
+
+            Ok(Ok(playlist.name))
+        })
+        .await??;
+
+    let embed = match outcome {
+        Ok(name) => serenity::CreateEmbed::new()
+            .title("Success")
+            .color(Color::DARK_GREEN)
+            .description(format!("Deleted the playlist '{}'.", name)),
+        Err(message) => serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description(message),
+    };
+
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+    Ok(())
+}
+
+/// Copy a playlist you can see into a new private playlist of your own
+#[poise::command(prefix_command, slash_command, rename = "clone")]
+pub async fn clone_playlist(
+    ctx: Context<'_>,
+    #[autocomplete = "autocomplete_playlist"]
+    #[description = "The playlist to copy"]
+    playlist: i64,
+    #[description = "Name of the copy (the original name by default)"] name: Option<String>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get() as i64);
+    let user_id = ctx.author().id.get();
+    let db = ctx.data().db.clone();
+
+    let outcome = tokio::task::spawn_blocking(
+        move || -> Result<Result<(String, i64), String>, rusqlite::Error> {
+            let db_lock = db.blocking_lock();
+
+            let original = match load_playlist(&db_lock, guild_id, playlist, user_id, Access::View)?
+            {
+                Ok(playlist) => playlist,
+                Err(message) => return Ok(Err(message)),
+            };
+
+            let name = name
+                .map(|name| name.trim().to_string())
+                .filter(|name| !name.is_empty())
+                .unwrap_or_else(|| original.name.clone());
+
+            let tx = db_lock.unchecked_transaction()?;
+
+            let copy_id: Option<i64> = tx
+                .query_row(
+                    "INSERT INTO playlists (guild_id, owner_id, name, visibility, created_at)
+                     VALUES (?1, ?2, ?3, ?4, unixepoch())
+                     ON CONFLICT(guild_id, owner_id, name) DO NOTHING
+                     RETURNING id",
+                    params![guild_id, user_id as i64, name, Visibility::Private.key()],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            let Some(copy_id) = copy_id else {
+                return Ok(Err(format!(
+                    "You already have a playlist called '{}'. Pick another name.",
+                    name
+                )));
+            };
+
+            // Songs deleted from the catalog aren't worth copying
+            tx.execute(
+                "INSERT INTO playlist_songs (playlist_id, position, song_id, added_by)
+                 SELECT ?1, ROW_NUMBER() OVER (ORDER BY p.position), p.song_id, ?2
+                 FROM playlist_songs p
+                 JOIN songs s ON s.id = p.song_id
+                 WHERE p.playlist_id = ?3
+                 ORDER BY p.position",
+                params![copy_id, user_id as i64, original.id],
+            )?;
+            tx.commit()?;
+
+            Ok(Ok((name, copy_id)))
+        },
+    )
+    .await??;
+
+    let embed = match outcome {
+        Ok((name, copy_id)) => serenity::CreateEmbed::new()
+            .title("Success")
+            .color(Color::DARK_GREEN)
+            .description(format!(
+                "Copied the playlist into '{}' with ID: {}. The copy is private.",
+                name, copy_id
+            )),
+        Err(message) => serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description(message),
+    };
+
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+    Ok(())
+}
@@ -0,0 +1,706 @@
+use std::{sync::Arc, time::Duration};
+
+use poise::serenity_prelude::{self as serenity, Color, Mentionable};
+use rusqlite::{Connection, OptionalExtension, params};
+use tokio::sync::Mutex;
+
+use crate::{Context, Data, Error};
+
+/// Prefix of the custom ID of vote buttons, followed by `<poll id>:<option position>`
+const VOTE_PREFIX: &str = "poll-vote:";
+/// How often the scheduler checks for polls past their deadline
+const SCHEDULER_INTERVAL: Duration = Duration::from_secs(30);
+/// Polls can't run for longer than this
+const MAX_DURATION: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+struct PollOption {
+    song_id: i32,
+    title: String,
+    votes: u32,
+}
+
+struct Poll {
+    id: i64,
+    channel_id: u64,
+    message_id: Option<i64>,
+    question: String,
+    ends_at: i64,
+    closed: bool,
+    options: Vec<PollOption>,
+}
+
+/// Parses durations like `30m`, `2h`, `1d` or `1h30m`, returning `None` if it doesn't fit in a `u64`
+pub fn parse_duration(input: &str) -> Option<Duration> {
+    let mut total = 0u64;
+    let mut number = String::new();
+
+    for c in input.trim().to_lowercase().chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 24 * 60 * 60,
+            'w' => 7 * 24 * 60 * 60,
+            ' ' => continue,
+            _ => return None,
+        };
+        total = total.checked_add(number.parse::<u64>().ok()?.checked_mul(unit)?)?;
+        number.clear();
+    }
+
+    // A bare number counts as minutes
+    if !number.is_empty() {
+        total = total.checked_add(number.parse::<u64>().ok()?.checked_mul(60)?)?;
+    }
+
+    (total > 0).then(|| Duration::from_secs(total))
+}
+
+fn load_poll(conn: &Connection, poll_id: i64) -> Result<Option<Poll>, rusqlite::Error> {
+    let poll = conn
+        .query_row(
+            "SELECT id, channel_id, message_id, question, ends_at, closed FROM polls WHERE id = ?1",
+            params![poll_id],
+            |row| {
+                Ok(Poll {
+                    id: row.get(0)?,
+                    channel_id: row.get::<_, i64>(1)? as u64,
+                    message_id: row.get(2)?,
+                    question: row.get(3)?,
+                    ends_at: row.get(4)?,
+                    closed: row.get(5)?,
+                    options: Vec::new(),
+                })
+            },
+        )
+        .optional()?;
+
+    let Some(mut poll) = poll else {
+        return Ok(None);
+    };
+
+    let mut stmt = conn.prepare(
+        "SELECT o.song_id, COALESCE(s.title, o.title),
+            (SELECT COUNT(*) FROM poll_votes v WHERE v.poll_id = o.poll_id AND v.position = o.position)
+         FROM poll_options o
+         LEFT JOIN songs s ON s.id = o.song_id
+         WHERE o.poll_id = ?1
+         ORDER BY o.position",
+    )?;
+    let option_iter = stmt.query_map(params![poll_id], |row| {
+        Ok(PollOption {
+            song_id: row.get(0)?,
+            title: row.get(1)?,
+            votes: row.get(2)?,
+        })
+    })?;
+    poll.options = option_iter.collect::<Result<_, _>>()?;
+
+    Ok(Some(poll))
+}
+
+/// Indices of the options with the most votes; empty when nobody voted
+fn leaders(poll: &Poll) -> Vec<usize> {
+    let most_votes = poll
+        .options
+        .iter()
+        .map(|option| option.votes)
+        .max()
+        .unwrap_or_default();
+
+    if most_votes == 0 {
+        return Vec::new();
+    }
+
+    poll.options
+        .iter()
+        .enumerate()
+        .filter(|(_, option)| option.votes == most_votes)
+        .map(|(i, _)| i)
+        .collect()
+}
+
+fn poll_embed(poll: &Poll) -> serenity::CreateEmbed {
+    let total_votes: u32 = poll.options.iter().map(|option| option.votes).sum();
+
+    let results = poll
+        .options
+        .iter()
+        .enumerate()
+        .map(|(i, option)| {
+            let share = (option.votes * 100).checked_div(total_votes).unwrap_or(0);
+            format!(
+                "{}. **{}** — {} vote(s) ({}%)",
+                i + 1,
+                option.title,
+                option.votes,
+                share
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let status = if poll.closed {
+        format!("Closed <t:{}:R>.", poll.ends_at)
+    } else {
+        format!(
+            "Closes <t:{}:R>. You can change your vote until then.",
+            poll.ends_at
+        )
+    };
+
+    serenity::CreateEmbed::new()
+        .title(poll.question.clone())
+        .color(if poll.closed {
+            Color::DARK_GREY
+        } else {
+            Color::TEAL
+        })
+        .description(format!("{}\n\n{}", results, status))
+        .footer(serenity::CreateEmbedFooter::new(format!(
+            "Poll ID: {} • {} vote(s)",
+            poll.id, total_votes
+        )))
+}
+
+/// One button per song, five to a row; disabled once the poll is closed
+fn poll_buttons(poll: &Poll) -> Vec<serenity::CreateActionRow> {
+    let winners = if poll.closed {
+        leaders(poll)
+    } else {
+        Vec::new()
+    };
+
+    poll.options
+        .chunks(5)
+        .enumerate()
+        .map(|(row, options)| {
+            let buttons = options
+                .iter()
+                .enumerate()
+                .map(|(column, option)| {
+                    let position = row * 5 + column;
+                    let style = if winners.contains(&position) {
+                        serenity::ButtonStyle::Success
+                    } else {
+                        serenity::ButtonStyle::Secondary
+                    };
+
+                    serenity::CreateButton::new(format!("{}{}:{}", VOTE_PREFIX, poll.id, position))
+                        .label(
+                            format!("{}. {}", position + 1, option.title)
+                                .chars()
+                                .take(80)
+                                .collect::<String>(),
+                        )
+                        .style(style)
+                        .disabled(poll.closed)
+                })
+                .collect();
+
+            serenity::CreateActionRow::Buttons(buttons)
+        })
+        .collect()
+}
+
+/// Closes a poll, records its winner and updates its message with the final results
+async fn close_poll(
+    http: &serenity::Http,
+    db: &Arc<Mutex<Connection>>,
+    poll_id: i64,
+) -> Result<bool, Error> {
+    let db_cloned = db.clone();
+    let poll = tokio::task::spawn_blocking(move || -> Result<Option<Poll>, rusqlite::Error> {
+        let db_lock = db_cloned.blocking_lock();
+
+        let Some(mut poll) = load_poll(&db_lock, poll_id)? else {
+            return Ok(None);
+        };
+        if poll.closed {
+            return Ok(None);
+        }
+        poll.closed = true;
+
+        // Ties are recorded without a winner
+        let leaders = leaders(&poll);
+        let winner = match leaders.as_slice() {
+            [winner] => Some(poll.options[*winner].song_id),
+            _ => None,
+        };
+
+        poll.ends_at = db_lock.query_row(
+            "UPDATE polls SET closed = 1, winner_song_id = ?2, ends_at = MIN(ends_at, unixepoch())
+             WHERE id = ?1 RETURNING ends_at",
+            params![poll_id, winner],
+            |row| row.get(0),
+        )?;
+
+        Ok(Some(poll))
+    })
+    .await??;
+
+    let Some(poll) = poll else {
+        return Ok(false);
+    };
+
+    let channel_id = serenity::ChannelId::new(poll.channel_id);
+
+    if let Some(message_id) = poll.message_id {
+        let edit = serenity::EditMessage::new()
+            .embed(poll_embed(&poll))
+            .components(poll_buttons(&poll));
+        channel_id
+            .edit_message(http, serenity::MessageId::new(message_id as u64), edit)
+            .await?;
+    }
+
+    let leaders = leaders(&poll);
+    let result = match leaders.as_slice() {
+        [] => "Nobody voted.".to_string(),
+        [winner] => format!(
+            "**{}** wins with {} vote(s)!",
+            poll.options[*winner].title, poll.options[*winner].votes
+        ),
+        tied => format!(
+            "It's a tie between {}.",
+            tied.iter()
+                .map(|i| format!("**{}**", poll.options[*i].title))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+
+    let result_embed = serenity::CreateEmbed::new()
+        .title(format!("Poll closed: {}", poll.question))
+        .color(Color::TEAL)
+        .description(result);
+
+    let mut message = serenity::CreateMessage::new().embed(result_embed);
+    if let Some(message_id) = poll.message_id {
+        message =
+            message.reference_message((channel_id, serenity::MessageId::new(message_id as u64)));
+    }
+    channel_id.send_message(http, message).await?;
+
+    Ok(true)
+}
+
+/// Background task that closes polls once their deadline has passed.
+///
+/// Polls live in the database, so a poll whose deadline passed while the bot was offline is
+/// closed right after a restart.
+pub async fn scheduler(http: Arc<serenity::Http>, db: Arc<Mutex<Connection>>) {
+    let mut interval = tokio::time::interval(SCHEDULER_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let db_cloned = db.clone();
+        let due = tokio::task::spawn_blocking(move || -> Result<Vec<i64>, rusqlite::Error> {
+            let db_lock = db_cloned.blocking_lock();
+
+            let mut stmt = db_lock
+                .prepare("SELECT id FROM polls WHERE closed = 0 AND ends_at <= unixepoch()")?;
+            stmt.query_map([], |row| row.get(0))?.collect()
+        })
+        .await;
+
+        let due = match due {
+            Ok(Ok(due)) => due,
+            Ok(Err(err)) => {
+                eprintln!("Error while checking poll deadlines: {}", err);
+                continue;
+            }
+            Err(err) => {
+                eprintln!("Poll scheduler task failed: {}", err);
+                continue;
+            }
+        };
+
+        for poll_id in due {
+            if let Err(err) = close_poll(&http, &db, poll_id).await {
+                eprintln!("Error while closing poll {}: {}", poll_id, err);
+            }
+        }
+    }
+}
+
+/// Records or changes a vote from a poll button and updates the live counts
+pub async fn handle_vote(
+    ctx: &serenity::Context,
+    interaction: &serenity::ComponentInteraction,
+    data: &Data,
+) -> Result<(), Error> {
+    let Some((poll_id, position)) = interaction
+        .data
+        .custom_id
+        .strip_prefix(VOTE_PREFIX)
+        .and_then(|vote| vote.split_once(':'))
+    else {
+        return Ok(());
+    };
+    let (Ok(poll_id), Ok(position)) = (poll_id.parse::<i64>(), position.parse::<i64>()) else {
+        return Ok(());
+    };
+    let user_id = interaction.user.id.get() as i64;
+
+    let db = data.db.clone();
+    let poll = tokio::task::spawn_blocking(move || -> Result<Option<Poll>, rusqlite::Error> {
+        let db_lock = db.blocking_lock();
+
+        let open: bool = db_lock.query_row(
+            "SELECT COUNT(*) FROM polls WHERE id = ?1 AND closed = 0 AND ends_at > unixepoch()",
+            params![poll_id],
+            |row| row.get(0),
+        )?;
+        if !open {
+            return Ok(None);
+        }
+
+        db_lock.execute(
+            "INSERT INTO poll_votes (poll_id, user_id, position) VALUES (?1, ?2, ?3)
+             ON CONFLICT(poll_id, user_id) DO UPDATE SET position = excluded.position",
+            params![poll_id, user_id, position],
+        )?;
+
+        load_poll(&db_lock, poll_id)
+    })
+    .await??;
+
+    let response = match poll {
+        Some(poll) => serenity::CreateInteractionResponse::UpdateMessage(
+            serenity::CreateInteractionResponseMessage::new()
+                .embed(poll_embed(&poll))
+                .components(poll_buttons(&poll)),
+        ),
+        None => serenity::CreateInteractionResponse::Message(
+            serenity::CreateInteractionResponseMessage::new()
+                .content("This poll has closed.")
+                .ephemeral(true),
+        ),
+    };
+
+    interaction.create_response(ctx, response).await?;
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    subcommands("create", "close", "list"),
+    subcommand_required
+)]
+
+/// Vote on songs
+pub async fn poll(_: Context<'_>) -> Result<(), Error> {
+    // This will never be called, because `subcommand_required` parameter is set
+    Ok(())
+}
+
+/// Start a poll between up to 10 songs
+#[poise::command(
+    prefix_command,
+    slash_command,
+    check = "crate::checks::check_is_moderator"
+)]
+#[allow(clippy::too_many_arguments)]
+pub async fn create(
+    ctx: Context<'_>,
+    #[description = "How long the poll runs, e.g. 30m, 2h or 1d"] duration: String,
+    #[autocomplete = "crate::autocomplete::autocomplete_song"]
+    #[description = "Song 1"]
+    song1: i32,
+    #[autocomplete = "crate::autocomplete::autocomplete_song"]
+    #[description = "Song 2"]
+    song2: i32,
+    #[autocomplete = "crate::autocomplete::autocomplete_song"]
+    #[description = "Song 3"]
+    song3: Option<i32>,
+    #[autocomplete = "crate::autocomplete::autocomplete_song"]
+    #[description = "Song 4"]
+    song4: Option<i32>,
+    #[autocomplete = "crate::autocomplete::autocomplete_song"]
+    #[description = "Song 5"]
+    song5: Option<i32>,
+    #[autocomplete = "crate::autocomplete::autocomplete_song"]
+    #[description = "Song 6"]
+    song6: Option<i32>,
+    #[autocomplete = "crate::autocomplete::autocomplete_song"]
+    #[description = "Song 7"]
+    song7: Option<i32>,
+    #[autocomplete = "crate::autocomplete::autocomplete_song"]
+    #[description = "Song 8"]
+    song8: Option<i32>,
+    #[autocomplete = "crate::autocomplete::autocomplete_song"]
+    #[description = "Song 9"]
+    song9: Option<i32>,
+    #[autocomplete = "crate::autocomplete::autocomplete_song"]
+    #[description = "Song 10"]
+    song10: Option<i32>,
+    #[description = "Question to ask (\"Which song is best?\" by default)"] question: Option<
+        String,
+    >,
+) -> Result<(), Error> {
+    let Some(duration) = parse_duration(&duration).filter(|duration| *duration <= MAX_DURATION)
+    else {
+        let invalid_duration_embed = serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description(
+                "The duration must look like `30m`, `2h` or `1d`, and be at most 30 days.",
+            );
+        ctx.send(poise::CreateReply::default().embed(invalid_duration_embed))
+            .await?;
+        return Ok(());
+    };
+
+    // Songs picked more than once only get a single button
+    let mut song_ids = Vec::new();
+    for song_id in [
+        Some(song1),
+        Some(song2),
+        song3,
+        song4,
+        song5,
+        song6,
+        song7,
+        song8,
+        song9,
+        song10,
+    ]
+    .into_iter()
+    .flatten()
+    {
+        if !song_ids.contains(&song_id) {
+            song_ids.push(song_id);
+        }
+    }
+
+    let question = question.unwrap_or_else(|| "Which song is best?".to_string());
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get() as i64);
+    let channel_id = ctx.channel_id().get() as i64;
+    let creator_id = ctx.author().id.get() as i64;
+    let db = ctx.data().db.clone();
+
+    let poll = tokio::task::spawn_blocking(move || -> Result<Result<Poll, String>, rusqlite::Error> {
+        let db_lock = db.blocking_lock();
+
+        let mut titles = Vec::new();
+        for song_id in &song_ids {
+            let title: Option<String> = db_lock
+                .query_row(
+                    "SELECT title FROM songs WHERE id = ?1",
+                    params![song_id],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            match title {
+                Some(title) => titles.push(title),
+                None => return Ok(Err(format!("No song found with ID: {}", song_id))),
+            }
+        }
+
+        if titles.len() < 2 {
+            return Ok(Err("A poll needs at least two different songs.".to_string()));
+        }
+
+        let tx = db_lock.unchecked_transaction()?;
+        let poll_id: i64 = tx.query_row(
+            "INSERT INTO polls (guild_id, channel_id, creator_id, question, ends_at, closed)
+             VALUES (?1, ?2, ?3, ?4, unixepoch() + ?5, 0)
+             RETURNING id",
+            params![guild_id, channel_id, creator_id, question, duration.as_secs() as i64],
+            |row| row.get(0),
+        )?;
+
+        {
+            let mut stmt = tx.prepare(
+                "INSERT INTO poll_options (poll_id, position, song_id, title) VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for (position, (song_id, title)) in song_ids.iter().zip(&titles).enumerate() {
+                stmt.execute(params![poll_id, position as i64, song_id, title])?;
+            }
+        }
+        tx.commit()?;
+
+        Ok(load_poll(&db_lock, poll_id)?.ok_or_else(|| "Failed to create the poll.".to_string()))
+    })
+    .await??;
+
+    let poll = match poll {
+        Ok(poll) => poll,
+        Err(message) => {
+            let create_fail_embed = serenity::CreateEmbed::new()
+                .title("Error")
+                .color(Color::RED)
+                .description(message);
+            ctx.send(poise::CreateReply::default().embed(create_fail_embed))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let reply = ctx
+        .send(
+            poise::CreateReply::default()
+                .embed(poll_embed(&poll))
+                .components(poll_buttons(&poll)),
+        )
+        .await?;
+    let message_id = reply.message().await?.id.get() as i64;
+
+    let db = ctx.data().db.clone();
+    let poll_id = poll.id;
+    tokio::task::spawn_blocking(move || -> Result<usize, rusqlite::Error> {
+        let db_lock = db.blocking_lock();
+        db_lock.execute(
+            "UPDATE polls SET message_id = ?2 WHERE id = ?1",
+            params![poll_id, message_id],
+        )
+    })
+    .await??;
+
+    Ok(())
+}
+
+/// Close a poll before its deadline
+#[poise::command(
+    prefix_command,
+    slash_command,
+    check = "crate::checks::check_is_moderator"
+)]
+pub async fn close(
+    ctx: Context<'_>,
+    #[description = "The ID of the poll"] poll_id: i64,
+) -> Result<(), Error> {
+    let closed = close_poll(ctx.http(), &ctx.data().db, poll_id).await?;
+
+    let embed = if closed {
+        serenity::CreateEmbed::new()
+            .title("Success")
+            .color(Color::DARK_GREEN)
+            .description(format!("Closed poll {}.", poll_id))
+    } else {
+        serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description(format!("No open poll found with ID: {}", poll_id))
+    };
+
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+
+    Ok(())
+}
+
+/// List recent polls and their results
+#[poise::command(prefix_command, slash_command)]
+pub async fn list(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get() as i64);
+    let db = ctx.data().db.clone();
+
+    let polls =
+        tokio::task::spawn_blocking(move || -> Result<Vec<(Poll, u64)>, rusqlite::Error> {
+            let db_lock = db.blocking_lock();
+
+            let mut stmt = db_lock.prepare(
+                "SELECT id, creator_id FROM polls WHERE guild_id = ?1 ORDER BY id DESC LIMIT 10",
+            )?;
+            let poll_ids = stmt
+                .query_map(params![guild_id], |row| {
+                    Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)? as u64))
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let mut polls = Vec::new();
+            for (poll_id, creator_id) in poll_ids {
+                if let Some(poll) = load_poll(&db_lock, poll_id)? {
+                    polls.push((poll, creator_id));
+                }
+            }
+
+            Ok(polls)
+        })
+        .await??;
+
+    if polls.is_empty() {
+        let no_polls_embed = serenity::CreateEmbed::new()
+            .title("No Polls")
+            .description("No polls have been run yet.");
+
+        ctx.send(poise::CreateReply::default().embed(no_polls_embed))
+            .await?;
+
+        return Ok(());
+    }
+
+    let poll_list = polls
+        .iter()
+        .map(|(poll, creator_id)| {
+            let result = if !poll.closed {
+                format!("open until <t:{}:f>", poll.ends_at)
+            } else {
+                match leaders(poll).as_slice() {
+                    [] => "no votes".to_string(),
+                    [winner] => format!("won by **{}**", poll.options[*winner].title),
+                    _ => "tied".to_string(),
+                }
+            };
+
+            format!(
+                "`#{}` {} by {} — {}",
+                poll.id,
+                poll.question,
+                serenity::UserId::new(*creator_id).mention(),
+                result
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let poll_list_embed = serenity::CreateEmbed::new()
+        .title("Polls")
+        .color(Color::TEAL)
+        .description(poll_list);
+
+    ctx.send(poise::CreateReply::default().embed(poll_list_embed))
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_units_and_bare_minutes() {
+        assert_eq!(parse_duration("30m"), Some(Duration::from_secs(30 * 60)));
+        assert_eq!(parse_duration("1h30m"), Some(Duration::from_secs(90 * 60)));
+        assert_eq!(
+            parse_duration("2d"),
+            Some(Duration::from_secs(2 * 24 * 60 * 60))
+        );
+        assert_eq!(parse_duration("45"), Some(Duration::from_secs(45 * 60)));
+    }
+
+    #[test]
+    fn rejects_invalid_and_empty_durations() {
+        assert_eq!(parse_duration(""), None);
+        assert_eq!(parse_duration("0m"), None);
+        assert_eq!(parse_duration("soon"), None);
+    }
+
+    #[test]
+    fn rejects_overflowing_durations() {
+        assert_eq!(parse_duration("99999999999999999w"), None);
+        assert_eq!(parse_duration("99999999999999999999999"), None);
+        assert_eq!(parse_duration("18446744073709551615s1s"), None);
+    }
+}
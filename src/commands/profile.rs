@@ -0,0 +1,190 @@
+use crate::{Context, Error};
+use poise::serenity_prelude::{self as serenity, Color};
+use rusqlite::{Connection, params};
+
+/// Top-rated songs listed on a profile
+const TOP_RATED: u32 = 3;
+/// Playlist names listed on a profile
+const MAX_PLAYLISTS: usize = 5;
+
+struct Profile {
+    favorites: u32,
+    ratings: u32,
+    /// Titles and scores of the member's highest rated songs
+    top_rated: Vec<(String, u8)>,
+    /// Names of the playlists the viewer can see
+    playlists: Vec<String>,
+    games_played: u32,
+    games_won: u32,
+    points: u32,
+    achievements: u32,
+    suggestions_accepted: u32,
+    edits_accepted: u32,
+    songs_added: u32,
+}
+
+fn load_profile(
+    conn: &Connection,
+    guild_id: i64,
+    user_id: i64,
+    include_private: bool,
+) -> Result<Profile, rusqlite::Error> {
+    let count =
+        |query: &str, query_params: &[&dyn rusqlite::ToSql]| -> Result<u32, rusqlite::Error> {
+            conn.query_row(query, query_params, |row| row.get(0))
+        };
+
+    let mut stmt = conn.prepare(
+        "SELECT s.title, r.score FROM ratings r
+         JOIN songs s ON s.id = r.song_id
+         WHERE r.user_id = ?1
+         ORDER BY r.score DESC, r.rated_at DESC
+         LIMIT ?2",
+    )?;
+    let top_rated = stmt
+        .query_map(params![user_id, TOP_RATED], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT name FROM playlists
+         WHERE guild_id = ?1 AND owner_id = ?2 AND (?3 OR visibility != 'private')
+         ORDER BY created_at DESC",
+    )?;
+    let playlists = stmt
+        .query_map(params![guild_id, user_id, include_private], |row| {
+            row.get(0)
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let (games_played, games_won, points) = conn.query_row(
+        "SELECT COALESCE(SUM(games), 0), COALESCE(SUM(won), 0), COALESCE(SUM(points), 0)
+         FROM points_ledger
+         WHERE guild_id = ?1 AND user_id = ?2",
+        params![guild_id, user_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )?;
+
+    Ok(Profile {
+        favorites: count(
+            "SELECT COUNT(*) FROM favorites WHERE user_id = ?1",
+            params![user_id],
+        )?,
+        ratings: count(
+            "SELECT COUNT(*) FROM ratings WHERE user_id = ?1",
+            params![user_id],
+        )?,
+        top_rated,
+        playlists,
+        games_played,
+        games_won,
+        points,
+        achievements: count(
+            "SELECT COUNT(*) FROM achievements WHERE guild_id = ?1 AND user_id = ?2",
+            params![guild_id, user_id],
+        )?,
+        suggestions_accepted: count(
+            "SELECT COUNT(*) FROM song_suggestions
+             WHERE guild_id = ?1 AND user_id = ?2 AND status = 'approved'",
+            params![guild_id, user_id],
+        )?,
+        edits_accepted: count(
+            "SELECT COUNT(*) FROM edit_proposals
+             WHERE guild_id = ?1 AND user_id = ?2 AND status = 'accepted'",
+            params![guild_id, user_id],
+        )?,
+        songs_added: count(
+            "SELECT COUNT(*) FROM songs WHERE added_by = ?1",
+            params![user_id],
+        )?,
+    })
+}
+
+/// Show a summary of someone's activity
+#[poise::command(prefix_command, slash_command)]
+pub async fn profile(
+    ctx: Context<'_>,
+    #[description = "Whose profile to show (yours by default)"] user: Option<serenity::User>,
+) -> Result<(), Error> {
+    let user = user.as_ref().unwrap_or_else(|| ctx.author());
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get() as i64);
+    let user_id = user.id.get() as i64;
+    // Private playlists only show up on your own profile
+    let include_private = user.id == ctx.author().id;
+    let db = ctx.data().db.clone();
+
+    let profile = tokio::task::spawn_blocking(move || {
+        let db_lock = db.blocking_lock();
+        load_profile(&db_lock, guild_id, user_id, include_private)
+    })
+    .await??;
+
+    let top_rated = if profile.top_rated.is_empty() {
+        "No ratings yet".to_string()
+    } else {
+        profile
+            .top_rated
+            .iter()
+            .map(|(title, score)| format!("★ {}/10 — {}", score, title))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let playlists = if profile.playlists.is_empty() {
+        "None".to_string()
+    } else {
+        let mut names = profile
+            .playlists
+            .iter()
+            .take(MAX_PLAYLISTS)
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(", ");
+        if profile.playlists.len() > MAX_PLAYLISTS {
+            names.push_str(&format!(
+                " and {} more",
+                profile.playlists.len() - MAX_PLAYLISTS
+            ));
+        }
+        names
+    };
+
+    let mut profile_embed = serenity::CreateEmbed::new()
+        .title(format!("{}'s Profile", user.name))
+        .color(Color::BLUE)
+        .thumbnail(user.face())
+        .field("Favorites", format!("♥ {}", profile.favorites), true)
+        .field("Ratings", profile.ratings.to_string(), true)
+        .field("Points", profile.points.to_string(), true)
+        .field(
+            "Games",
+            format!("{} played, {} won", profile.games_played, profile.games_won),
+            true,
+        )
+        .field("Achievements", profile.achievements.to_string(), true)
+        .field(
+            "Contributions",
+            format!(
+                "{} suggestion(s), {} edit(s) accepted",
+                profile.suggestions_accepted, profile.edits_accepted
+            ),
+            true,
+        )
+        .field("Top rated", top_rated, false)
+        .field(
+            format!("Playlists ({})", profile.playlists.len()),
+            playlists,
+            false,
+        );
+
+    // Only moderators add songs, so don't show an empty field for everyone else
+    if profile.songs_added > 0 {
+        profile_embed = profile_embed.field("Songs added", profile.songs_added.to_string(), true);
+    }
+
+    ctx.send(poise::CreateReply::default().embed(profile_embed))
+        .await?;
+
+    Ok(())
+}
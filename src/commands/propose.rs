@@ -0,0 +1,423 @@
+use crate::{
+    Context, Data, Error, checks,
+    review::{self, ReviewOutcome},
+};
+use poise::serenity_prelude::{self as serenity, Color, Mentionable};
+use rusqlite::{Connection, OptionalExtension, params};
+
+const REVIEW_PREFIX: &str = "edit-review:";
+/// Columns read by `proposal_from_row`
+const PROPOSAL_COLUMNS: &str =
+    "id, song_id, user_id, channel_id, old_title, old_album, new_title, new_album, reason";
+
+/// A proposed change to a song, with the values it had when the proposal was made
+struct Proposal {
+    id: i64,
+    song_id: i32,
+    user_id: u64,
+    channel_id: u64,
+    old_title: String,
+    old_album: String,
+    new_title: Option<String>,
+    new_album: Option<String>,
+    reason: Option<String>,
+}
+
+fn proposal_from_row(row: &rusqlite::Row) -> Result<Proposal, rusqlite::Error> {
+    Ok(Proposal {
+        id: row.get(0)?,
+        song_id: row.get(1)?,
+        user_id: row.get::<_, i64>(2)? as u64,
+        channel_id: row.get::<_, i64>(3)? as u64,
+        old_title: row.get(4)?,
+        old_album: row.get(5)?,
+        new_title: row.get(6)?,
+        new_album: row.get(7)?,
+        reason: row.get(8)?,
+    })
+}
+
+/// What happened to a proposal after a moderator reviewed it
+enum Review {
+    Accepted,
+    /// The song was changed or deleted since the proposal, or the new title is taken
+    Conflict(String),
+    Rejected,
+}
+
+/// Oldest pending proposal of the guild and the number of pending proposals
+fn next_pending(
+    conn: &Connection,
+    guild_id: i64,
+) -> Result<(Option<Proposal>, u32), rusqlite::Error> {
+    review::next_pending(
+        conn,
+        "edit_proposals",
+        PROPOSAL_COLUMNS,
+        guild_id,
+        proposal_from_row,
+    )
+}
+
+/// Formats one field of the before/after view
+fn diff_line(old: &str, new: Option<&str>) -> String {
+    match new {
+        Some(new) => format!("~~{}~~ → **{}**", old, new),
+        None => format!("{} (unchanged)", old),
+    }
+}
+
+fn review_embed(proposal: Option<&Proposal>, pending: u32) -> serenity::CreateEmbed {
+    let Some(proposal) = proposal else {
+        return serenity::CreateEmbed::new()
+            .title("Edit Proposals")
+            .description("There are no pending edit proposals.");
+    };
+
+    serenity::CreateEmbed::new()
+        .title("Edit Proposal")
+        .color(Color::BLUE)
+        .field(
+            "Title",
+            diff_line(&proposal.old_title, proposal.new_title.as_deref()),
+            false,
+        )
+        .field(
+            "Album",
+            diff_line(&proposal.old_album, proposal.new_album.as_deref()),
+            false,
+        )
+        .field(
+            "Reason",
+            proposal.reason.as_deref().unwrap_or("No reason given"),
+            false,
+        )
+        .field(
+            "Proposed by",
+            serenity::UserId::new(proposal.user_id)
+                .mention()
+                .to_string(),
+            false,
+        )
+        .footer(serenity::CreateEmbedFooter::new(format!(
+            "Proposal #{} • Song ID: {} • {} pending",
+            proposal.id, proposal.song_id, pending
+        )))
+}
+
+fn review_buttons(proposal: Option<&Proposal>) -> Vec<serenity::CreateActionRow> {
+    review::review_buttons(
+        REVIEW_PREFIX,
+        proposal.map(|proposal| proposal.id),
+        "Accept",
+    )
+}
+
+/// Whether another song already uses `title`
+fn title_taken(conn: &Connection, title: &str, song_id: i32) -> Result<bool, rusqlite::Error> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM songs WHERE UPPER(title) = UPPER(?1) AND id != ?2",
+        params![title, song_id],
+        |row| row.get(0),
+    )
+}
+
+/// Applies an accepted proposal, unless the song changed since it was proposed
+fn apply_proposal(conn: &Connection, proposal: &Proposal) -> Result<Review, rusqlite::Error> {
+    let current: Option<(String, String)> = conn
+        .query_row(
+            "SELECT title, album FROM songs WHERE id = ?1",
+            params![proposal.song_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+
+    let Some((title, album)) = current else {
+        return Ok(Review::Conflict("The song has been deleted.".to_string()));
+    };
+    if title != proposal.old_title || album != proposal.old_album {
+        return Ok(Review::Conflict(
+            "The song has been changed since the proposal was made.".to_string(),
+        ));
+    }
+    if let Some(new_title) = &proposal.new_title
+        && title_taken(conn, new_title, proposal.song_id)?
+    {
+        return Ok(Review::Conflict(format!(
+            "Another song is already called '{}'.",
+            new_title
+        )));
+    }
+
+    conn.execute(
+        "UPDATE songs SET title = COALESCE(?2, title), album = COALESCE(?3, album) WHERE id = ?1",
+        params![proposal.song_id, proposal.new_title, proposal.new_album],
+    )?;
+
+    Ok(Review::Accepted)
+}
+
+/// Propose a fix to a song's title or album
+#[poise::command(prefix_command, slash_command, rename = "propose-edit")]
+pub async fn propose_edit(
+    ctx: Context<'_>,
+    #[autocomplete = "crate::autocomplete::autocomplete_song"]
+    #[description = "The song to fix"]
+    song_id: i32,
+    #[description = "The corrected title"] title: Option<String>,
+    #[autocomplete = "crate::commands::new::autocomplete_album"]
+    #[description = "The correct album"]
+    album: Option<String>,
+    #[description = "Why the song should be changed"] reason: Option<String>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get() as i64);
+    let channel_id = ctx.channel_id().get() as i64;
+    let user_id = ctx.author().id.get() as i64;
+    let title = title
+        .map(|title| title.trim().to_string())
+        .filter(|title| !title.is_empty());
+    let album = album
+        .map(|album| album.trim().to_string())
+        .filter(|album| !album.is_empty());
+    let db = ctx.data().db.clone();
+
+    let outcome =
+        tokio::task::spawn_blocking(move || -> Result<Result<i64, String>, rusqlite::Error> {
+            let db_lock = db.blocking_lock();
+
+            let current: Option<(String, String)> = db_lock
+                .query_row(
+                    "SELECT title, album FROM songs WHERE id = ?1",
+                    params![song_id],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .optional()?;
+            let Some((old_title, old_album)) = current else {
+                return Ok(Err(format!("No song found with ID: {}", song_id)));
+            };
+
+            // Only store the fields that actually change
+            let new_title = title.filter(|title| *title != old_title);
+            let new_album = album.filter(|album| *album != old_album);
+            if new_title.is_none() && new_album.is_none() {
+                return Ok(Err(
+                    "Give a new title or album that differs from the current one.".to_string(),
+                ));
+            }
+
+            if let Some(new_title) = &new_title
+                && title_taken(&db_lock, new_title, song_id)?
+            {
+                return Ok(Err(format!(
+                    "Another song is already called '{}'.",
+                    new_title
+                )));
+            }
+
+            db_lock.execute(
+                "INSERT INTO edit_proposals (guild_id, song_id, user_id, channel_id, old_title, old_album,
+                    new_title, new_album, reason, status, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, 'pending', unixepoch())",
+                params![
+                    guild_id, song_id, user_id, channel_id, old_title, old_album, new_title,
+                    new_album, reason
+                ],
+            )?;
+
+            Ok(Ok(db_lock.last_insert_rowid()))
+        })
+        .await??;
+
+    let embed = match outcome {
+        Ok(proposal_id) => serenity::CreateEmbed::new()
+            .title("Success")
+            .color(Color::DARK_GREEN)
+            .description(
+                "Your edit has been sent to the moderators. You'll be told once it's reviewed.",
+            )
+            .footer(serenity::CreateEmbedFooter::new(format!(
+                "Proposal #{}",
+                proposal_id
+            ))),
+        Err(message) => serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description(message),
+    };
+
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+
+    Ok(())
+}
+
+/// Review pending edit proposals
+#[poise::command(
+    prefix_command,
+    slash_command,
+    rename = "edit-proposals",
+    check = "crate::checks::check_is_moderator"
+)]
+pub async fn edit_proposals(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get() as i64);
+    let db = ctx.data().db.clone();
+
+    let (proposal, pending) = tokio::task::spawn_blocking(move || {
+        let db_lock = db.blocking_lock();
+        next_pending(&db_lock, guild_id)
+    })
+    .await??;
+
+    ctx.send(
+        poise::CreateReply::default()
+            .embed(review_embed(proposal.as_ref(), pending))
+            .components(review_buttons(proposal.as_ref())),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Handles Accept/Reject clicks from the review queue, then shows the next proposal
+pub async fn handle_review(
+    ctx: &serenity::Context,
+    interaction: &serenity::ComponentInteraction,
+    data: &Data,
+) -> Result<(), Error> {
+    let Some((proposal_id, action)) =
+        review::parse_review_button(&interaction.data.custom_id, REVIEW_PREFIX)
+    else {
+        return Ok(());
+    };
+    let accept = action == "accept";
+
+    if !checks::check_interaction_moderator(ctx, interaction, data).await? {
+        return Ok(());
+    }
+
+    let guild_id = interaction.guild_id.map_or(0, |id| id.get() as i64);
+    let reviewer_id = interaction.user.id.get() as i64;
+    let db = data.db.clone();
+
+    let (reviewed, next, pending) = tokio::task::spawn_blocking(
+        move || -> Result<ReviewOutcome<Proposal, Review>, rusqlite::Error> {
+            let db_lock = db.blocking_lock();
+            let tx = db_lock.unchecked_transaction()?;
+
+            // Another moderator may have reviewed it in the meantime
+            let proposal = tx
+                .query_row(
+                    &format!(
+                        "SELECT {} FROM edit_proposals
+                         WHERE id = ?1 AND guild_id = ?2 AND status = 'pending'",
+                        PROPOSAL_COLUMNS
+                    ),
+                    params![proposal_id, guild_id],
+                    proposal_from_row,
+                )
+                .optional()?;
+
+            let reviewed = match proposal {
+                Some(proposal) => {
+                    let review = if accept {
+                        apply_proposal(&tx, &proposal)?
+                    } else {
+                        Review::Rejected
+                    };
+
+                    let status = match review {
+                        Review::Accepted => "accepted",
+                        Review::Conflict(_) | Review::Rejected => "rejected",
+                    };
+                    tx.execute(
+                        "UPDATE edit_proposals
+                         SET status = ?2, reviewed_by = ?3, reviewed_at = unixepoch()
+                         WHERE id = ?1",
+                        params![proposal.id, status, reviewer_id],
+                    )?;
+
+                    Some((proposal, review))
+                }
+                None => None,
+            };
+            tx.commit()?;
+
+            let (next, pending) = next_pending(&db_lock, guild_id)?;
+
+            Ok((reviewed, next, pending))
+        },
+    )
+    .await??;
+
+    // A changed album moves the song between cached album lists
+    if let Some((proposal, Review::Accepted)) = &reviewed
+        && proposal.new_album.is_some()
+    {
+        data.song_ids.invalidate();
+    }
+
+    let status = match &reviewed {
+        Some((proposal, Review::Accepted)) => format!(
+            "✅ Accepted proposal #{} by {}.",
+            proposal.id,
+            serenity::UserId::new(proposal.user_id).mention()
+        ),
+        Some((proposal, Review::Conflict(message))) => format!(
+            "⚠️ Couldn't apply proposal #{}: {} It was rejected.",
+            proposal.id, message
+        ),
+        Some((proposal, Review::Rejected)) => format!("❌ Rejected proposal #{}.", proposal.id),
+        None => "This proposal has already been reviewed.".to_string(),
+    };
+
+    interaction
+        .create_response(
+            ctx,
+            serenity::CreateInteractionResponse::UpdateMessage(
+                serenity::CreateInteractionResponseMessage::new()
+                    .content(status)
+                    .embed(review_embed(next.as_ref(), pending))
+                    .components(review_buttons(next.as_ref())),
+            ),
+        )
+        .await?;
+
+    if let Some((proposal, review)) = reviewed {
+        notify_proposer(ctx, &proposal, &review).await?;
+    }
+
+    Ok(())
+}
+
+/// Tells the member how their proposal was reviewed
+async fn notify_proposer(
+    ctx: &serenity::Context,
+    proposal: &Proposal,
+    review: &Review,
+) -> Result<(), serenity::Error> {
+    let outcome_embed = match review {
+        Review::Accepted => serenity::CreateEmbed::new()
+            .title("Edit Accepted")
+            .color(Color::DARK_GREEN)
+            .description(format!(
+                "Your edit to '{}' has been applied. Thanks for helping keep the list accurate!",
+                proposal.old_title
+            )),
+        Review::Conflict(message) => serenity::CreateEmbed::new()
+            .title("Edit Rejected")
+            .color(Color::RED)
+            .description(format!(
+                "Your edit to '{}' couldn't be applied. {}",
+                proposal.old_title, message
+            )),
+        Review::Rejected => serenity::CreateEmbed::new()
+            .title("Edit Rejected")
+            .color(Color::RED)
+            .description(format!(
+                "Your edit to '{}' was not applied.",
+                proposal.old_title
+            )),
+    };
+
+    review::notify_member(ctx, proposal.user_id, proposal.channel_id, outcome_embed).await
+}
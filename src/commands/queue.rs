@@ -0,0 +1,609 @@
+use crate::{Context, Error, positions};
+use poise::serenity_prelude::{self as serenity, Color, Mentionable};
+use rusqlite::{Connection, OptionalExtension, params};
+
+/// Requests a member can have waiting in the queue at once
+const MAX_REQUESTS_PER_USER: u32 = 3;
+/// Requests listed by `/queue show`
+const MAX_LISTED: usize = 20;
+
+struct QueuedSong {
+    position: u32,
+    song_id: i32,
+    title: String,
+    album: String,
+    user_id: u64,
+}
+
+/// Song that's currently playing, with where its embed was posted
+struct NowPlaying {
+    song: Option<QueuedSong>,
+    channel_id: Option<u64>,
+    message_id: Option<u64>,
+}
+
+/// Requests in queue order; deleted songs are left out
+fn load_queue(conn: &Connection, guild_id: i64) -> Result<Vec<QueuedSong>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT q.position, q.song_id, s.title, s.album, q.user_id FROM request_queue q
+         JOIN songs s ON s.id = q.song_id
+         WHERE q.guild_id = ?1
+         ORDER BY q.position",
+    )?;
+    let song_iter = stmt.query_map(params![guild_id], |row| {
+        Ok(QueuedSong {
+            position: row.get(0)?,
+            song_id: row.get(1)?,
+            title: row.get(2)?,
+            album: row.get(3)?,
+            user_id: row.get::<_, i64>(4)? as u64,
+        })
+    })?;
+
+    song_iter.collect()
+}
+
+fn load_now_playing(conn: &Connection, guild_id: i64) -> Result<NowPlaying, rusqlite::Error> {
+    let state = conn
+        .query_row(
+            "SELECT p.song_id, s.title, s.album, p.user_id, p.channel_id, p.message_id
+             FROM request_state p
+             LEFT JOIN songs s ON s.id = p.song_id
+             WHERE p.guild_id = ?1",
+            params![guild_id],
+            |row| {
+                let song = match (
+                    row.get::<_, Option<i32>>(0)?,
+                    row.get::<_, Option<String>>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                ) {
+                    (Some(song_id), Some(title), Some(album)) => Some(QueuedSong {
+                        position: 0,
+                        song_id,
+                        title,
+                        album,
+                        user_id: row.get::<_, Option<i64>>(3)?.unwrap_or_default() as u64,
+                    }),
+                    _ => None,
+                };
+
+                Ok(NowPlaying {
+                    song,
+                    channel_id: row.get::<_, Option<i64>>(4)?.map(|id| id as u64),
+                    message_id: row.get::<_, Option<i64>>(5)?.map(|id| id as u64),
+                })
+            },
+        )
+        .optional()?;
+
+    Ok(state.unwrap_or(NowPlaying {
+        song: None,
+        channel_id: None,
+        message_id: None,
+    }))
+}
+
+/// Removes the request at `position` and closes the gap it leaves
+fn take_request(
+    conn: &Connection,
+    guild_id: i64,
+    position: u32,
+) -> Result<Option<QueuedSong>, rusqlite::Error> {
+    let request = conn
+        .query_row(
+            "SELECT q.position, q.song_id, COALESCE(s.title, 'Deleted song'), COALESCE(s.album, ''),
+                q.user_id
+             FROM request_queue q
+             LEFT JOIN songs s ON s.id = q.song_id
+             WHERE q.guild_id = ?1 AND q.position = ?2",
+            params![guild_id, position],
+            |row| {
+                Ok(QueuedSong {
+                    position: row.get(0)?,
+                    song_id: row.get(1)?,
+                    title: row.get(2)?,
+                    album: row.get(3)?,
+                    user_id: row.get::<_, i64>(4)? as u64,
+                })
+            },
+        )
+        .optional()?;
+
+    if request.is_some() {
+        conn.execute(
+            "DELETE FROM request_queue WHERE guild_id = ?1 AND position = ?2",
+            params![guild_id, position],
+        )?;
+        conn.execute(
+            "UPDATE request_queue SET position = position - 1
+             WHERE guild_id = ?1 AND position > ?2",
+            params![guild_id, position],
+        )?;
+    }
+
+    Ok(request)
+}
+
+/// Drops a deleted song from every guild's queue
+pub fn remove_song_requests(conn: &Connection, song_id: i32) -> Result<(), rusqlite::Error> {
+    let mut stmt =
+        conn.prepare("SELECT guild_id, position FROM request_queue WHERE song_id = ?1")?;
+    let requests = stmt
+        .query_map(params![song_id], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for (guild_id, position) in requests {
+        take_request(conn, guild_id, position)?;
+    }
+
+    Ok(())
+}
+
+fn now_playing_embed(
+    now_playing: Option<&QueuedSong>,
+    queue: &[QueuedSong],
+) -> serenity::CreateEmbed {
+    let embed = match now_playing {
+        Some(song) => serenity::CreateEmbed::new()
+            .title("Now Playing")
+            .color(Color::PURPLE)
+            .description(format!("**{}** — {}", song.title, song.album))
+            .field(
+                "Requested by",
+                serenity::UserId::new(song.user_id).mention().to_string(),
+                true,
+            ),
+        None => serenity::CreateEmbed::new()
+            .title("Now Playing")
+            .description("Nothing is playing right now."),
+    };
+
+    let up_next = match queue.first() {
+        Some(next) => format!("{} — {}", next.title, next.album),
+        None => "Nothing, request a song with `/request`".to_string(),
+    };
+
+    embed
+        .field("Up next", up_next, true)
+        .footer(serenity::CreateEmbedFooter::new(format!(
+            "{} request(s) in the queue",
+            queue.len()
+        )))
+}
+
+/// Edits the "now playing" embed in place, posting a new one here if `post` is set and the
+/// old one is gone
+async fn update_now_playing(ctx: Context<'_>, post: bool) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get() as i64);
+    let db = ctx.data().db.clone();
+
+    let (now_playing, queue) = tokio::task::spawn_blocking(move || {
+        let db_lock = db.blocking_lock();
+        Ok::<_, rusqlite::Error>((
+            load_now_playing(&db_lock, guild_id)?,
+            load_queue(&db_lock, guild_id)?,
+        ))
+    })
+    .await??;
+
+    let embed = now_playing_embed(now_playing.song.as_ref(), &queue);
+
+    if let (Some(channel_id), Some(message_id)) = (now_playing.channel_id, now_playing.message_id) {
+        let edited = serenity::ChannelId::new(channel_id)
+            .edit_message(
+                ctx,
+                serenity::MessageId::new(message_id),
+                serenity::EditMessage::new().embed(embed.clone()),
+            )
+            .await;
+
+        if edited.is_ok() || !post {
+            return Ok(());
+        }
+    } else if !post {
+        return Ok(());
+    }
+
+    let message = ctx
+        .channel_id()
+        .send_message(ctx, serenity::CreateMessage::new().embed(embed))
+        .await?;
+
+    let channel_id = message.channel_id.get() as i64;
+    let message_id = message.id.get() as i64;
+    let db = ctx.data().db.clone();
+    tokio::task::spawn_blocking(move || {
+        let db_lock = db.blocking_lock();
+        db_lock.execute(
+            "INSERT INTO request_state (guild_id, channel_id, message_id) VALUES (?1, ?2, ?3)
+             ON CONFLICT(guild_id) DO UPDATE SET
+                channel_id = excluded.channel_id,
+                message_id = excluded.message_id",
+            params![guild_id, channel_id, message_id],
+        )
+    })
+    .await??;
+
+    Ok(())
+}
+
+/// Request a song to be played on stream
+#[poise::command(prefix_command, slash_command)]
+pub async fn request(
+    ctx: Context<'_>,
+    #[autocomplete = "crate::autocomplete::autocomplete_song"]
+    #[description = "The song to request"]
+    song_id: i32,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get() as i64);
+    let user_id = ctx.author().id.get() as i64;
+    let db = ctx.data().db.clone();
+
+    let outcome = tokio::task::spawn_blocking(
+        move || -> Result<Result<(String, u32), String>, rusqlite::Error> {
+            let db_lock = db.blocking_lock();
+
+            let title: Option<String> = db_lock
+                .query_row(
+                    "SELECT title FROM songs WHERE id = ?1",
+                    params![song_id],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            let Some(title) = title else {
+                return Ok(Err(format!("No song found with ID: {}", song_id)));
+            };
+
+            let already_queued: bool = db_lock.query_row(
+                "SELECT COUNT(*) FROM request_queue WHERE guild_id = ?1 AND song_id = ?2",
+                params![guild_id, song_id],
+                |row| row.get(0),
+            )?;
+            if already_queued {
+                return Ok(Err(format!("'{}' is already in the queue.", title)));
+            }
+
+            let user_requests: u32 = db_lock.query_row(
+                "SELECT COUNT(*) FROM request_queue WHERE guild_id = ?1 AND user_id = ?2",
+                params![guild_id, user_id],
+                |row| row.get(0),
+            )?;
+            if user_requests >= MAX_REQUESTS_PER_USER {
+                return Ok(Err(format!(
+                    "You already have {} requests in the queue. Wait for one to be played first.",
+                    MAX_REQUESTS_PER_USER
+                )));
+            }
+
+            let position = db_lock.query_row(
+                "INSERT INTO request_queue (guild_id, song_id, user_id, position, requested_at)
+                 SELECT ?1, ?2, ?3, COALESCE(MAX(position), 0) + 1, unixepoch()
+                 FROM request_queue WHERE guild_id = ?1
+                 RETURNING position",
+                params![guild_id, song_id, user_id],
+                |row| row.get(0),
+            )?;
+
+            Ok(Ok((title, position)))
+        },
+    )
+    .await??;
+
+    let embed = match &outcome {
+        Ok((title, position)) => serenity::CreateEmbed::new()
+            .title("Success")
+            .color(Color::DARK_GREEN)
+            .description(format!(
+                "Requested '{}'. It's number {} in the queue.",
+                title, position
+            )),
+        Err(message) => serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description(message),
+    };
+
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+    if outcome.is_ok() {
+        update_now_playing(ctx, false).await?;
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    subcommands("show", "next", "skip", "clear", "remove", "move_request"),
+    subcommand_required
+)]
+
+/// Show or manage the stream request queue
+pub async fn queue(_: Context<'_>) -> Result<(), Error> {
+    // This will never be called, because `subcommand_required` parameter is set
+    Ok(())
+}
+
+/// Show the song requests in order
+#[poise::command(prefix_command, slash_command)]
+pub async fn show(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get() as i64);
+    let db = ctx.data().db.clone();
+
+    let (now_playing, queue) = tokio::task::spawn_blocking(move || {
+        let db_lock = db.blocking_lock();
+        Ok::<_, rusqlite::Error>((
+            load_now_playing(&db_lock, guild_id)?,
+            load_queue(&db_lock, guild_id)?,
+        ))
+    })
+    .await??;
+
+    let mut request_list = queue
+        .iter()
+        .take(MAX_LISTED)
+        .map(|song| {
+            format!(
+                "{}. {} — {} [ID: {}] requested by {}",
+                song.position,
+                song.title,
+                song.album,
+                song.song_id,
+                serenity::UserId::new(song.user_id).mention()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    if queue.is_empty() {
+        request_list = "The queue is empty. Request a song with `/request`.".to_string();
+    } else if queue.len() > MAX_LISTED {
+        request_list.push_str(&format!("\n…and {} more", queue.len() - MAX_LISTED));
+    }
+
+    let now_playing = match &now_playing.song {
+        Some(song) => format!(
+            "{} — {} (requested by {})",
+            song.title,
+            song.album,
+            serenity::UserId::new(song.user_id).mention()
+        ),
+        None => "Nothing".to_string(),
+    };
+
+    let queue_embed = serenity::CreateEmbed::new()
+        .title("Request Queue")
+        .color(Color::PURPLE)
+        .field("Now playing", now_playing, false)
+        .description(request_list)
+        .footer(serenity::CreateEmbedFooter::new(format!(
+            "{} request(s) • Up to {} per member",
+            queue.len(),
+            MAX_REQUESTS_PER_USER
+        )));
+
+    ctx.send(poise::CreateReply::default().embed(queue_embed))
+        .await?;
+
+    Ok(())
+}
+
+/// Play the next request and update the "now playing" embed
+#[poise::command(
+    prefix_command,
+    slash_command,
+    check = "crate::checks::check_is_moderator"
+)]
+pub async fn next(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get() as i64);
+    let db = ctx.data().db.clone();
+
+    let next =
+        tokio::task::spawn_blocking(move || -> Result<Option<QueuedSong>, rusqlite::Error> {
+            let db_lock = db.blocking_lock();
+            let tx = db_lock.unchecked_transaction()?;
+
+            let next = take_request(&tx, guild_id, 1)?;
+            tx.execute(
+                "INSERT INTO request_state (guild_id, song_id, user_id) VALUES (?1, ?2, ?3)
+             ON CONFLICT(guild_id) DO UPDATE SET
+                song_id = excluded.song_id,
+                user_id = excluded.user_id",
+                params![
+                    guild_id,
+                    next.as_ref().map(|song| song.song_id),
+                    next.as_ref().map(|song| song.user_id as i64)
+                ],
+            )?;
+            tx.commit()?;
+
+            Ok(next)
+        })
+        .await??;
+
+    let reply = match &next {
+        Some(song) => format!("Now playing '{}'.", song.title),
+        None => "The queue is empty, so nothing is playing now.".to_string(),
+    };
+    ctx.send(poise::CreateReply::default().content(reply).ephemeral(true))
+        .await?;
+
+    update_now_playing(ctx, true).await?;
+
+    Ok(())
+}
+
+/// Drop the next request without playing it
+#[poise::command(
+    prefix_command,
+    slash_command,
+    check = "crate::checks::check_is_moderator"
+)]
+pub async fn skip(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get() as i64);
+    let db = ctx.data().db.clone();
+
+    let skipped = tokio::task::spawn_blocking(move || {
+        let db_lock = db.blocking_lock();
+        let tx = db_lock.unchecked_transaction()?;
+        let skipped = take_request(&tx, guild_id, 1)?;
+        tx.commit()?;
+        Ok::<_, rusqlite::Error>(skipped)
+    })
+    .await??;
+
+    let embed = match skipped {
+        Some(song) => serenity::CreateEmbed::new()
+            .title("Success")
+            .color(Color::DARK_GREEN)
+            .description(format!(
+                "Skipped '{}' requested by {}.",
+                song.title,
+                serenity::UserId::new(song.user_id).mention()
+            )),
+        None => serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description("The queue is empty."),
+    };
+
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+    update_now_playing(ctx, false).await?;
+
+    Ok(())
+}
+
+/// Remove every request from the queue
+#[poise::command(
+    prefix_command,
+    slash_command,
+    check = "crate::checks::check_is_moderator"
+)]
+pub async fn clear(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get() as i64);
+    let db = ctx.data().db.clone();
+
+    let cleared = tokio::task::spawn_blocking(move || {
+        let db_lock = db.blocking_lock();
+        db_lock.execute(
+            "DELETE FROM request_queue WHERE guild_id = ?1",
+            params![guild_id],
+        )
+    })
+    .await??;
+
+    let clear_embed = serenity::CreateEmbed::new()
+        .title("Success")
+        .color(Color::DARK_GREEN)
+        .description(format!("Cleared {} request(s) from the queue.", cleared));
+
+    ctx.send(poise::CreateReply::default().embed(clear_embed))
+        .await?;
+
+    update_now_playing(ctx, false).await?;
+
+    Ok(())
+}
+
+/// Remove the request at a position
+#[poise::command(
+    prefix_command,
+    slash_command,
+    check = "crate::checks::check_is_moderator"
+)]
+pub async fn remove(
+    ctx: Context<'_>,
+    #[description = "Position of the request, as shown by /queue show"] position: u32,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get() as i64);
+    let db = ctx.data().db.clone();
+
+    let removed = tokio::task::spawn_blocking(move || {
+        let db_lock = db.blocking_lock();
+        let tx = db_lock.unchecked_transaction()?;
+        let removed = take_request(&tx, guild_id, position)?;
+        tx.commit()?;
+        Ok::<_, rusqlite::Error>(removed)
+    })
+    .await??;
+
+    let embed = match removed {
+        Some(song) => serenity::CreateEmbed::new()
+            .title("Success")
+            .color(Color::DARK_GREEN)
+            .description(format!(
+                "Removed '{}' from position {}.",
+                song.title, song.position
+            )),
+        None => serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description(format!("There's no request at position {}.", position)),
+    };
+
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+    update_now_playing(ctx, false).await?;
+
+    Ok(())
+}
+
+/// Move a request to another position
+#[poise::command(
+    prefix_command,
+    slash_command,
+    rename = "move",
+    check = "crate::checks::check_is_moderator"
+)]
+pub async fn move_request(
+    ctx: Context<'_>,
+    #[description = "Current position of the request"] from: u32,
+    #[description = "Position to move it to"] to: u32,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get() as i64);
+    let db = ctx.data().db.clone();
+
+    let moved = tokio::task::spawn_blocking(move || -> Result<Result<(), u32>, rusqlite::Error> {
+        let db_lock = db.blocking_lock();
+
+        let count: u32 = db_lock.query_row(
+            "SELECT COUNT(*) FROM request_queue WHERE guild_id = ?1",
+            params![guild_id],
+            |row| row.get(0),
+        )?;
+        if from == 0 || to == 0 || from > count || to > count {
+            return Ok(Err(count));
+        }
+
+        let tx = db_lock.unchecked_transaction()?;
+
+        positions::move_position(&tx, "request_queue", "guild_id", guild_id, from, to)?;
+        tx.commit()?;
+
+        Ok(Ok(()))
+    })
+    .await??;
+
+    let embed = match moved {
+        Ok(()) => serenity::CreateEmbed::new()
+            .title("Success")
+            .color(Color::DARK_GREEN)
+            .description(format!(
+                "Moved the request at position {} to position {}.",
+                from, to
+            )),
+        Err(count) => serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description(format!("Positions in the queue go from 1 to {}.", count)),
+    };
+
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+    update_now_playing(ctx, false).await?;
+
+    Ok(())
+}
@@ -1,6 +1,15 @@
-use crate::{Context, Error};
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    Context, Error,
+    commands::fav,
+    sampling::{self, SongIdCache},
+};
+use poise::ChoiceParameter;
 use poise::serenity_prelude::{self as serenity, Color};
-use rusqlite::{Row, params};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rusqlite::{Connection, OptionalExtension, params};
 
 #[derive(Debug, poise::ChoiceParameter)]
 pub enum Album {
@@ -12,24 +21,475 @@ pub enum Album {
     Covers,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, poise::ChoiceParameter)]
+pub enum Mode {
+    #[name = "Uniform"]
+    Uniform,
+    #[name = "Favorites-weighted"]
+    FavoritesWeighted,
+    #[name = "Least recent"]
+    LeastRecent,
+    #[name = "Rating-weighted"]
+    RatingWeighted,
+    #[name = "My favorites"]
+    MyFavorites,
+    #[name = "Tag-weighted"]
+    TagWeighted,
+}
+
 #[derive(Debug)]
 struct Song {
-    id: i32,
     title: String,
     album: String,
+    favorites: u32,
+}
+
+/// Stored data the weighted modes draw from
+#[derive(Default)]
+struct SongStats {
+    favorites: u32,
+    average_rating: Option<f64>,
+    last_picked: Option<i64>,
+    /// Whether the song has the tag a tag-weighted draw favors
+    tagged: bool,
+}
+
+/// How many times likelier a tagged song is to be picked in tag-weighted mode
+const TAG_WEIGHT: f64 = 5.0;
+
+/// Pick weight of a song under the given mode
+fn song_weight(mode: Mode, stats: &SongStats, now: i64) -> f64 {
+    match mode {
+        Mode::Uniform | Mode::MyFavorites => 1.0,
+        // Every song keeps a base chance, each favorite adds another one
+        Mode::FavoritesWeighted => 1.0 + f64::from(stats.favorites),
+        // Weight grows with the days since the last pick, capped at 30 days
+        Mode::LeastRecent => match stats.last_picked {
+            Some(picked_at) => 1.0 + ((now - picked_at).max(0) as f64 / 86400.0).min(30.0),
+            None => 31.0,
+        },
+        // Unrated songs count as a neutral 5 out of 10
+        Mode::RatingWeighted => stats.average_rating.unwrap_or(5.0),
+        // Untagged songs can still come up, just less often
+        Mode::TagWeighted => {
+            if stats.tagged {
+                TAG_WEIGHT
+            } else {
+                1.0
+            }
+        }
+    }
+}
+
+/// No-repeat window used when drawing random songs for a guild
+struct RepeatSettings {
+    per_channel: bool,
+    picks: u32,
+    days: u32,
+}
+
+impl Default for RepeatSettings {
+    fn default() -> Self {
+        Self {
+            per_channel: false,
+            picks: 10,
+            days: 0,
+        }
+    }
+}
+
+fn load_settings(conn: &Connection, guild_id: i64) -> Result<RepeatSettings, rusqlite::Error> {
+    let settings = conn
+        .query_row(
+            "SELECT per_channel, repeat_picks, repeat_days FROM random_settings WHERE guild_id = ?1",
+            params![guild_id],
+            |row| {
+                Ok(RepeatSettings {
+                    per_channel: row.get(0)?,
+                    picks: row.get(1)?,
+                    days: row.get(2)?,
+                })
+            },
+        )
+        .optional()?;
+
+    Ok(settings.unwrap_or_default())
+}
+
+/// What a draw asked for and how much of the pick history it could see.
+///
+/// Only this is stored with the seed. A replay recomputes the draw's inputs from it and
+/// compares them against the fingerprint stored alongside.
+struct DrawRequest {
+    mode: Mode,
+    amount: usize,
+    album: String,
+    tag: String,
+    /// Member whose favorites a my-favorites draw picks from
+    user_id: i64,
+    /// Channel the pick history is limited to; 0 matches every channel in the guild
+    channel_filter: i64,
+    picks: u32,
+    days: u32,
+    /// Newest pick history row when the draw was made
+    history_id: i64,
+    drawn_at: i64,
+}
+
+/// Returns the IDs of songs picked inside the no-repeat window of a draw, most recent first
+fn recent_picks(
+    conn: &Connection,
+    guild_id: i64,
+    request: &DrawRequest,
+) -> Result<Vec<i32>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT song_id FROM random_history
+         WHERE guild_id = ?1 AND (?2 = 0 OR channel_id = ?2) AND id <= ?5
+         AND (id IN (
+                SELECT id FROM random_history
+                WHERE guild_id = ?1 AND (?2 = 0 OR channel_id = ?2) AND id <= ?5
+                ORDER BY id DESC LIMIT ?3
+             )
+             OR (?4 > 0 AND picked_at >= ?6 - ?4 * 86400))
+         ORDER BY id DESC",
+    )?;
+
+    let id_iter = stmt.query_map(
+        params![
+            guild_id,
+            request.channel_filter,
+            request.picks,
+            request.days,
+            request.history_id,
+            request.drawn_at
+        ],
+        |row| row.get(0),
+    )?;
+
+    let mut ids = Vec::new();
+    for id in id_iter {
+        ids.push(id?);
+    }
+
+    Ok(ids)
+}
+
+/// Reads per-song stats for the weighted modes, only for songs that have any
+fn load_stats(
+    conn: &Connection,
+    guild_id: i64,
+    request: &DrawRequest,
+) -> Result<HashMap<i32, SongStats>, rusqlite::Error> {
+    let mut stats: HashMap<i32, SongStats> = HashMap::new();
+
+    let (query, query_params) = match request.mode {
+        Mode::Uniform | Mode::MyFavorites => return Ok(stats),
+        Mode::FavoritesWeighted => (
+            "SELECT song_id, COUNT(*) FROM favorites GROUP BY song_id",
+            params![],
+        ),
+        Mode::LeastRecent => (
+            "SELECT song_id, MAX(picked_at) FROM random_history
+             WHERE guild_id = ?1 AND (?2 = 0 OR channel_id = ?2) AND id <= ?3
+             GROUP BY song_id",
+            params![guild_id, request.channel_filter, request.history_id],
+        ),
+        Mode::RatingWeighted => (
+            "SELECT song_id, AVG(score) FROM ratings GROUP BY song_id",
+            params![],
+        ),
+        Mode::TagWeighted => (
+            "SELECT song_id, 1 FROM song_tags WHERE tag = ?1",
+            params![request.tag],
+        ),
+    };
+
+    let mut stmt = conn.prepare(query)?;
+    let mut rows = stmt.query(query_params)?;
+    while let Some(row) = rows.next()? {
+        let entry = stats.entry(row.get(0)?).or_default();
+        match request.mode {
+            Mode::FavoritesWeighted => entry.favorites = row.get(1)?,
+            Mode::LeastRecent => entry.last_picked = row.get(1)?,
+            Mode::RatingWeighted => entry.average_rating = row.get(1)?,
+            Mode::TagWeighted => entry.tagged = row.get(1)?,
+            Mode::Uniform | Mode::MyFavorites => {}
+        }
+    }
+
+    Ok(stats)
+}
+
+/// Pick weight of every ID in `ids`, empty for the modes that draw uniformly
+fn draw_weights(
+    conn: &Connection,
+    ids: &[i32],
+    guild_id: i64,
+    request: &DrawRequest,
+) -> Result<Vec<f64>, rusqlite::Error> {
+    if matches!(request.mode, Mode::Uniform | Mode::MyFavorites) {
+        return Ok(Vec::new());
+    }
+
+    let stats = load_stats(conn, guild_id, request)?;
+
+    Ok(ids
+        .iter()
+        .map(|id| {
+            song_weight(
+                request.mode,
+                stats.get(id).unwrap_or(&SongStats::default()),
+                request.drawn_at,
+            )
+        })
+        .collect())
+}
+
+/// Everything a draw picks from besides the seed
+struct DrawInputs {
+    amount: usize,
+    /// Sorted IDs the draw picks from, after the album and favorites filters
+    candidates: Vec<i32>,
+    /// Weight of each candidate, empty for the modes that draw uniformly
+    weights: Vec<f64>,
+    /// IDs inside the no-repeat window when the draw was made
+    recent: Vec<i32>,
+}
+
+impl DrawInputs {
+    fn pick<R: Rng>(&self, rng: &mut R) -> Vec<i32> {
+        let recent: HashSet<i32> = self.recent.iter().copied().collect();
+
+        if self.weights.is_empty() {
+            sampling::uniform_sample(&self.candidates, self.amount, &recent, rng)
+        } else {
+            sampling::weighted_sample_excluding(
+                &self.candidates,
+                &self.weights,
+                self.amount,
+                &recent,
+                rng,
+            )
+        }
+    }
+
+    /// FNV-1a hash of the inputs, so a replay can tell whether it still sees the same ones
+    fn fingerprint(&self) -> u64 {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let mut write = |bytes: &[u8]| {
+            for byte in bytes {
+                hash ^= u64::from(*byte);
+                hash = hash.wrapping_mul(0x0100_0000_01b3);
+            }
+        };
+
+        // Lengths keep values from shifting between the lists unnoticed
+        write(&(self.candidates.len() as u64).to_le_bytes());
+        for id in &self.candidates {
+            write(&id.to_le_bytes());
+        }
+        write(&(self.weights.len() as u64).to_le_bytes());
+        for weight in &self.weights {
+            write(&weight.to_bits().to_le_bytes());
+        }
+        write(&(self.recent.len() as u64).to_le_bytes());
+        for id in &self.recent {
+            write(&id.to_le_bytes());
+        }
+
+        hash
+    }
+}
+
+/// Candidates, weights and no-repeat window of a draw, read from the database as it is now
+fn draw_inputs(
+    conn: &Connection,
+    song_ids: &SongIdCache,
+    guild_id: i64,
+    request: &DrawRequest,
+) -> Result<DrawInputs, rusqlite::Error> {
+    let mut candidates = song_ids.get(conn, &request.album)?.to_vec();
+    if request.mode == Mode::MyFavorites {
+        let favorites: HashSet<i32> = fav::user_favorites(conn, request.user_id)?
+            .into_iter()
+            .collect();
+        candidates.retain(|id| favorites.contains(id));
+    }
+
+    Ok(DrawInputs {
+        amount: request.amount,
+        weights: draw_weights(conn, &candidates, guild_id, request)?,
+        recent: recent_picks(conn, guild_id, request)?,
+        candidates,
+    })
+}
+
+fn save_draw(
+    conn: &Connection,
+    guild_id: i64,
+    seed: u64,
+    request: &DrawRequest,
+    fingerprint: u64,
+) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "INSERT OR REPLACE INTO random_draws (guild_id, seed, mode, amount, album, tag, user_id,
+            channel_filter, repeat_picks, repeat_days, history_id, fingerprint, drawn_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+        params![
+            guild_id,
+            format!("{:016x}", seed),
+            request.mode.name(),
+            request.amount,
+            request.album,
+            request.tag,
+            request.user_id,
+            request.channel_filter,
+            request.picks,
+            request.days,
+            request.history_id,
+            format!("{:016x}", fingerprint),
+            request.drawn_at
+        ],
+    )?;
+
+    Ok(())
+}
+
+/// Reads a stored draw and the fingerprint of its inputs
+fn load_draw(
+    conn: &Connection,
+    guild_id: i64,
+    seed: u64,
+) -> Result<Option<(DrawRequest, u64)>, rusqlite::Error> {
+    conn.query_row(
+        "SELECT mode, amount, album, tag, user_id, channel_filter, repeat_picks, repeat_days,
+            history_id, fingerprint, drawn_at
+         FROM random_draws WHERE guild_id = ?1 AND seed = ?2",
+        params![guild_id, format!("{:016x}", seed)],
+        |row| {
+            let request = DrawRequest {
+                mode: Mode::from_name(&row.get::<_, String>(0)?).unwrap_or(Mode::Uniform),
+                amount: row.get(1)?,
+                album: row.get(2)?,
+                tag: row.get(3)?,
+                user_id: row.get(4)?,
+                channel_filter: row.get(5)?,
+                picks: row.get(6)?,
+                days: row.get(7)?,
+                history_id: row.get(8)?,
+                drawn_at: row.get(10)?,
+            };
+            // A malformed fingerprint never matches, so the replay reports changed inputs
+            let fingerprint = u64::from_str_radix(&row.get::<_, String>(9)?, 16).unwrap_or(0);
+
+            Ok((request, fingerprint))
+        },
+    )
+    .optional()
+}
+
+/// Mode of a draw and its songs in draw order
+type Drawn = (Mode, Vec<(i32, Option<Song>)>);
+
+/// Reads the picked songs in draw order; songs deleted since a draw come back as `None`
+fn load_songs(
+    conn: &Connection,
+    picked: &[i32],
+) -> Result<Vec<(i32, Option<Song>)>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT title, album, (SELECT COUNT(*) FROM favorites WHERE song_id = songs.id)
+         FROM songs WHERE id = ?1",
+    )?;
+
+    let mut songs = Vec::new();
+    for id in picked {
+        let song = stmt
+            .query_row(params![id], |row| {
+                Ok(Song {
+                    title: row.get(0)?,
+                    album: row.get(1)?,
+                    favorites: row.get(2)?,
+                })
+            })
+            .optional()?;
+        songs.push((*id, song));
+    }
+
+    Ok(songs)
 }
 
-/// Gets a single or multiple random songs
 #[poise::command(
-    slash_command,
     prefix_command,
+    slash_command,
+    subcommands("pick", "config", "reset_history"),
     check = "crate::checks::check_is_moderator"
 )]
+
+/// Draw random songs or manage the no-repeat history
+///
+/// Slash commands can't invoke a parent command, but `~random` without a subcommand
+/// still draws songs like it did before `pick` existed.
 pub async fn random(
     ctx: Context<'_>,
     #[description = "Select an album to get a random song from"] album: Option<Album>,
     #[description = "Amount of songs to get"] amount: Option<u32>,
+    #[description = "How songs are weighted (uniform by default)"] mode: Option<Mode>,
+    #[description = "Tag to favor in tag-weighted mode"] tag: Option<String>,
+    #[description = "Seed of a previous draw to reproduce it"] seed: Option<String>,
 ) -> Result<(), Error> {
+    draw(ctx, album, amount, mode, tag, seed).await
+}
+
+/// Gets a single or multiple random songs
+#[poise::command(prefix_command, slash_command)]
+pub async fn pick(
+    ctx: Context<'_>,
+    #[description = "Select an album to get a random song from"] album: Option<Album>,
+    #[description = "Amount of songs to get"] amount: Option<u32>,
+    #[description = "How songs are weighted (uniform by default)"] mode: Option<Mode>,
+    #[description = "Tag to favor in tag-weighted mode"] tag: Option<String>,
+    #[description = "Seed of a previous draw to reproduce it"] seed: Option<String>,
+) -> Result<(), Error> {
+    draw(ctx, album, amount, mode, tag, seed).await
+}
+
+async fn draw(
+    ctx: Context<'_>,
+    album: Option<Album>,
+    amount: Option<u32>,
+    mode: Option<Mode>,
+    tag: Option<String>,
+    seed: Option<String>,
+) -> Result<(), Error> {
+    let mode = mode.unwrap_or(Mode::Uniform);
+    let tag = tag.map(|tag| tag.trim().to_lowercase()).unwrap_or_default();
+
+    if mode == Mode::TagWeighted && tag.is_empty() {
+        let missing_tag_embed = serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description("Tag-weighted picks need a `tag`.");
+        ctx.send(poise::CreateReply::default().embed(missing_tag_embed))
+            .await?;
+        return Ok(());
+    }
+
+    let replay = seed.is_some();
+    let seed = match seed {
+        Some(seed) => match u64::from_str_radix(seed.trim(), 16) {
+            Ok(seed) => seed,
+            Err(_) => {
+                let invalid_seed_embed = serenity::CreateEmbed::new()
+                    .title("Error")
+                    .color(Color::RED)
+                    .description(format!("'{}' is not a valid seed.", seed));
+                ctx.send(poise::CreateReply::default().embed(invalid_seed_embed))
+                    .await?;
+                return Ok(());
+            }
+        },
+        None => rand::random(),
+    };
     let album_str = match album {
         Some(Album::SinglesBSides) => "Singles/B-Sides",
         Some(Album::LiveSongs) => "Live Songs",
@@ -38,43 +498,96 @@ pub async fn random(
     }
     .to_string();
 
-    let amount_to_query = amount.unwrap_or(1).min(25) as i32; // prevent overly large queries
+    let amount_to_query = amount.unwrap_or(1).min(25) as usize; // prevent overly large queries
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get() as i64);
+    let channel_id = ctx.channel_id().get() as i64;
+    let user_id = ctx.author().id.get() as i64;
     let db = ctx.data().db.clone();
+    let song_ids = ctx.data().song_ids.clone();
 
-    let songs = tokio::task::spawn_blocking(move || {
-        let db_lock = db.blocking_lock();
+    let drawn = tokio::task::spawn_blocking(
+        move || -> Result<Result<Drawn, String>, rusqlite::Error> {
+            let db_lock = db.blocking_lock();
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
 
-        let mut stmt = if album_str.is_empty() {
-            db_lock.prepare("SELECT * FROM songs ORDER BY RANDOM() LIMIT ?1")?
-        } else {
-            db_lock.prepare("SELECT * FROM songs WHERE album = ?1 ORDER BY RANDOM() LIMIT ?2")?
-        };
+            // Replays recompute the draw and leave the pick history alone
+            if replay {
+                let Some((request, fingerprint)) = load_draw(&db_lock, guild_id, seed)? else {
+                    return Ok(Err(format!(
+                        "No draw with seed `{:016x}` was made in this server.",
+                        seed
+                    )));
+                };
+                let inputs = draw_inputs(&db_lock, &song_ids, guild_id, &request)?;
+                if inputs.fingerprint() != fingerprint {
+                    return Ok(Err(
+                        "The songs, favorites, ratings, tags or pick history this draw used have changed since, so it can't be replayed."
+                            .to_string(),
+                    ));
+                }
+                let songs = load_songs(&db_lock, &inputs.pick(&mut rng))?;
+                return Ok(Ok((request.mode, songs)));
+            }
 
-        let map_row = |row: &Row<'_>| {
-            Ok(Song {
-                id: row.get(0)?,
-                title: row.get(1)?,
-                album: row.get(2)?,
-            })
-        };
+            let settings = load_settings(&db_lock, guild_id)?;
+            let request = DrawRequest {
+                mode,
+                amount: amount_to_query,
+                album: album_str,
+                tag,
+                user_id,
+                channel_filter: if settings.per_channel { channel_id } else { 0 },
+                picks: settings.picks,
+                days: settings.days,
+                history_id: db_lock.query_row(
+                    "SELECT COALESCE(MAX(id), 0) FROM random_history",
+                    [],
+                    |row| row.get(0),
+                )?,
+                drawn_at: db_lock.query_row("SELECT unixepoch()", [], |row| row.get(0))?,
+            };
 
-        let song_iter = if album_str.is_empty() {
-            stmt.query_map(params![amount_to_query], map_row)?
-        } else {
-            stmt.query_map(params![album_str, amount_to_query], map_row)?
-        };
+            let inputs = draw_inputs(&db_lock, &song_ids, guild_id, &request)?;
+            let picked = inputs.pick(&mut rng);
 
-        // Collect all the songs
-        let mut songs = Vec::new();
-        for song_result in song_iter {
-            songs.push(song_result?);
-        }
+            if !picked.is_empty() {
+                save_draw(&db_lock, guild_id, seed, &request, inputs.fingerprint())?;
+            }
 
-        Ok::<_, rusqlite::Error>(songs)
-    })
+            let mut insert = db_lock.prepare(
+                "INSERT INTO random_history (guild_id, channel_id, song_id, picked_at)
+                 VALUES (?1, ?2, ?3, unixepoch())",
+            )?;
+            for id in &picked {
+                insert.execute(params![guild_id, channel_id, id])?;
+            }
+
+            Ok(Ok((mode, load_songs(&db_lock, &picked)?)))
+        },
+    )
     .await??;
 
-    if songs.is_empty() {
+    let (mode, songs) = match drawn {
+        Ok(drawn) => drawn,
+        Err(message) => {
+            let replay_error_embed = serenity::CreateEmbed::new()
+                .title("Error")
+                .color(Color::RED)
+                .description(message);
+            ctx.send(poise::CreateReply::default().embed(replay_error_embed))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    if songs.is_empty() && mode == Mode::MyFavorites {
+        let no_favorites_embed = serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description("You haven't favorited any matching songs yet. Add some with `/fav add`.");
+        ctx.send(poise::CreateReply::default().embed(no_favorites_embed))
+            .await?;
+    } else if songs.is_empty() {
         let song_empty_embed = serenity::CreateEmbed::new()
             .title("Error")
             .color(Color::RED)
@@ -85,14 +598,30 @@ pub async fn random(
         let formatted = songs
             .iter()
             .enumerate()
-            .map(|(i, s)| format!("{}. {} — {} [ID: {}]", i + 1, s.title, s.album, s.id))
+            .map(|(i, (id, song))| match song {
+                Some(s) => format!(
+                    "{}. {} — {} [ID: {}] ♥ {}",
+                    i + 1,
+                    s.title,
+                    s.album,
+                    id,
+                    s.favorites
+                ),
+                None => format!("{}. ~~Deleted song~~ [ID: {}]", i + 1, id),
+            })
             .collect::<Vec<_>>()
             .join("\n");
 
         let random_song_embed = serenity::CreateEmbed::new()
             .title("Random Result(s)")
             .color(Color::BLUE)
-            .description(formatted);
+            .description(formatted)
+            .footer(serenity::CreateEmbedFooter::new(format!(
+                "Mode: {} • Seed: {:016x}{}",
+                mode.name(),
+                seed,
+                if replay { " • Replay" } else { "" }
+            )));
 
         ctx.send(poise::CreateReply::default().embed(random_song_embed))
             .await?;
@@ -100,3 +629,196 @@ pub async fn random(
 
     Ok(())
 }
+
+/// Configure the no-repeat window for random picks
+#[poise::command(prefix_command, slash_command)]
+pub async fn config(
+    ctx: Context<'_>,
+    #[description = "Don't repeat any of the last N picks"] picks: Option<u32>,
+    #[description = "Don't repeat songs picked in the last N days"] days: Option<u32>,
+    #[description = "Track history per channel instead of per server"] per_channel: Option<bool>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get() as i64);
+    let db = ctx.data().db.clone();
+
+    let settings =
+        tokio::task::spawn_blocking(move || -> Result<RepeatSettings, rusqlite::Error> {
+            let db_lock = db.blocking_lock();
+
+            let mut settings = load_settings(&db_lock, guild_id)?;
+            settings.picks = picks.unwrap_or(settings.picks);
+            settings.days = days.unwrap_or(settings.days);
+            settings.per_channel = per_channel.unwrap_or(settings.per_channel);
+
+            db_lock.execute(
+                "INSERT INTO random_settings (guild_id, per_channel, repeat_picks, repeat_days)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(guild_id) DO UPDATE SET
+                per_channel = excluded.per_channel,
+                repeat_picks = excluded.repeat_picks,
+                repeat_days = excluded.repeat_days",
+                params![
+                    guild_id,
+                    settings.per_channel,
+                    settings.picks,
+                    settings.days
+                ],
+            )?;
+
+            Ok(settings)
+        })
+        .await??;
+
+    let scope = if settings.per_channel {
+        "per channel"
+    } else {
+        "per server"
+    };
+
+    let config_embed = serenity::CreateEmbed::new()
+        .title("Random Settings")
+        .color(Color::BLUE)
+        .field("Last picks excluded", settings.picks.to_string(), true)
+        .field("Days excluded", settings.days.to_string(), true)
+        .field("History", scope, true);
+
+    ctx.send(poise::CreateReply::default().embed(config_embed))
+        .await?;
+
+    Ok(())
+}
+
+/// Clear the random pick history
+#[poise::command(prefix_command, slash_command, rename = "reset-history")]
+pub async fn reset_history(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get() as i64);
+    let channel_id = ctx.channel_id().get() as i64;
+    let db = ctx.data().db.clone();
+
+    let (cleared, per_channel) =
+        tokio::task::spawn_blocking(move || -> Result<(usize, bool), rusqlite::Error> {
+            let db_lock = db.blocking_lock();
+
+            let settings = load_settings(&db_lock, guild_id)?;
+            let channel_filter = if settings.per_channel { channel_id } else { 0 };
+
+            let cleared = db_lock.execute(
+                "DELETE FROM random_history WHERE guild_id = ?1 AND (?2 = 0 OR channel_id = ?2)",
+                params![guild_id, channel_filter],
+            )?;
+
+            Ok((cleared, settings.per_channel))
+        })
+        .await??;
+
+    let scope = if per_channel {
+        "this channel"
+    } else {
+        "this server"
+    };
+
+    let reset_embed = serenity::CreateEmbed::new()
+        .title("Success")
+        .color(Color::DARK_GREEN)
+        .description(format!(
+            "Cleared {} random pick(s) from the history of {}.",
+            cleared, scope
+        ));
+
+    ctx.send(poise::CreateReply::default().embed(reset_embed))
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: i64 = 86400;
+
+    #[test]
+    fn uniform_modes_ignore_stats() {
+        let stats = SongStats {
+            favorites: 10,
+            average_rating: Some(9.0),
+            last_picked: Some(0),
+            tagged: true,
+        };
+
+        assert_eq!(song_weight(Mode::Uniform, &stats, DAY), 1.0);
+        assert_eq!(song_weight(Mode::MyFavorites, &stats, DAY), 1.0);
+    }
+
+    #[test]
+    fn favorites_and_ratings_raise_the_weight() {
+        let none = SongStats::default();
+        let loved = SongStats {
+            favorites: 3,
+            average_rating: Some(9.0),
+            ..Default::default()
+        };
+
+        assert_eq!(song_weight(Mode::FavoritesWeighted, &none, 0), 1.0);
+        assert_eq!(song_weight(Mode::FavoritesWeighted, &loved, 0), 4.0);
+        assert_eq!(song_weight(Mode::RatingWeighted, &none, 0), 5.0);
+        assert_eq!(song_weight(Mode::RatingWeighted, &loved, 0), 9.0);
+    }
+
+    #[test]
+    fn least_recent_grows_with_days_since_the_last_pick() {
+        let picked = |days_ago: i64| SongStats {
+            last_picked: Some(100 * DAY - days_ago * DAY),
+            ..Default::default()
+        };
+        let now = 100 * DAY;
+
+        assert_eq!(song_weight(Mode::LeastRecent, &picked(0), now), 1.0);
+        assert_eq!(song_weight(Mode::LeastRecent, &picked(7), now), 8.0);
+        assert_eq!(song_weight(Mode::LeastRecent, &picked(90), now), 31.0);
+        assert_eq!(
+            song_weight(Mode::LeastRecent, &SongStats::default(), now),
+            31.0
+        );
+    }
+
+    #[test]
+    fn fingerprint_changes_with_any_input() {
+        let inputs = || DrawInputs {
+            amount: 3,
+            candidates: (1..=20).collect(),
+            weights: (1..=20).map(|i| 10.0 / f64::from(i)).collect(),
+            recent: vec![4, 9],
+        };
+        let fingerprint = inputs().fingerprint();
+
+        assert_eq!(inputs().fingerprint(), fingerprint);
+
+        let mut removed_song = inputs();
+        removed_song.candidates.pop();
+        removed_song.weights.pop();
+        assert_ne!(removed_song.fingerprint(), fingerprint);
+
+        let mut new_rating = inputs();
+        new_rating.weights[0] = 9.5;
+        assert_ne!(new_rating.fingerprint(), fingerprint);
+
+        let mut reset_history = inputs();
+        reset_history.recent.clear();
+        assert_ne!(reset_history.fingerprint(), fingerprint);
+    }
+
+    #[test]
+    fn tagged_songs_are_favored() {
+        let tagged = SongStats {
+            tagged: true,
+            ..Default::default()
+        };
+
+        assert_eq!(song_weight(Mode::TagWeighted, &tagged, 0), TAG_WEIGHT);
+        assert_eq!(
+            song_weight(Mode::TagWeighted, &SongStats::default(), 0),
+            1.0
+        );
+    }
+}
@@ -0,0 +1,343 @@
+use crate::{Context, Data, Error};
+use poise::serenity_prelude::{self as serenity, Color};
+use rusqlite::{Connection, OptionalExtension, params};
+
+const RATE_PREFIX: &str = "rate:";
+const MAX_SCORE: u8 = 10;
+/// Ratings a song needs before `/toprated` ranks it, unless overridden
+const DEFAULT_MIN_VOTES: u32 = 3;
+
+/// Song ID, title, album, average score and number of ratings
+type RankedSong = (i32, String, String, f64, u32);
+
+/// Aggregate of the ratings given to a song or album
+pub struct RatingSummary {
+    pub count: u32,
+    pub average: Option<f64>,
+    /// Number of ratings per score, from 1 to 10
+    pub distribution: [u32; MAX_SCORE as usize],
+}
+
+impl RatingSummary {
+    /// Adds average, count and distribution fields to `embed`
+    pub fn add_fields(&self, embed: serenity::CreateEmbed) -> serenity::CreateEmbed {
+        let average = match self.average {
+            Some(average) => format!("{:.1}/10", average),
+            None => "Not rated yet".to_string(),
+        };
+
+        let embed = embed.field("Average rating", average, true).field(
+            "Ratings",
+            self.count.to_string(),
+            true,
+        );
+
+        if self.count == 0 {
+            return embed;
+        }
+
+        let most = self.distribution.iter().copied().max().unwrap_or_default();
+        let distribution = self
+            .distribution
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(i, amount)| {
+                // Bars are scaled so the most common score fills 10 blocks
+                let filled = (amount * 10).checked_div(most).unwrap_or_default() as usize;
+                format!(
+                    "`{:>2}` {}{} {}",
+                    i + 1,
+                    "█".repeat(filled),
+                    "░".repeat(10 - filled),
+                    amount
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        embed.field("Distribution", distribution, false)
+    }
+}
+
+/// Summarizes the ratings of one song, or of every song on an album
+pub fn load_summary(
+    conn: &Connection,
+    song_id: Option<i32>,
+    album: Option<&str>,
+) -> Result<RatingSummary, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT r.score, COUNT(*) FROM ratings r
+         JOIN songs s ON s.id = r.song_id
+         WHERE (?1 IS NULL OR s.id = ?1) AND (?2 IS NULL OR s.album = ?2)
+         GROUP BY r.score",
+    )?;
+    let score_iter = stmt.query_map(params![song_id, album], |row| {
+        Ok((row.get::<_, u8>(0)?, row.get::<_, u32>(1)?))
+    })?;
+
+    let mut distribution = [0; MAX_SCORE as usize];
+    for score in score_iter {
+        let (score, amount) = score?;
+        if (1..=MAX_SCORE).contains(&score) {
+            distribution[score as usize - 1] = amount;
+        }
+    }
+
+    let count: u32 = distribution.iter().sum();
+    let total: u32 = distribution
+        .iter()
+        .enumerate()
+        .map(|(i, amount)| (i as u32 + 1) * amount)
+        .sum();
+
+    Ok(RatingSummary {
+        count,
+        average: (count > 0).then(|| f64::from(total) / f64::from(count)),
+        distribution,
+    })
+}
+
+/// Buttons rating `song_id` from 1 to 10, routed through [`handle_rating`]
+pub fn rating_buttons(song_id: i32) -> Vec<serenity::CreateActionRow> {
+    (1..=MAX_SCORE)
+        .collect::<Vec<_>>()
+        .chunks(5)
+        .map(|scores| {
+            serenity::CreateActionRow::Buttons(
+                scores
+                    .iter()
+                    .map(|score| {
+                        serenity::CreateButton::new(format!("{}{}:{}", RATE_PREFIX, song_id, score))
+                            .label(score.to_string())
+                            .style(serenity::ButtonStyle::Secondary)
+                    })
+                    .collect(),
+            )
+        })
+        .collect()
+}
+
+/// Stores a rating, replacing the user's previous one, and returns the song title
+fn save_rating(
+    conn: &Connection,
+    user_id: i64,
+    song_id: i32,
+    score: u8,
+) -> Result<Option<String>, rusqlite::Error> {
+    let title: Option<String> = conn
+        .query_row(
+            "SELECT title FROM songs WHERE id = ?1",
+            params![song_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    if title.is_some() {
+        conn.execute(
+            "INSERT INTO ratings (user_id, song_id, score, rated_at)
+             VALUES (?1, ?2, ?3, unixepoch())
+             ON CONFLICT(user_id, song_id) DO UPDATE SET
+                score = excluded.score,
+                rated_at = excluded.rated_at",
+            params![user_id, song_id, score],
+        )?;
+    }
+
+    Ok(title)
+}
+
+/// Rate a song from 1 to 10
+#[poise::command(prefix_command, slash_command)]
+pub async fn rate(
+    ctx: Context<'_>,
+    #[autocomplete = "crate::autocomplete::autocomplete_song"]
+    #[description = "The song"]
+    song_id: i32,
+    #[description = "Your score from 1 to 10"] score: u8,
+) -> Result<(), Error> {
+    if !(1..=MAX_SCORE).contains(&score) {
+        let invalid_score_embed = serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description("Scores go from 1 to 10.");
+        ctx.send(poise::CreateReply::default().embed(invalid_score_embed))
+            .await?;
+        return Ok(());
+    }
+
+    let user_id = ctx.author().id.get() as i64;
+    let db = ctx.data().db.clone();
+
+    let rated = tokio::task::spawn_blocking(
+        move || -> Result<Option<(String, RatingSummary)>, rusqlite::Error> {
+            let db_lock = db.blocking_lock();
+
+            let Some(title) = save_rating(&db_lock, user_id, song_id, score)? else {
+                return Ok(None);
+            };
+
+            Ok(Some((title, load_summary(&db_lock, Some(song_id), None)?)))
+        },
+    )
+    .await??;
+
+    let embed = match rated {
+        Some((title, summary)) => summary.add_fields(
+            serenity::CreateEmbed::new()
+                .title("Success")
+                .color(Color::DARK_GREEN)
+                .description(format!("You rated '{}' {}/10.", title, score)),
+        ),
+        None => serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description(format!("No song found with ID: {}", song_id)),
+    };
+
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+    Ok(())
+}
+
+/// Handles a click on one of the buttons from [`rating_buttons`]
+pub async fn handle_rating(
+    ctx: &serenity::Context,
+    interaction: &serenity::ComponentInteraction,
+    data: &Data,
+) -> Result<(), Error> {
+    let Some((song_id, score)) = interaction
+        .data
+        .custom_id
+        .strip_prefix(RATE_PREFIX)
+        .and_then(|rest| rest.split_once(':'))
+    else {
+        return Ok(());
+    };
+    let (Ok(song_id), Ok(score)) = (song_id.parse::<i32>(), score.parse::<u8>()) else {
+        return Ok(());
+    };
+    let user_id = interaction.user.id.get() as i64;
+
+    let db = data.db.clone();
+    let title = tokio::task::spawn_blocking(move || {
+        let db_lock = db.blocking_lock();
+        save_rating(&db_lock, user_id, song_id, score)
+    })
+    .await??;
+
+    let content = match title {
+        Some(title) => format!("You rated **{}** {}/10.", title, score),
+        None => "This song has been deleted.".to_string(),
+    };
+
+    interaction
+        .create_response(
+            ctx,
+            serenity::CreateInteractionResponse::Message(
+                serenity::CreateInteractionResponseMessage::new()
+                    .content(content)
+                    .ephemeral(true),
+            ),
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Show the highest rated songs
+#[poise::command(prefix_command, slash_command)]
+pub async fn toprated(
+    ctx: Context<'_>,
+    #[autocomplete = "crate::commands::new::autocomplete_album"]
+    #[description = "Only include songs from this album"]
+    album: Option<String>,
+    #[description = "Ratings a song needs to be ranked (3 by default)"] min_votes: Option<u32>,
+) -> Result<(), Error> {
+    let min_votes = min_votes.unwrap_or(DEFAULT_MIN_VOTES).max(1);
+    let db = ctx.data().db.clone();
+    let album_cloned = album.clone();
+
+    let (songs, album_summary) = tokio::task::spawn_blocking(
+        move || -> Result<(Vec<RankedSong>, Option<RatingSummary>), rusqlite::Error> {
+            let db_lock = db.blocking_lock();
+
+            let mut stmt = db_lock.prepare(
+                "SELECT s.id, s.title, s.album, AVG(r.score) AS average, COUNT(*) AS votes
+                 FROM ratings r
+                 JOIN songs s ON s.id = r.song_id
+                 WHERE ?1 IS NULL OR s.album = ?1
+                 GROUP BY s.id
+                 HAVING votes >= ?2
+                 ORDER BY average DESC, votes DESC
+                 LIMIT 10",
+            )?;
+            let song_iter = stmt.query_map(params![album_cloned, min_votes], |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                ))
+            })?;
+            let songs = song_iter.collect::<Result<Vec<_>, _>>()?;
+
+            let album_summary = match &album_cloned {
+                Some(album) => Some(load_summary(&db_lock, None, Some(album))?),
+                None => None,
+            };
+
+            Ok((songs, album_summary))
+        },
+    )
+    .await??;
+
+    let title = match &album {
+        Some(album) => format!("Top Rated: {}", album),
+        None => "Top Rated".to_string(),
+    };
+
+    let ranking = if songs.is_empty() {
+        format!(
+            "No songs have at least {} rating(s) yet. Rate some with `/rate`.",
+            min_votes
+        )
+    } else {
+        songs
+            .iter()
+            .enumerate()
+            .map(|(i, (id, title, album, average, votes))| {
+                format!(
+                    "{}. {} — {} [ID: {}] ★ {:.1} ({} rating(s))",
+                    i + 1,
+                    title,
+                    album,
+                    id,
+                    average,
+                    votes
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let mut top_rated_embed = serenity::CreateEmbed::new()
+        .title(title)
+        .color(Color::GOLD)
+        .description(ranking)
+        .footer(serenity::CreateEmbedFooter::new(format!(
+            "Songs need at least {} rating(s) to be ranked",
+            min_votes
+        )));
+
+    // Asking for an album also shows how the album as a whole is rated
+    if let Some(summary) = album_summary {
+        top_rated_embed = summary.add_fields(top_rated_embed);
+    }
+
+    ctx.send(poise::CreateReply::default().embed(top_rated_embed))
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,808 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
+use futures::StreamExt;
+use poise::serenity_prelude::{self as serenity, Color};
+use rand::{SeedableRng, seq::SliceRandom};
+use rand_chacha::ChaCha8Rng;
+use rusqlite::{Connection, OptionalExtension, params};
+
+use crate::{Context, Error, commands::poll::parse_duration};
+
+/// Length assumed for songs whose duration hasn't been set
+const DEFAULT_SONG_LENGTH: u32 = 4 * 60;
+/// Longest length a song can be given
+const MAX_SONG_LENGTH: u32 = 24 * 60 * 60;
+/// Amount of songs when neither a count nor a duration is given
+const DEFAULT_COUNT: usize = 15;
+const MAX_COUNT: usize = 50;
+/// How long the Save button stays usable
+const SAVE_TIMEOUT: Duration = Duration::from_secs(300);
+
+#[derive(Clone)]
+struct SetlistSong {
+    id: i32,
+    title: String,
+    album: String,
+    length: Option<u32>,
+    opener: bool,
+    closer: bool,
+}
+
+impl SetlistSong {
+    fn length_or_default(&self) -> u32 {
+        self.length.unwrap_or(DEFAULT_SONG_LENGTH)
+    }
+}
+
+/// Songs of a saved setlist by ID, or `None` for songs deleted from the catalog since
+type SavedSongs = Vec<(i32, Option<SetlistSong>)>;
+
+/// Everything a generated setlist has to satisfy
+struct Constraints {
+    count: Option<usize>,
+    duration: Option<u32>,
+    album_limits: HashMap<String, (usize, usize)>,
+    required: Vec<i32>,
+    excluded: HashSet<i32>,
+}
+
+/// Parses song lengths like `3:45`, or a plain number of seconds, up to 24 hours
+pub fn parse_song_length(input: &str) -> Option<u32> {
+    let input = input.trim();
+
+    let seconds = match input.split_once(':') {
+        Some((minutes, seconds)) => {
+            let seconds: u32 = seconds.parse().ok()?;
+            if seconds >= 60 {
+                return None;
+            }
+            minutes
+                .parse::<u32>()
+                .ok()?
+                .checked_mul(60)?
+                .checked_add(seconds)?
+        }
+        None => input.parse().ok()?,
+    };
+
+    (seconds > 0 && seconds <= MAX_SONG_LENGTH).then_some(seconds)
+}
+
+/// Formats seconds as `m:ss`, or `h:mm:ss` past an hour
+pub fn format_song_length(seconds: u32) -> String {
+    if seconds >= 3600 {
+        format!(
+            "{}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        )
+    } else {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+/// Parses comma-separated song IDs
+fn parse_song_ids(input: &str) -> Result<Vec<i32>, String> {
+    input
+        .split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(|id| {
+            id.parse::<i32>()
+                .map_err(|_| format!("'{}' is not a valid song ID.", id))
+        })
+        .collect()
+}
+
+/// Parses per-album limits like `Album A:1-3, Album B:0-2` or `Album C:2` (at most 2)
+fn parse_album_limits(input: &str) -> Result<HashMap<String, (usize, usize)>, String> {
+    let mut limits = HashMap::new();
+
+    for limit in input
+        .split(',')
+        .map(str::trim)
+        .filter(|limit| !limit.is_empty())
+    {
+        let invalid = || {
+            format!(
+                "'{}' is not a valid album limit. Use `Album:min-max` or `Album:max`.",
+                limit
+            )
+        };
+
+        let (album, range) = limit.rsplit_once(':').ok_or_else(invalid)?;
+        let (min, max) = match range.split_once('-') {
+            Some((min, max)) => (
+                min.trim().parse().map_err(|_| invalid())?,
+                max.trim().parse().map_err(|_| invalid())?,
+            ),
+            None => (0, range.trim().parse().map_err(|_| invalid())?),
+        };
+
+        if min > max {
+            return Err(invalid());
+        }
+
+        limits.insert(album.trim().to_string(), (min, max));
+    }
+
+    Ok(limits)
+}
+
+fn load_songs(conn: &Connection) -> Result<Vec<SetlistSong>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT s.id, s.title, s.album, s.duration,
+            EXISTS(SELECT 1 FROM song_tags t WHERE t.song_id = s.id AND t.tag = 'opener'),
+            EXISTS(SELECT 1 FROM song_tags t WHERE t.song_id = s.id AND t.tag = 'closer')
+         FROM songs s
+         ORDER BY s.id",
+    )?;
+
+    let song_iter = stmt.query_map([], |row| {
+        Ok(SetlistSong {
+            id: row.get(0)?,
+            title: row.get(1)?,
+            album: row.get(2)?,
+            length: row.get(3)?,
+            opener: row.get(4)?,
+            closer: row.get(5)?,
+        })
+    })?;
+
+    song_iter.collect()
+}
+
+/// Builds an ordered setlist from `songs`. The result only depends on the catalog, the
+/// constraints and `seed`.
+///
+/// Required songs go in first, then album minimums are filled, then the rest of the set is
+/// drawn at random until the target count or duration is reached. A song tagged `opener`
+/// is pinned to the start and one tagged `closer` to the end.
+fn build_setlist(
+    songs: &[SetlistSong],
+    constraints: &Constraints,
+    seed: u64,
+) -> Result<Vec<SetlistSong>, String> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    let by_id: HashMap<i32, &SetlistSong> = songs.iter().map(|song| (song.id, song)).collect();
+
+    let mut candidates: Vec<&SetlistSong> = songs
+        .iter()
+        .filter(|song| !constraints.excluded.contains(&song.id))
+        .filter(|song| !constraints.required.contains(&song.id))
+        .collect();
+    candidates.shuffle(&mut rng);
+
+    let target_count = match (constraints.count, constraints.duration) {
+        (Some(count), _) => count,
+        (None, Some(_)) => MAX_COUNT,
+        (None, None) => DEFAULT_COUNT,
+    };
+    let target_length = constraints.duration.unwrap_or(u32::MAX);
+
+    let mut selected: Vec<&SetlistSong> = Vec::new();
+    let mut per_album: HashMap<&str, usize> = HashMap::new();
+    let mut total_length = 0u32;
+
+    let max_for = |album: &str| {
+        constraints
+            .album_limits
+            .get(album)
+            .map_or(usize::MAX, |(_, max)| *max)
+    };
+
+    for song_id in &constraints.required {
+        let Some(song) = by_id.get(song_id) else {
+            return Err(format!("No song found with ID: {}", song_id));
+        };
+        if constraints.excluded.contains(song_id) {
+            return Err(format!("'{}' is both required and excluded.", song.title));
+        }
+        if selected.iter().any(|picked| picked.id == *song_id) {
+            continue;
+        }
+
+        let album_count = per_album.entry(song.album.as_str()).or_default();
+        *album_count += 1;
+        if *album_count > max_for(&song.album) {
+            return Err(format!(
+                "The required songs go over the limit for '{}'.",
+                song.album
+            ));
+        }
+
+        total_length = total_length.saturating_add(song.length_or_default());
+        selected.push(song);
+    }
+
+    if selected.len() > target_count {
+        return Err(format!(
+            "{} songs are required, but the setlist only has room for {}.",
+            selected.len(),
+            target_count
+        ));
+    }
+
+    // Pin an opener and a closer unless the required songs already provide one
+    for wants_opener in [true, false] {
+        let tagged = |song: &SetlistSong| {
+            if wants_opener {
+                song.opener
+            } else {
+                song.closer
+            }
+        };
+        if selected.iter().any(|song| tagged(song)) || selected.len() >= target_count {
+            continue;
+        }
+
+        let pick = candidates.iter().position(|song| {
+            tagged(song)
+                && !selected.iter().any(|picked| picked.id == song.id)
+                && per_album.get(song.album.as_str()).copied().unwrap_or(0) < max_for(&song.album)
+                && total_length
+                    .checked_add(song.length_or_default())
+                    .is_some_and(|total| total <= target_length)
+        });
+        if let Some(index) = pick {
+            let song = candidates.remove(index);
+            *per_album.entry(song.album.as_str()).or_default() += 1;
+            total_length = total_length.saturating_add(song.length_or_default());
+            selected.push(song);
+        }
+    }
+
+    // Then fill every album up to its minimum, and finally anything that fits
+    let mut albums_with_minimum: Vec<(&String, &(usize, usize))> =
+        constraints.album_limits.iter().collect();
+    albums_with_minimum.sort();
+
+    for (album, (min, _)) in albums_with_minimum {
+        while per_album.get(album.as_str()).copied().unwrap_or(0) < *min {
+            let Some(index) = candidates.iter().position(|song| &song.album == album) else {
+                return Err(format!("'{}' doesn't have enough songs.", album));
+            };
+            if selected.len() >= target_count {
+                return Err("The album minimums don't fit in the setlist.".to_string());
+            }
+
+            let song = candidates.remove(index);
+            *per_album.entry(song.album.as_str()).or_default() += 1;
+            total_length = total_length.saturating_add(song.length_or_default());
+            selected.push(song);
+        }
+    }
+
+    for song in candidates {
+        if selected.len() >= target_count {
+            break;
+        }
+        if per_album.get(song.album.as_str()).copied().unwrap_or(0) >= max_for(&song.album) {
+            continue;
+        }
+        if total_length
+            .checked_add(song.length_or_default())
+            .is_none_or(|total| total > target_length)
+        {
+            continue;
+        }
+
+        *per_album.entry(song.album.as_str()).or_default() += 1;
+        total_length = total_length.saturating_add(song.length_or_default());
+        selected.push(song);
+    }
+
+    if selected.is_empty() {
+        return Err("No songs match these constraints.".to_string());
+    }
+
+    // Everything but the opener and closer keeps its random order
+    selected.shuffle(&mut rng);
+    if let Some(index) = selected.iter().position(|song| song.opener) {
+        let opener = selected.remove(index);
+        selected.insert(0, opener);
+    }
+    if let Some(index) = selected.iter().skip(1).position(|song| song.closer) {
+        let closer = selected.remove(index + 1);
+        selected.push(closer);
+    }
+
+    Ok(selected.into_iter().cloned().collect())
+}
+
+/// Embed listing `songs` by position. Songs deleted from the catalog since the setlist
+/// was saved are `None`, and count towards the runtime with the default length.
+fn setlist_embed(
+    title: &str,
+    songs: &[(i32, Option<&SetlistSong>)],
+    seed: Option<u64>,
+) -> serenity::CreateEmbed {
+    let total_length = songs
+        .iter()
+        .map(|(_, song)| song.map_or(DEFAULT_SONG_LENGTH, SetlistSong::length_or_default))
+        .fold(0u32, u32::saturating_add);
+    let estimated = songs
+        .iter()
+        .any(|(_, song)| song.is_none_or(|song| song.length.is_none()));
+
+    let song_list = songs
+        .iter()
+        .enumerate()
+        .map(|(i, (song_id, song))| match song {
+            Some(song) => {
+                let length = match song.length {
+                    Some(length) => format_song_length(length),
+                    None => "?:??".to_string(),
+                };
+                format!(
+                    "{}. **{}** ({}) — {} `ID: {}`",
+                    i + 1,
+                    song.title,
+                    song.album,
+                    length,
+                    song.id
+                )
+            }
+            None => format!("{}. ~~Deleted song~~ `ID: {}`", i + 1, song_id),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut footer = format!(
+        "{} song(s) • Runtime: {}{}",
+        songs.len(),
+        if estimated { "~" } else { "" },
+        format_song_length(total_length)
+    );
+    if let Some(seed) = seed {
+        footer.push_str(&format!(" • Seed: {:016x}", seed));
+    }
+
+    serenity::CreateEmbed::new()
+        .title(title)
+        .color(Color::BLUE)
+        .description(song_list.chars().take(4096).collect::<String>())
+        .footer(serenity::CreateEmbedFooter::new(footer))
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    subcommands("generate", "saved", "show", "length"),
+    subcommand_required
+)]
+
+/// Generate, save and show setlists
+pub async fn setlist(_: Context<'_>) -> Result<(), Error> {
+    // This will never be called, because `subcommand_required` parameter is set
+    Ok(())
+}
+
+/// Generate a setlist from the catalog
+#[poise::command(prefix_command, slash_command)]
+#[allow(clippy::too_many_arguments)]
+pub async fn generate(
+    ctx: Context<'_>,
+    #[description = "Amount of songs (15 by default)"] count: Option<u32>,
+    #[description = "Target runtime, e.g. 90m or 1h30m"] duration: Option<String>,
+    #[description = "Per-album limits, e.g. \"Album A:1-3, Album B:2\""] albums: Option<String>,
+    #[description = "Comma-separated IDs of songs that must be played"] include: Option<String>,
+    #[description = "Comma-separated IDs of songs to leave out"] exclude: Option<String>,
+    #[description = "Seed of a previous setlist to reproduce it"] seed: Option<String>,
+    #[description = "Name to save the setlist under"] name: Option<String>,
+) -> Result<(), Error> {
+    let parsed = (|| -> Result<(Constraints, u64), String> {
+        let duration = match duration {
+            Some(duration) => Some(
+                parse_duration(&duration)
+                    .and_then(|parsed| u32::try_from(parsed.as_secs()).ok())
+                    .ok_or_else(|| format!("'{}' is not a valid duration.", duration))?,
+            ),
+            None => None,
+        };
+        let seed = match seed {
+            Some(seed) => u64::from_str_radix(seed.trim(), 16)
+                .map_err(|_| format!("'{}' is not a valid seed.", seed))?,
+            None => rand::random(),
+        };
+
+        let constraints = Constraints {
+            count: count.map(|count| (count as usize).clamp(1, MAX_COUNT)),
+            duration,
+            album_limits: albums
+                .as_deref()
+                .map(parse_album_limits)
+                .transpose()?
+                .unwrap_or_default(),
+            required: include
+                .as_deref()
+                .map(parse_song_ids)
+                .transpose()?
+                .unwrap_or_default(),
+            excluded: exclude
+                .as_deref()
+                .map(parse_song_ids)
+                .transpose()?
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+        };
+
+        Ok((constraints, seed))
+    })();
+
+    let (constraints, seed) = match parsed {
+        Ok(parsed) => parsed,
+        Err(message) => {
+            let invalid_embed = serenity::CreateEmbed::new()
+                .title("Error")
+                .color(Color::RED)
+                .description(message);
+            ctx.send(poise::CreateReply::default().embed(invalid_embed))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let db = ctx.data().db.clone();
+    let songs = tokio::task::spawn_blocking(move || {
+        let db_lock = db.blocking_lock();
+        load_songs(&db_lock)
+    })
+    .await??;
+
+    let setlist = match build_setlist(&songs, &constraints, seed) {
+        Ok(setlist) => setlist,
+        Err(message) => {
+            let generate_fail_embed = serenity::CreateEmbed::new()
+                .title("Error")
+                .color(Color::RED)
+                .description(message);
+            ctx.send(poise::CreateReply::default().embed(generate_fail_embed))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let name = name.unwrap_or_else(|| format!("Setlist {:016x}", seed));
+    let listed: Vec<_> = setlist.iter().map(|song| (song.id, Some(song))).collect();
+    let embed = setlist_embed(&name, &listed, Some(seed));
+
+    // Only moderators get to save setlists
+    if !crate::checks::is_moderator(ctx) {
+        ctx.send(poise::CreateReply::default().embed(embed)).await?;
+        return Ok(());
+    }
+
+    let save_id = format!("setlist-save:{}", ctx.id());
+    let save_button = serenity::CreateActionRow::Buttons(vec![
+        serenity::CreateButton::new(&save_id)
+            .label("Save")
+            .style(serenity::ButtonStyle::Success),
+    ]);
+
+    let reply = ctx
+        .send(
+            poise::CreateReply::default()
+                .embed(embed.clone())
+                .components(vec![save_button]),
+        )
+        .await?;
+
+    let author_id = ctx.author().id;
+    let mut clicks = serenity::ComponentInteractionCollector::new(ctx)
+        .filter(move |interaction| {
+            interaction.data.custom_id == save_id && interaction.user.id == author_id
+        })
+        .timeout(SAVE_TIMEOUT)
+        .stream();
+
+    let Some(interaction) = clicks.next().await else {
+        // Nobody saved it in time, so drop the button
+        reply
+            .edit(
+                ctx,
+                poise::CreateReply::default()
+                    .embed(embed)
+                    .components(Vec::new()),
+            )
+            .await?;
+        return Ok(());
+    };
+
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get() as i64);
+    let created_by = author_id.get() as i64;
+    let song_ids: Vec<i32> = setlist.iter().map(|song| song.id).collect();
+    let name_cloned = name.clone();
+    let db = ctx.data().db.clone();
+
+    let setlist_id = tokio::task::spawn_blocking(move || -> Result<i64, rusqlite::Error> {
+        let db_lock = db.blocking_lock();
+        let tx = db_lock.unchecked_transaction()?;
+
+        let setlist_id: i64 = tx.query_row(
+            "INSERT INTO setlists (guild_id, name, seed, created_by, created_at)
+             VALUES (?1, ?2, ?3, ?4, unixepoch())
+             RETURNING id",
+            params![guild_id, name_cloned, format!("{:016x}", seed), created_by],
+            |row| row.get(0),
+        )?;
+
+        {
+            let mut stmt = tx.prepare(
+                "INSERT INTO setlist_songs (setlist_id, position, song_id) VALUES (?1, ?2, ?3)",
+            )?;
+            for (position, song_id) in song_ids.iter().enumerate() {
+                stmt.execute(params![setlist_id, position as i64, song_id])?;
+            }
+        }
+        tx.commit()?;
+
+        Ok(setlist_id)
+    })
+    .await??;
+
+    interaction
+        .create_response(
+            ctx,
+            serenity::CreateInteractionResponse::UpdateMessage(
+                serenity::CreateInteractionResponseMessage::new()
+                    .embed(embed.footer(serenity::CreateEmbedFooter::new(format!(
+                        "Saved as setlist {}",
+                        setlist_id
+                    ))))
+                    .components(Vec::new()),
+            ),
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// List saved setlists
+#[poise::command(prefix_command, slash_command)]
+pub async fn saved(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get() as i64);
+    let db = ctx.data().db.clone();
+
+    let setlists = tokio::task::spawn_blocking(
+        move || -> Result<Vec<(i64, String, u32, i64)>, rusqlite::Error> {
+            let db_lock = db.blocking_lock();
+
+            let mut stmt = db_lock.prepare(
+                "SELECT l.id, l.name, COUNT(s.song_id), l.created_at FROM setlists l
+                 LEFT JOIN setlist_songs s ON s.setlist_id = l.id
+                 WHERE l.guild_id = ?1
+                 GROUP BY l.id
+                 ORDER BY l.id DESC LIMIT 20",
+            )?;
+            let setlist_iter = stmt.query_map(params![guild_id], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })?;
+
+            setlist_iter.collect()
+        },
+    )
+    .await??;
+
+    if setlists.is_empty() {
+        let no_setlists_embed = serenity::CreateEmbed::new()
+            .title("No Setlists")
+            .description("No setlists have been saved yet.");
+
+        ctx.send(poise::CreateReply::default().embed(no_setlists_embed))
+            .await?;
+
+        return Ok(());
+    }
+
+    let setlist_list = setlists
+        .iter()
+        .map(|(id, name, songs, created_at)| {
+            format!(
+                "`#{}` **{}** — {} song(s), saved <t:{}:d>",
+                id, name, songs, created_at
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let setlist_list_embed = serenity::CreateEmbed::new()
+        .title("Saved Setlists")
+        .color(Color::BLUE)
+        .description(setlist_list);
+
+    ctx.send(poise::CreateReply::default().embed(setlist_list_embed))
+        .await?;
+
+    Ok(())
+}
+
+/// Show a saved setlist
+#[poise::command(prefix_command, slash_command)]
+pub async fn show(
+    ctx: Context<'_>,
+    #[description = "The ID of the setlist"] setlist_id: i64,
+) -> Result<(), Error> {
+    let db = ctx.data().db.clone();
+
+    let setlist = tokio::task::spawn_blocking(
+        move || -> Result<Option<(String, SavedSongs)>, rusqlite::Error> {
+            let db_lock = db.blocking_lock();
+
+            let name: Option<String> = db_lock
+                .query_row(
+                    "SELECT name FROM setlists WHERE id = ?1",
+                    params![setlist_id],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            let Some(name) = name else {
+                return Ok(None);
+            };
+
+            // Songs deleted from the catalog keep their place in the setlist
+            let mut stmt = db_lock.prepare(
+                "SELECT l.song_id, s.title, s.album, s.duration FROM setlist_songs l
+                 LEFT JOIN songs s ON s.id = l.song_id
+                 WHERE l.setlist_id = ?1
+                 ORDER BY l.position",
+            )?;
+            let song_iter = stmt.query_map(params![setlist_id], |row| {
+                let song_id: i32 = row.get(0)?;
+                let title: Option<String> = row.get(1)?;
+                let album: Option<String> = row.get(2)?;
+                let song = match (title, album) {
+                    (Some(title), Some(album)) => Some(SetlistSong {
+                        id: song_id,
+                        title,
+                        album,
+                        length: row.get(3)?,
+                        opener: false,
+                        closer: false,
+                    }),
+                    _ => None,
+                };
+                Ok((song_id, song))
+            })?;
+
+            Ok(Some((name, song_iter.collect::<Result<_, _>>()?)))
+        },
+    )
+    .await??;
+
+    let embed = match setlist {
+        Some((name, songs)) => {
+            let listed: Vec<_> = songs
+                .iter()
+                .map(|(song_id, song)| (*song_id, song.as_ref()))
+                .collect();
+            setlist_embed(&name, &listed, None)
+        }
+        None => serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description(format!("No setlist found with ID: {}", setlist_id)),
+    };
+
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+    Ok(())
+}
+
+/// Set how long a song is, for runtime targets
+#[poise::command(
+    prefix_command,
+    slash_command,
+    check = "crate::checks::check_is_moderator"
+)]
+pub async fn length(
+    ctx: Context<'_>,
+    #[autocomplete = "crate::autocomplete::autocomplete_song"]
+    #[description = "The song"]
+    song_id: i32,
+    #[description = "Length of the song, e.g. 3:45"] length: String,
+) -> Result<(), Error> {
+    let Some(seconds) = parse_song_length(&length) else {
+        let invalid_length_embed = serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description(format!(
+                "'{}' is not a valid length. Use `m:ss`, up to 24 hours.",
+                length
+            ));
+        ctx.send(poise::CreateReply::default().embed(invalid_length_embed))
+            .await?;
+        return Ok(());
+    };
+
+    let db = ctx.data().db.clone();
+
+    let affected_rows = tokio::task::spawn_blocking(move || -> Result<usize, rusqlite::Error> {
+        let db_lock = db.blocking_lock();
+
+        db_lock.execute(
+            "UPDATE songs SET duration = ?2 WHERE id = ?1",
+            params![song_id, seconds],
+        )
+    })
+    .await??;
+
+    let embed = if affected_rows == 0 {
+        serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description(format!("No song found with ID: {}", song_id))
+    } else {
+        serenity::CreateEmbed::new()
+            .title("Success")
+            .color(Color::DARK_GREEN)
+            .description(format!(
+                "Song with ID: {} is now {} long.",
+                song_id,
+                format_song_length(seconds)
+            ))
+    };
+
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_song_lengths() {
+        assert_eq!(parse_song_length("3:45"), Some(225));
+        assert_eq!(parse_song_length("200"), Some(200));
+        assert_eq!(parse_song_length("3:60"), None);
+        assert_eq!(parse_song_length("0:00"), None);
+    }
+
+    #[test]
+    fn rejects_overflowing_song_lengths() {
+        assert_eq!(parse_song_length("99999999:00"), None);
+        assert_eq!(parse_song_length("71582788:59"), None);
+        assert_eq!(parse_song_length("71582788:15"), None);
+    }
+
+    #[test]
+    fn caps_song_lengths_at_a_day() {
+        assert_eq!(parse_song_length("1440:00"), Some(MAX_SONG_LENGTH));
+        assert_eq!(parse_song_length("1440:01"), None);
+        assert_eq!(parse_song_length("86401"), None);
+    }
+
+    #[test]
+    fn long_songs_dont_overflow_the_runtime() {
+        let song = |id: i32| SetlistSong {
+            id,
+            title: format!("Song {}", id),
+            album: "Album".to_string(),
+            length: Some(u32::MAX),
+            opener: false,
+            closer: false,
+        };
+        let constraints = Constraints {
+            count: None,
+            duration: Some(u32::MAX),
+            album_limits: HashMap::new(),
+            required: vec![1, 2],
+            excluded: HashSet::new(),
+        };
+
+        let setlist = build_setlist(&[song(1), song(2), song(3)], &constraints, 7).unwrap();
+
+        assert_eq!(setlist.len(), 2);
+        // Rendering adds up the runtime too
+        let listed: Vec<_> = setlist.iter().map(|song| (song.id, Some(song))).collect();
+        let _ = setlist_embed("Setlist", &listed, None);
+    }
+}
@@ -0,0 +1,117 @@
+use crate::{
+    Context, Error,
+    commands::{fav, rate, setlist},
+};
+use poise::serenity_prelude::{self as serenity, Color, Mentionable};
+use rusqlite::{OptionalExtension, params};
+
+struct SongInfo {
+    title: String,
+    album: String,
+    duration: Option<u32>,
+    tags: Vec<String>,
+    /// Members whose edit proposals were accepted
+    editors: Vec<u64>,
+    favorites: u32,
+    ratings: rate::RatingSummary,
+}
+
+/// Show a song with its tags, favorites and ratings
+#[poise::command(prefix_command, slash_command)]
+pub async fn song(
+    ctx: Context<'_>,
+    #[autocomplete = "crate::autocomplete::autocomplete_song"]
+    #[description = "The song"]
+    song_id: i32,
+) -> Result<(), Error> {
+    let db = ctx.data().db.clone();
+
+    let info = tokio::task::spawn_blocking(move || -> Result<Option<SongInfo>, rusqlite::Error> {
+        let db_lock = db.blocking_lock();
+
+        let song: Option<(String, String, Option<u32>)> = db_lock
+            .query_row(
+                "SELECT title, album, duration FROM songs WHERE id = ?1",
+                params![song_id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .optional()?;
+
+        let Some((title, album, duration)) = song else {
+            return Ok(None);
+        };
+
+        let mut stmt =
+            db_lock.prepare("SELECT tag FROM song_tags WHERE song_id = ?1 ORDER BY tag")?;
+        let tags = stmt
+            .query_map(params![song_id], |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+
+        let mut stmt = db_lock.prepare(
+            "SELECT DISTINCT user_id FROM edit_proposals WHERE song_id = ?1 AND status = 'accepted'",
+        )?;
+        let editors = stmt
+            .query_map(params![song_id], |row| Ok(row.get::<_, i64>(0)? as u64))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Some(SongInfo {
+            title,
+            album,
+            duration,
+            tags,
+            editors,
+            favorites: fav::favorite_count(&db_lock, song_id)?,
+            ratings: rate::load_summary(&db_lock, Some(song_id), None)?,
+        }))
+    })
+    .await??;
+
+    let Some(info) = info else {
+        let not_found_embed = serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description(format!("No song found with ID: {}", song_id));
+        ctx.send(poise::CreateReply::default().embed(not_found_embed))
+            .await?;
+        return Ok(());
+    };
+
+    let mut song_embed = serenity::CreateEmbed::new()
+        .title(&info.title)
+        .color(Color::BLUE)
+        .field("Album", &info.album, true)
+        .field("Favorites", format!("♥ {}", info.favorites), true);
+
+    if let Some(duration) = info.duration {
+        song_embed = song_embed.field("Length", setlist::format_song_length(duration), true);
+    }
+    if !info.tags.is_empty() {
+        song_embed = song_embed.field("Tags", info.tags.join(", "), false);
+    }
+    if !info.editors.is_empty() {
+        let editors = info
+            .editors
+            .iter()
+            .map(|user_id| serenity::UserId::new(*user_id).mention().to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        song_embed = song_embed.field("Corrections by", editors, false);
+    }
+
+    let song_embed = info
+        .ratings
+        .add_fields(song_embed)
+        .footer(serenity::CreateEmbedFooter::new(format!(
+            "ID: {} • Rate it with the buttons below",
+            song_id
+        )));
+
+    ctx.send(
+        poise::CreateReply::default()
+            .embed(song_embed)
+            .components(rate::rating_buttons(song_id)),
+    )
+    .await?;
+
+    Ok(())
+}
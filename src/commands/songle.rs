@@ -0,0 +1,532 @@
+use chrono::{Datelike, Days, NaiveDate, Utc};
+use poise::serenity_prelude::{self as serenity, Color};
+use rand::{SeedableRng, seq::SliceRandom};
+use rand_chacha::ChaCha8Rng;
+use rusqlite::{Connection, OptionalExtension, params};
+
+use crate::{
+    Context, Error, games,
+    points::{self, Achievement, Game},
+};
+
+const MAX_GUESSES: usize = 6;
+/// Only titles with this many letters make good puzzles
+const MIN_LETTERS: usize = 4;
+const MAX_LETTERS: usize = 12;
+
+struct Stats {
+    played: u32,
+    wins: u32,
+    /// Wins per amount of guesses needed
+    distribution: Vec<u32>,
+    current_streak: u32,
+    max_streak: u32,
+}
+
+struct Puzzle {
+    number: u32,
+    title: String,
+    album: String,
+    answer: String,
+}
+
+/// Board after a guess, with any achievements finishing the puzzle unlocked
+struct GuessOutcome {
+    puzzle: Puzzle,
+    guesses: Vec<String>,
+    unlocked: Vec<(serenity::UserId, Achievement)>,
+}
+
+/// Uppercase letters of `text`, dropping spaces, digits and punctuation
+fn letters(text: &str) -> String {
+    games::normalize(text)
+        .chars()
+        .filter(|c| c.is_alphabetic())
+        .flat_map(char::to_uppercase)
+        .collect()
+}
+
+/// Coloured squares for a guess, marking repeated letters only as often as the answer has them
+fn feedback(guess: &str, answer: &str) -> String {
+    let guess: Vec<char> = guess.chars().collect();
+    let answer: Vec<char> = answer.chars().collect();
+
+    let mut squares = vec!['⬛'; guess.len()];
+    let mut unmatched: Vec<char> = Vec::new();
+
+    for (i, letter) in guess.iter().enumerate() {
+        if answer.get(i) == Some(letter) {
+            squares[i] = '🟩';
+        } else if let Some(answer_letter) = answer.get(i) {
+            unmatched.push(*answer_letter);
+        }
+    }
+
+    for (i, letter) in guess.iter().enumerate() {
+        if squares[i] == '🟩' {
+            continue;
+        }
+        if let Some(position) = unmatched.iter().position(|c| c == letter) {
+            unmatched.swap_remove(position);
+            squares[i] = '🟨';
+        }
+    }
+
+    squares.into_iter().collect()
+}
+
+/// Today's puzzle, picked from the catalog the first time it's needed.
+///
+/// Songs that were already an answer are only reused once every title has had its turn.
+fn todays_puzzle(conn: &Connection, day: NaiveDate) -> Result<Option<Puzzle>, rusqlite::Error> {
+    let day_str = day.to_string();
+
+    let exists: bool = conn.query_row(
+        "SELECT COUNT(*) FROM songle_puzzles WHERE day = ?1",
+        params![day_str],
+        |row| row.get(0),
+    )?;
+
+    if !exists {
+        let mut stmt = conn.prepare(
+            "SELECT s.id, s.title, EXISTS(SELECT 1 FROM songle_puzzles p WHERE p.song_id = s.id)
+             FROM songs s
+             ORDER BY s.id",
+        )?;
+        let candidates = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, i32>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, bool>(2)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let candidates: Vec<(i32, String, bool)> = candidates
+            .into_iter()
+            .filter(|(_, title, _)| {
+                (MIN_LETTERS..=MAX_LETTERS).contains(&letters(title).chars().count())
+            })
+            .collect();
+        let fresh: Vec<&(i32, String, bool)> =
+            candidates.iter().filter(|(_, _, used)| !used).collect();
+        let pool: Vec<&(i32, String, bool)> = if fresh.is_empty() {
+            candidates.iter().collect()
+        } else {
+            fresh
+        };
+
+        // Seeding by date makes the pick independent of who asks first
+        let mut rng = ChaCha8Rng::seed_from_u64(day.num_days_from_ce() as u64);
+        let Some((song_id, title, _)) = pool.choose(&mut rng) else {
+            return Ok(None);
+        };
+
+        conn.execute(
+            "INSERT OR IGNORE INTO songle_puzzles (day, song_id, answer) VALUES (?1, ?2, ?3)",
+            params![day_str, song_id, letters(title)],
+        )?;
+    }
+
+    conn.query_row(
+        "SELECT (SELECT COUNT(*) FROM songle_puzzles WHERE day <= p.day),
+            COALESCE(s.title, p.answer), COALESCE(s.album, ''), p.answer
+         FROM songle_puzzles p
+         LEFT JOIN songs s ON s.id = p.song_id
+         WHERE p.day = ?1",
+        params![day_str],
+        |row| {
+            Ok(Puzzle {
+                number: row.get(0)?,
+                title: row.get(1)?,
+                album: row.get(2)?,
+                answer: row.get(3)?,
+            })
+        },
+    )
+    .optional()
+}
+
+fn load_guesses(
+    conn: &Connection,
+    day: NaiveDate,
+    user_id: i64,
+) -> Result<Vec<String>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT guess FROM songle_guesses WHERE day = ?1 AND user_id = ?2 ORDER BY guess_number",
+    )?;
+    let guess_iter = stmt.query_map(params![day.to_string(), user_id], |row| row.get(0))?;
+
+    guess_iter.collect()
+}
+
+/// Records a finished puzzle, updates the player's win streak and awards points for solving it
+fn record_result(
+    conn: &Connection,
+    guild_id: i64,
+    day: NaiveDate,
+    user_id: i64,
+    puzzle: &Puzzle,
+    solved: bool,
+    guesses: usize,
+) -> Result<Vec<(serenity::UserId, Achievement)>, rusqlite::Error> {
+    conn.execute(
+        "INSERT OR IGNORE INTO songle_results (day, user_id, solved, guesses) VALUES (?1, ?2, ?3, ?4)",
+        params![day.to_string(), user_id, solved, guesses as i64],
+    )?;
+
+    let yesterday = day
+        .checked_sub_days(Days::new(1))
+        .unwrap_or(day)
+        .to_string();
+    if solved {
+        conn.execute(
+            "INSERT INTO songle_streaks (user_id, current_streak, max_streak, last_win_day)
+             VALUES (?1, 1, 1, ?2)
+             ON CONFLICT(user_id) DO UPDATE SET
+                current_streak = CASE WHEN last_win_day = ?3 THEN current_streak + 1 ELSE 1 END,
+                max_streak = MAX(max_streak,
+                    CASE WHEN last_win_day = ?3 THEN current_streak + 1 ELSE 1 END),
+                last_win_day = ?2",
+            params![user_id, day.to_string(), yesterday],
+        )?;
+    } else {
+        conn.execute(
+            "UPDATE songle_streaks SET current_streak = 0 WHERE user_id = ?1",
+            params![user_id],
+        )?;
+    }
+
+    // Fewer guesses earn more points
+    let award = points::Award {
+        user_id: serenity::UserId::new(user_id as u64),
+        points: if solved {
+            (MAX_GUESSES + 1 - guesses) as u32
+        } else {
+            0
+        },
+        won: solved,
+        albums: if solved {
+            vec![puzzle.album.clone()]
+        } else {
+            Vec::new()
+        },
+    };
+
+    points::record_game(conn, guild_id, Game::Songle, &[award])
+}
+
+fn board(guesses: &[String], answer: &str, with_letters: bool) -> String {
+    guesses
+        .iter()
+        .map(|guess| {
+            if with_letters {
+                format!("{} `{}`", feedback(guess, answer), guess)
+            } else {
+                feedback(guess, answer)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    subcommands("guess", "share", "stats"),
+    subcommand_required
+)]
+
+/// Guess the daily song title in six tries
+pub async fn songle(_: Context<'_>) -> Result<(), Error> {
+    // This will never be called, because `subcommand_required` parameter is set
+    Ok(())
+}
+
+/// Guess today's song title
+#[poise::command(prefix_command, slash_command, ephemeral)]
+pub async fn guess(
+    ctx: Context<'_>,
+    #[description = "Your guess, as letters only"] word: String,
+) -> Result<(), Error> {
+    let today = Utc::now().date_naive();
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get() as i64);
+    let user_id = ctx.author().id.get() as i64;
+    let guess = letters(&word);
+    let db = ctx.data().db.clone();
+
+    let outcome = tokio::task::spawn_blocking(
+        move || -> Result<Result<GuessOutcome, String>, rusqlite::Error> {
+            let db_lock = db.blocking_lock();
+
+            let Some(puzzle) = todays_puzzle(&db_lock, today)? else {
+                return Ok(Err(format!(
+                    "No song title has between {} and {} letters.",
+                    MIN_LETTERS, MAX_LETTERS
+                )));
+            };
+
+            let mut guesses = load_guesses(&db_lock, today, user_id)?;
+            let finished =
+                guesses.len() >= MAX_GUESSES || guesses.last() == Some(&puzzle.answer);
+            if finished {
+                return Ok(Err(format!(
+                    "You already finished Songle #{}. Come back tomorrow, or use `/songle share`!",
+                    puzzle.number
+                )));
+            }
+
+            if guess.chars().count() != puzzle.answer.chars().count() {
+                return Ok(Err(format!(
+                    "Today's title has {} letters, but your guess has {}.",
+                    puzzle.answer.chars().count(),
+                    guess.chars().count()
+                )));
+            }
+
+            db_lock.execute(
+                "INSERT INTO songle_guesses (day, user_id, guess_number, guess) VALUES (?1, ?2, ?3, ?4)",
+                params![today.to_string(), user_id, guesses.len() as i64, guess],
+            )?;
+            guesses.push(guess.clone());
+
+            let solved = guess == puzzle.answer;
+            let unlocked = if solved || guesses.len() >= MAX_GUESSES {
+                record_result(
+                    &db_lock,
+                    guild_id,
+                    today,
+                    user_id,
+                    &puzzle,
+                    solved,
+                    guesses.len(),
+                )?
+            } else {
+                Vec::new()
+            };
+
+            Ok(Ok(GuessOutcome {
+                puzzle,
+                guesses,
+                unlocked,
+            }))
+        },
+    )
+    .await??;
+
+    let GuessOutcome {
+        puzzle,
+        guesses,
+        unlocked,
+    } = match outcome {
+        Ok(outcome) => outcome,
+        Err(message) => {
+            let guess_fail_embed = serenity::CreateEmbed::new()
+                .title("Error")
+                .color(Color::RED)
+                .description(message);
+            ctx.send(poise::CreateReply::default().embed(guess_fail_embed))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let solved = guesses.last() == Some(&puzzle.answer);
+    let status = if solved {
+        format!(
+            "Solved in {}/{}! It was **{}**.",
+            guesses.len(),
+            MAX_GUESSES,
+            puzzle.title
+        )
+    } else if guesses.len() >= MAX_GUESSES {
+        format!("Out of guesses. It was **{}**.", puzzle.title)
+    } else {
+        format!("{} guess(es) left.", MAX_GUESSES - guesses.len())
+    };
+
+    let board_embed = serenity::CreateEmbed::new()
+        .title(format!("Songle #{}", puzzle.number))
+        .color(if solved {
+            Color::DARK_GREEN
+        } else {
+            Color::DARK_GOLD
+        })
+        .description(format!(
+            "{}\n\n{}",
+            board(&guesses, &puzzle.answer, true),
+            status
+        ));
+
+    ctx.send(poise::CreateReply::default().embed(board_embed))
+        .await?;
+
+    points::announce(ctx, ctx.channel_id(), &unlocked).await?;
+
+    Ok(())
+}
+
+/// Share today's result without spoiling the answer
+#[poise::command(prefix_command, slash_command)]
+pub async fn share(ctx: Context<'_>) -> Result<(), Error> {
+    let today = Utc::now().date_naive();
+    let user_id = ctx.author().id.get() as i64;
+    let db = ctx.data().db.clone();
+
+    let played = tokio::task::spawn_blocking(
+        move || -> Result<Option<(Puzzle, Vec<String>)>, rusqlite::Error> {
+            let db_lock = db.blocking_lock();
+
+            let Some(puzzle) = todays_puzzle(&db_lock, today)? else {
+                return Ok(None);
+            };
+            let guesses = load_guesses(&db_lock, today, user_id)?;
+
+            Ok(Some((puzzle, guesses)))
+        },
+    )
+    .await??;
+
+    let Some((puzzle, guesses)) = played.filter(|(puzzle, guesses)| {
+        guesses.len() >= MAX_GUESSES || guesses.last() == Some(&puzzle.answer)
+    }) else {
+        let not_finished_embed = serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description("Finish today's puzzle with `/songle guess` before sharing it.");
+        ctx.send(
+            poise::CreateReply::default()
+                .embed(not_finished_embed)
+                .ephemeral(true),
+        )
+        .await?;
+        return Ok(());
+    };
+
+    let score = if guesses.last() == Some(&puzzle.answer) {
+        guesses.len().to_string()
+    } else {
+        "X".to_string()
+    };
+
+    ctx.say(format!(
+        "Songle #{} {}/{}\n{}",
+        puzzle.number,
+        score,
+        MAX_GUESSES,
+        board(&guesses, &puzzle.answer, false)
+    ))
+    .await?;
+
+    Ok(())
+}
+
+/// Show Songle statistics
+#[poise::command(prefix_command, slash_command)]
+pub async fn stats(
+    ctx: Context<'_>,
+    #[description = "Whose statistics to show (yours by default)"] user: Option<serenity::User>,
+) -> Result<(), Error> {
+    let user = user.as_ref().unwrap_or_else(|| ctx.author());
+    let user_id = user.id.get() as i64;
+    let yesterday = Utc::now()
+        .date_naive()
+        .checked_sub_days(Days::new(1))
+        .map(|day| day.to_string())
+        .unwrap_or_default();
+    let db = ctx.data().db.clone();
+
+    let stats = tokio::task::spawn_blocking(move || -> Result<Stats, rusqlite::Error> {
+        let db_lock = db.blocking_lock();
+
+        let (played, wins): (u32, u32) = db_lock.query_row(
+            "SELECT COUNT(*), COALESCE(SUM(solved), 0) FROM songle_results WHERE user_id = ?1",
+            params![user_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+
+        let mut distribution = vec![0; MAX_GUESSES];
+        let mut stmt = db_lock.prepare(
+            "SELECT guesses, COUNT(*) FROM songle_results
+                 WHERE user_id = ?1 AND solved = 1
+                 GROUP BY guesses",
+        )?;
+        let count_iter = stmt.query_map(params![user_id], |row| {
+            Ok((row.get::<_, usize>(0)?, row.get::<_, u32>(1)?))
+        })?;
+        for count in count_iter {
+            let (guesses, count) = count?;
+            if let Some(slot) = distribution.get_mut(guesses.saturating_sub(1)) {
+                *slot = count;
+            }
+        }
+
+        // A streak only counts as current when the last win was today or yesterday
+        let (current_streak, max_streak) = db_lock
+            .query_row(
+                "SELECT CASE WHEN last_win_day >= ?2 THEN current_streak ELSE 0 END, max_streak
+                     FROM songle_streaks WHERE user_id = ?1",
+                params![user_id, yesterday],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?
+            .unwrap_or((0, 0));
+
+        Ok(Stats {
+            played,
+            wins,
+            distribution,
+            current_streak,
+            max_streak,
+        })
+    })
+    .await??;
+
+    if stats.played == 0 {
+        let no_stats_embed = serenity::CreateEmbed::new()
+            .title("No Statistics")
+            .description(format!("{} hasn't finished a Songle yet.", user.name));
+
+        ctx.send(poise::CreateReply::default().embed(no_stats_embed))
+            .await?;
+
+        return Ok(());
+    }
+
+    let most = stats
+        .distribution
+        .iter()
+        .copied()
+        .max()
+        .unwrap_or_default()
+        .max(1);
+    let chart = stats
+        .distribution
+        .iter()
+        .enumerate()
+        .map(|(i, count)| {
+            format!(
+                "`{}` {} {}",
+                i + 1,
+                "🟩".repeat((count * 10).div_ceil(most) as usize),
+                count
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let stats_embed = serenity::CreateEmbed::new()
+        .title(format!("{}'s Songle Statistics", user.name))
+        .color(Color::DARK_GREEN)
+        .field("Played", stats.played.to_string(), true)
+        .field("Win %", (stats.wins * 100 / stats.played).to_string(), true)
+        .field("Current Streak", stats.current_streak.to_string(), true)
+        .field("Max Streak", stats.max_streak.to_string(), true)
+        .field("Guess Distribution", chart, false);
+
+    ctx.send(poise::CreateReply::default().embed(stats_embed))
+        .await?;
+
+    Ok(())
+}
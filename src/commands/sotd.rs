@@ -0,0 +1,543 @@
+use std::{collections::HashSet, sync::Arc, time::Duration};
+
+use chrono::{FixedOffset, NaiveTime, Utc};
+use poise::serenity_prelude::{self as serenity, Color, Mentionable};
+use rusqlite::{Connection, OptionalExtension, params};
+use tokio::sync::Mutex;
+
+use crate::{
+    Context, Error,
+    commands::{fav, rate},
+    sampling,
+};
+
+/// How often the scheduler checks whether a song of the day is due
+const SCHEDULER_INTERVAL: Duration = Duration::from_secs(30);
+
+struct SotdConfig {
+    channel_id: u64,
+    post_time: NaiveTime,
+    utc_offset: FixedOffset,
+    album: Option<String>,
+    tag: Option<String>,
+    enabled: bool,
+    last_posted: Option<String>,
+}
+
+struct Song {
+    id: i32,
+    title: String,
+    album: String,
+}
+
+fn load_config(conn: &Connection, guild_id: i64) -> Result<Option<SotdConfig>, rusqlite::Error> {
+    conn.query_row(
+        "SELECT channel_id, post_time, utc_offset, album, tag, enabled, last_posted
+         FROM sotd_config WHERE guild_id = ?1",
+        params![guild_id],
+        |row| {
+            Ok(SotdConfig {
+                channel_id: row.get::<_, i64>(0)? as u64,
+                post_time: NaiveTime::parse_from_str(&row.get::<_, String>(1)?, "%H:%M")
+                    .unwrap_or_default(),
+                utc_offset: FixedOffset::east_opt(row.get(2)?)
+                    .unwrap_or(FixedOffset::east_opt(0).unwrap()),
+                album: row.get(3)?,
+                tag: row.get(4)?,
+                enabled: row.get(5)?,
+                last_posted: row.get(6)?,
+            })
+        },
+    )
+    .optional()
+}
+
+/// Today's date in the guild's configured time zone
+fn local_date(config: &SotdConfig) -> String {
+    Utc::now()
+        .with_timezone(&config.utc_offset)
+        .date_naive()
+        .to_string()
+}
+
+/// Picks a song that hasn't been song of the day yet.
+///
+/// Once every matching song has been posted, the least recently posted ones come around again.
+fn draw_song_of_the_day(
+    conn: &Connection,
+    song_ids: &sampling::SongIdCache,
+    guild_id: i64,
+    config: &SotdConfig,
+) -> Result<Option<Song>, rusqlite::Error> {
+    let album_ids = song_ids.get(conn, config.album.as_deref().unwrap_or(""))?;
+
+    let candidates: Vec<i32> = match &config.tag {
+        Some(tag) => {
+            let mut stmt = conn.prepare("SELECT song_id FROM song_tags WHERE tag = ?1")?;
+            let tagged = stmt
+                .query_map(params![tag], |row| row.get(0))?
+                .collect::<Result<HashSet<i32>, _>>()?;
+
+            album_ids
+                .iter()
+                .copied()
+                .filter(|id| tagged.contains(id))
+                .collect()
+        }
+        None => album_ids.to_vec(),
+    };
+
+    let mut stmt =
+        conn.prepare("SELECT song_id FROM sotd_history WHERE guild_id = ?1 ORDER BY id DESC")?;
+    let posted = stmt
+        .query_map(params![guild_id], |row| row.get(0))?
+        .collect::<Result<Vec<i32>, _>>()?;
+
+    let posted_set: HashSet<i32> = posted.iter().copied().collect();
+    let fresh: Vec<i32> = candidates
+        .iter()
+        .copied()
+        .filter(|id| !posted_set.contains(id))
+        .collect();
+
+    let song_id = if fresh.is_empty() {
+        // Everything has been posted; take the song whose last post is the oldest
+        candidates
+            .iter()
+            .copied()
+            .max_by_key(|id| posted.iter().position(|posted_id| posted_id == id))
+    } else {
+        sampling::uniform_sample(&fresh, 1, &HashSet::new(), &mut rand::thread_rng())
+            .first()
+            .copied()
+    };
+
+    let Some(song_id) = song_id else {
+        return Ok(None);
+    };
+
+    let song = conn.query_row(
+        "SELECT id, title, album FROM songs WHERE id = ?1",
+        params![song_id],
+        |row| {
+            Ok(Song {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                album: row.get(2)?,
+            })
+        },
+    )?;
+
+    Ok(Some(song))
+}
+
+/// Records a posted song so it doesn't come up again soon.
+///
+/// Only the scheduled post marks the day as done; an extra `/sotd now` post doesn't.
+fn record_song_of_the_day(
+    conn: &Connection,
+    guild_id: i64,
+    song_id: i32,
+    posted_on: &str,
+    scheduled: bool,
+) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "INSERT INTO sotd_history (guild_id, song_id, posted_on, posted_at)
+         VALUES (?1, ?2, ?3, unixepoch())",
+        params![guild_id, song_id, posted_on],
+    )?;
+
+    if scheduled {
+        conn.execute(
+            "UPDATE sotd_config SET last_posted = ?2 WHERE guild_id = ?1",
+            params![guild_id, posted_on],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Draws and posts the song of the day to the guild's configured channel.
+///
+/// Nothing is recorded until the message went out, so a failed post is retried by the
+/// scheduler and doesn't use up the song.
+async fn post_song_of_the_day(
+    http: &serenity::Http,
+    db: Arc<Mutex<Connection>>,
+    song_ids: Arc<sampling::SongIdCache>,
+    guild_id: i64,
+    scheduled: bool,
+) -> Result<Option<Song>, Error> {
+    let db_cloned = db.clone();
+    let drawn = tokio::task::spawn_blocking(
+        move || -> Result<Option<(u64, String, Song, u32)>, rusqlite::Error> {
+            let db_lock = db_cloned.blocking_lock();
+
+            let Some(config) = load_config(&db_lock, guild_id)? else {
+                return Ok(None);
+            };
+
+            let Some(song) = draw_song_of_the_day(&db_lock, &song_ids, guild_id, &config)? else {
+                return Ok(None);
+            };
+            let favorites = fav::favorite_count(&db_lock, song.id)?;
+
+            Ok(Some((
+                config.channel_id,
+                local_date(&config),
+                song,
+                favorites,
+            )))
+        },
+    )
+    .await??;
+
+    let Some((channel_id, today, song, favorites)) = drawn else {
+        return Ok(None);
+    };
+
+    let sotd_embed = serenity::CreateEmbed::new()
+        .title("Song of the Day")
+        .color(Color::GOLD)
+        .description(format!("**{}** — {}", song.title, song.album))
+        .footer(serenity::CreateEmbedFooter::new(format!(
+            "ID: {} • ♥ {} favorite(s)",
+            song.id, favorites
+        )));
+
+    serenity::ChannelId::new(channel_id)
+        .send_message(
+            http,
+            serenity::CreateMessage::new()
+                .embed(sotd_embed)
+                .components(rate::rating_buttons(song.id)),
+        )
+        .await?;
+
+    let song_id = song.id;
+    tokio::task::spawn_blocking(move || {
+        let db_lock = db.blocking_lock();
+        record_song_of_the_day(&db_lock, guild_id, song_id, &today, scheduled)
+    })
+    .await??;
+
+    Ok(Some(song))
+}
+
+/// Background task that posts the song of the day once the configured local time has passed.
+///
+/// The schedule lives in the database, so a restart picks up where it left off and a missed
+/// post is made as soon as the bot is back.
+pub async fn scheduler(
+    http: Arc<serenity::Http>,
+    db: Arc<Mutex<Connection>>,
+    song_ids: Arc<sampling::SongIdCache>,
+) {
+    let mut interval = tokio::time::interval(SCHEDULER_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let db_cloned = db.clone();
+        let due = tokio::task::spawn_blocking(move || -> Result<Vec<i64>, rusqlite::Error> {
+            let db_lock = db_cloned.blocking_lock();
+
+            let mut stmt = db_lock.prepare("SELECT guild_id FROM sotd_config WHERE enabled = 1")?;
+            let guild_ids = stmt
+                .query_map([], |row| row.get(0))?
+                .collect::<Result<Vec<i64>, _>>()?;
+
+            let mut due = Vec::new();
+            for guild_id in guild_ids {
+                let Some(config) = load_config(&db_lock, guild_id)? else {
+                    continue;
+                };
+
+                let now = Utc::now().with_timezone(&config.utc_offset);
+                let today = now.date_naive().to_string();
+                if now.time() >= config.post_time && config.last_posted.as_ref() != Some(&today) {
+                    due.push(guild_id);
+                }
+            }
+
+            Ok(due)
+        })
+        .await;
+
+        let due = match due {
+            Ok(Ok(due)) => due,
+            Ok(Err(err)) => {
+                eprintln!("Error while checking song of the day schedules: {}", err);
+                continue;
+            }
+            Err(err) => {
+                eprintln!("Song of the day scheduler task failed: {}", err);
+                continue;
+            }
+        };
+
+        for guild_id in due {
+            if let Err(err) =
+                post_song_of_the_day(&http, db.clone(), song_ids.clone(), guild_id, true).await
+            {
+                eprintln!(
+                    "Error while posting song of the day for guild {}: {}",
+                    guild_id, err
+                );
+            }
+        }
+    }
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    subcommands("config", "now", "history"),
+    subcommand_required
+)]
+
+/// Configure, post or review the song of the day
+pub async fn sotd(_: Context<'_>) -> Result<(), Error> {
+    // This will never be called, because `subcommand_required` parameter is set
+    Ok(())
+}
+
+/// Configure where and when the song of the day is posted
+#[poise::command(
+    prefix_command,
+    slash_command,
+    check = "crate::checks::check_is_moderator"
+)]
+pub async fn config(
+    ctx: Context<'_>,
+    #[description = "Channel to post the song of the day in"] channel: Option<serenity::ChannelId>,
+    #[description = "Local time to post at, e.g. 18:30"] time: Option<String>,
+    #[description = "UTC offset of the local time, e.g. +02:00"] utc_offset: Option<String>,
+    #[autocomplete = "crate::commands::new::autocomplete_album"]
+    #[description = "Only pick songs from this album (\"any\" to clear)"]
+    album: Option<String>,
+    #[description = "Only pick songs with this tag (\"any\" to clear)"] tag: Option<String>,
+    #[description = "Whether the song of the day is posted"] enabled: Option<bool>,
+) -> Result<(), Error> {
+    let Some(guild_id) = ctx.guild_id() else {
+        ctx.say("The song of the day can only be configured in a server.")
+            .await?;
+        return Ok(());
+    };
+    let guild_id = guild_id.get() as i64;
+
+    let post_time = match time
+        .as_deref()
+        .map(|time| NaiveTime::parse_from_str(time, "%H:%M"))
+    {
+        Some(Err(_)) => {
+            let invalid_time_embed = serenity::CreateEmbed::new()
+                .title("Error")
+                .color(Color::RED)
+                .description("The time must be in 24-hour `HH:MM` format.");
+            ctx.send(poise::CreateReply::default().embed(invalid_time_embed))
+                .await?;
+            return Ok(());
+        }
+        Some(Ok(post_time)) => Some(post_time),
+        None => None,
+    };
+
+    let utc_offset = match utc_offset.as_deref().map(str::parse::<FixedOffset>) {
+        Some(Err(_)) => {
+            let invalid_offset_embed = serenity::CreateEmbed::new()
+                .title("Error")
+                .color(Color::RED)
+                .description("The UTC offset must look like `+02:00` or `-05:30`.");
+            ctx.send(poise::CreateReply::default().embed(invalid_offset_embed))
+                .await?;
+            return Ok(());
+        }
+        Some(Ok(utc_offset)) => Some(utc_offset),
+        None => None,
+    };
+
+    // "any" clears a filter, leaving it out keeps the current one
+    let clear_any = |filter: Option<String>| {
+        filter.map(|filter| {
+            let filter = filter.trim().to_string();
+            (!filter.eq_ignore_ascii_case("any")).then_some(filter)
+        })
+    };
+    let album = clear_any(album);
+    let tag = clear_any(tag).map(|tag| tag.map(|tag| tag.to_lowercase()));
+
+    let channel_id = channel.unwrap_or(ctx.channel_id()).get() as i64;
+    let channel_given = channel.is_some();
+
+    let db = ctx.data().db.clone();
+
+    let config = tokio::task::spawn_blocking(move || -> Result<SotdConfig, rusqlite::Error> {
+        let db_lock = db.blocking_lock();
+
+        let mut config = load_config(&db_lock, guild_id)?.unwrap_or(SotdConfig {
+            channel_id: channel_id as u64,
+            post_time: NaiveTime::from_hms_opt(12, 0, 0).unwrap(),
+            utc_offset: FixedOffset::east_opt(0).unwrap(),
+            album: None,
+            tag: None,
+            enabled: true,
+            last_posted: None,
+        });
+
+        if channel_given {
+            config.channel_id = channel_id as u64;
+        }
+        config.post_time = post_time.unwrap_or(config.post_time);
+        config.utc_offset = utc_offset.unwrap_or(config.utc_offset);
+        config.album = album.unwrap_or(config.album);
+        config.tag = tag.unwrap_or(config.tag);
+        config.enabled = enabled.unwrap_or(config.enabled);
+
+        db_lock.execute(
+            "INSERT INTO sotd_config (guild_id, channel_id, post_time, utc_offset, album, tag, enabled)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT(guild_id) DO UPDATE SET
+                channel_id = excluded.channel_id,
+                post_time = excluded.post_time,
+                utc_offset = excluded.utc_offset,
+                album = excluded.album,
+                tag = excluded.tag,
+                enabled = excluded.enabled",
+            params![
+                guild_id,
+                config.channel_id as i64,
+                config.post_time.format("%H:%M").to_string(),
+                config.utc_offset.local_minus_utc(),
+                config.album,
+                config.tag,
+                config.enabled
+            ],
+        )?;
+
+        Ok(config)
+    })
+    .await??;
+
+    let config_embed = serenity::CreateEmbed::new()
+        .title("Song of the Day Settings")
+        .color(Color::GOLD)
+        .field(
+            "Channel",
+            serenity::ChannelId::new(config.channel_id)
+                .mention()
+                .to_string(),
+            true,
+        )
+        .field(
+            "Time",
+            format!(
+                "{} (UTC{})",
+                config.post_time.format("%H:%M"),
+                config.utc_offset
+            ),
+            true,
+        )
+        .field("Enabled", if config.enabled { "Yes" } else { "No" }, true)
+        .field("Album", config.album.as_deref().unwrap_or("Any"), true)
+        .field("Tag", config.tag.as_deref().unwrap_or("Any"), true);
+
+    ctx.send(poise::CreateReply::default().embed(config_embed))
+        .await?;
+
+    Ok(())
+}
+
+/// Post a song of the day right away
+#[poise::command(
+    prefix_command,
+    slash_command,
+    check = "crate::checks::check_is_moderator"
+)]
+pub async fn now(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get() as i64);
+
+    let song = post_song_of_the_day(
+        ctx.http(),
+        ctx.data().db.clone(),
+        ctx.data().song_ids.clone(),
+        guild_id,
+        false,
+    )
+    .await?;
+
+    let embed = match song {
+        Some(song) => serenity::CreateEmbed::new()
+            .title("Success")
+            .color(Color::DARK_GREEN)
+            .description(format!("Posted '{}' as the song of the day.", song.title)),
+        None => serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description(
+                "Couldn't post a song of the day. Configure it with `/sotd config` and make sure matching songs exist.",
+            ),
+    };
+
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+    Ok(())
+}
+
+/// Show the most recent songs of the day
+#[poise::command(prefix_command, slash_command)]
+pub async fn history(
+    ctx: Context<'_>,
+    #[description = "Amount of days to show"] amount: Option<u32>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get() as i64);
+    let amount = amount.unwrap_or(10).min(25);
+
+    let db = ctx.data().db.clone();
+
+    let entries = tokio::task::spawn_blocking(move || -> Result<Vec<String>, rusqlite::Error> {
+        let db_lock = db.blocking_lock();
+
+        let mut stmt = db_lock.prepare(
+            "SELECT h.posted_on, s.title, s.album FROM sotd_history h
+             LEFT JOIN songs s ON s.id = h.song_id
+             WHERE h.guild_id = ?1
+             ORDER BY h.id DESC LIMIT ?2",
+        )?;
+
+        let entry_iter = stmt.query_map(params![guild_id, amount], |row| {
+            let title: Option<String> = row.get(1)?;
+            let album: Option<String> = row.get(2)?;
+            Ok(match (title, album) {
+                (Some(title), Some(album)) => {
+                    format!("`{}` — {} ({})", row.get::<_, String>(0)?, title, album)
+                }
+                _ => format!("`{}` — *deleted song*", row.get::<_, String>(0)?),
+            })
+        })?;
+
+        entry_iter.collect()
+    })
+    .await??;
+
+    if entries.is_empty() {
+        let no_history_embed = serenity::CreateEmbed::new()
+            .title("No History")
+            .description("No song of the day has been posted yet.");
+
+        ctx.send(poise::CreateReply::default().embed(no_history_embed))
+            .await?;
+
+        return Ok(());
+    }
+
+    let history_embed = serenity::CreateEmbed::new()
+        .title("Song of the Day History")
+        .color(Color::GOLD)
+        .description(entries.join("\n"));
+
+    ctx.send(poise::CreateReply::default().embed(history_embed))
+        .await?;
+
+    Ok(())
+}
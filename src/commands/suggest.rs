@@ -0,0 +1,347 @@
+use crate::{
+    Context, Data, Error, checks,
+    commands::new::insert_song,
+    review::{self, ReviewOutcome},
+};
+use poise::serenity_prelude::{self as serenity, Color, Mentionable};
+use rusqlite::{Connection, OptionalExtension, params};
+
+const REVIEW_PREFIX: &str = "suggestion-review:";
+/// Columns read by `suggestion_from_row`
+const SUGGESTION_COLUMNS: &str = "id, user_id, channel_id, title, album, created_at";
+
+struct Suggestion {
+    id: i64,
+    user_id: u64,
+    channel_id: u64,
+    title: String,
+    album: String,
+    created_at: i64,
+}
+
+fn suggestion_from_row(row: &rusqlite::Row) -> Result<Suggestion, rusqlite::Error> {
+    Ok(Suggestion {
+        id: row.get(0)?,
+        user_id: row.get::<_, i64>(1)? as u64,
+        channel_id: row.get::<_, i64>(2)? as u64,
+        title: row.get(3)?,
+        album: row.get(4)?,
+        created_at: row.get(5)?,
+    })
+}
+
+/// What happened to a suggestion after a moderator reviewed it
+enum Review {
+    Approved { song_id: i64 },
+    Duplicate,
+    Rejected,
+}
+
+/// Oldest pending suggestion of the guild and the number of pending suggestions
+fn next_pending(
+    conn: &Connection,
+    guild_id: i64,
+) -> Result<(Option<Suggestion>, u32), rusqlite::Error> {
+    review::next_pending(
+        conn,
+        "song_suggestions",
+        SUGGESTION_COLUMNS,
+        guild_id,
+        suggestion_from_row,
+    )
+}
+
+fn review_embed(suggestion: Option<&Suggestion>, pending: u32) -> serenity::CreateEmbed {
+    match suggestion {
+        Some(suggestion) => serenity::CreateEmbed::new()
+            .title("Song Suggestion")
+            .color(Color::BLUE)
+            .field("Title", &suggestion.title, true)
+            .field("Album", &suggestion.album, true)
+            .field(
+                "Suggested by",
+                format!(
+                    "{} <t:{}:R>",
+                    serenity::UserId::new(suggestion.user_id).mention(),
+                    suggestion.created_at
+                ),
+                false,
+            )
+            .footer(serenity::CreateEmbedFooter::new(format!(
+                "Suggestion #{} • {} pending",
+                suggestion.id, pending
+            ))),
+        None => serenity::CreateEmbed::new()
+            .title("Song Suggestions")
+            .description("There are no pending suggestions."),
+    }
+}
+
+fn review_buttons(suggestion: Option<&Suggestion>) -> Vec<serenity::CreateActionRow> {
+    review::review_buttons(
+        REVIEW_PREFIX,
+        suggestion.map(|suggestion| suggestion.id),
+        "Approve",
+    )
+}
+
+/// Suggest a song that's missing from the list
+#[poise::command(prefix_command, slash_command)]
+pub async fn suggest(
+    ctx: Context<'_>,
+    #[description = "Title of the song"] title: String,
+    #[autocomplete = "crate::commands::new::autocomplete_album"]
+    #[description = "Album of the song"]
+    album: String,
+) -> Result<(), Error> {
+    let title = title.trim().to_string();
+    let album = album.trim().to_string();
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get() as i64);
+    let channel_id = ctx.channel_id().get() as i64;
+    let user_id = ctx.author().id.get() as i64;
+    let db = ctx.data().db.clone();
+    let (title_cloned, album_cloned) = (title.clone(), album.clone());
+
+    let outcome =
+        tokio::task::spawn_blocking(move || -> Result<Result<i64, String>, rusqlite::Error> {
+            let db_lock = db.blocking_lock();
+
+            let exists: bool = db_lock.query_row(
+                "SELECT COUNT(*) FROM songs WHERE UPPER(title) = UPPER(?1)",
+                params![title_cloned],
+                |row| row.get(0),
+            )?;
+            if exists {
+                return Ok(Err(format!(
+                    "'{}' is already in the list.",
+                    title_cloned
+                )));
+            }
+
+            let already_suggested: bool = db_lock.query_row(
+                "SELECT COUNT(*) FROM song_suggestions
+                 WHERE guild_id = ?1 AND status = 'pending' AND UPPER(title) = UPPER(?2)",
+                params![guild_id, title_cloned],
+                |row| row.get(0),
+            )?;
+            if already_suggested {
+                return Ok(Err(format!(
+                    "'{}' has already been suggested and is waiting for review.",
+                    title_cloned
+                )));
+            }
+
+            db_lock.execute(
+                "INSERT INTO song_suggestions (guild_id, user_id, channel_id, title, album, status, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, 'pending', unixepoch())",
+                params![guild_id, user_id, channel_id, title_cloned, album_cloned],
+            )?;
+
+            Ok(Ok(db_lock.last_insert_rowid()))
+        })
+        .await??;
+
+    let embed = match outcome {
+        Ok(suggestion_id) => serenity::CreateEmbed::new()
+            .title("Success")
+            .color(Color::DARK_GREEN)
+            .description(format!(
+                "Suggested '{}' from '{}'. The moderators will review it and you'll be told the outcome.",
+                title, album
+            ))
+            .footer(serenity::CreateEmbedFooter::new(format!(
+                "Suggestion #{}",
+                suggestion_id
+            ))),
+        Err(message) => serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description(message),
+    };
+
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+
+    Ok(())
+}
+
+/// Review pending song suggestions
+#[poise::command(
+    prefix_command,
+    slash_command,
+    check = "crate::checks::check_is_moderator"
+)]
+pub async fn suggestions(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get() as i64);
+    let db = ctx.data().db.clone();
+
+    let (suggestion, pending) = tokio::task::spawn_blocking(move || {
+        let db_lock = db.blocking_lock();
+        next_pending(&db_lock, guild_id)
+    })
+    .await??;
+
+    ctx.send(
+        poise::CreateReply::default()
+            .embed(review_embed(suggestion.as_ref(), pending))
+            .components(review_buttons(suggestion.as_ref())),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Handles Approve/Reject clicks from the review queue, then shows the next suggestion
+pub async fn handle_review(
+    ctx: &serenity::Context,
+    interaction: &serenity::ComponentInteraction,
+    data: &Data,
+) -> Result<(), Error> {
+    let Some((suggestion_id, action)) =
+        review::parse_review_button(&interaction.data.custom_id, REVIEW_PREFIX)
+    else {
+        return Ok(());
+    };
+    let approve = action == "approve";
+
+    if !checks::check_interaction_moderator(ctx, interaction, data).await? {
+        return Ok(());
+    }
+
+    let guild_id = interaction.guild_id.map_or(0, |id| id.get() as i64);
+    let reviewer_id = interaction.user.id.get() as i64;
+    let db = data.db.clone();
+
+    let (reviewed, next, pending) = tokio::task::spawn_blocking(
+        move || -> Result<ReviewOutcome<Suggestion, Review>, rusqlite::Error> {
+            let db_lock = db.blocking_lock();
+            let tx = db_lock.unchecked_transaction()?;
+
+            // Another moderator may have reviewed it in the meantime
+            let suggestion = tx
+                .query_row(
+                    &format!(
+                        "SELECT {} FROM song_suggestions
+                         WHERE id = ?1 AND guild_id = ?2 AND status = 'pending'",
+                        SUGGESTION_COLUMNS
+                    ),
+                    params![suggestion_id, guild_id],
+                    suggestion_from_row,
+                )
+                .optional()?;
+
+            let reviewed = match suggestion {
+                Some(suggestion) => {
+                    let review = if approve {
+                        match insert_song(
+                            &tx,
+                            &suggestion.title,
+                            &suggestion.album,
+                            None,
+                            reviewer_id,
+                        )? {
+                            Some(song_id) => Review::Approved { song_id },
+                            None => Review::Duplicate,
+                        }
+                    } else {
+                        Review::Rejected
+                    };
+
+                    let (status, song_id) = match review {
+                        Review::Approved { song_id } => ("approved", Some(song_id)),
+                        Review::Duplicate | Review::Rejected => ("rejected", None),
+                    };
+                    tx.execute(
+                        "UPDATE song_suggestions
+                         SET status = ?2, reviewed_by = ?3, reviewed_at = unixepoch(), song_id = ?4
+                         WHERE id = ?1",
+                        params![suggestion.id, status, reviewer_id, song_id],
+                    )?;
+
+                    Some((suggestion, review))
+                }
+                None => None,
+            };
+            tx.commit()?;
+
+            let (next, pending) = next_pending(&db_lock, guild_id)?;
+
+            Ok((reviewed, next, pending))
+        },
+    )
+    .await??;
+
+    if matches!(reviewed, Some((_, Review::Approved { .. }))) {
+        data.song_ids.invalidate();
+    }
+
+    let status = match &reviewed {
+        Some((suggestion, Review::Approved { song_id })) => format!(
+            "✅ Approved '{}' and added it with ID: {}.",
+            suggestion.title, song_id
+        ),
+        Some((suggestion, Review::Duplicate)) => format!(
+            "⚠️ '{}' is already in the list, so the suggestion was rejected.",
+            suggestion.title
+        ),
+        Some((suggestion, Review::Rejected)) => format!("❌ Rejected '{}'.", suggestion.title),
+        None => "This suggestion has already been reviewed.".to_string(),
+    };
+
+    interaction
+        .create_response(
+            ctx,
+            serenity::CreateInteractionResponse::UpdateMessage(
+                serenity::CreateInteractionResponseMessage::new()
+                    .content(status)
+                    .embed(review_embed(next.as_ref(), pending))
+                    .components(review_buttons(next.as_ref())),
+            ),
+        )
+        .await?;
+
+    if let Some((suggestion, review)) = reviewed {
+        notify_suggester(ctx, &suggestion, &review).await?;
+    }
+
+    Ok(())
+}
+
+/// Tells the member how their suggestion was reviewed
+async fn notify_suggester(
+    ctx: &serenity::Context,
+    suggestion: &Suggestion,
+    review: &Review,
+) -> Result<(), serenity::Error> {
+    let outcome_embed = match review {
+        Review::Approved { song_id } => serenity::CreateEmbed::new()
+            .title("Suggestion Approved")
+            .color(Color::DARK_GREEN)
+            .description(format!(
+                "Your suggestion '{}' from '{}' has been added to the list with ID: {}.",
+                suggestion.title, suggestion.album, song_id
+            )),
+        Review::Duplicate => serenity::CreateEmbed::new()
+            .title("Suggestion Rejected")
+            .color(Color::RED)
+            .description(format!(
+                "Your suggestion '{}' is already in the list.",
+                suggestion.title
+            )),
+        Review::Rejected => serenity::CreateEmbed::new()
+            .title("Suggestion Rejected")
+            .color(Color::RED)
+            .description(format!(
+                "Your suggestion '{}' from '{}' was not added to the list.",
+                suggestion.title, suggestion.album
+            )),
+    };
+
+    review::notify_member(
+        ctx,
+        suggestion.user_id,
+        suggestion.channel_id,
+        outcome_embed,
+    )
+    .await
+}
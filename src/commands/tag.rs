@@ -0,0 +1,187 @@
+use crate::{Context, Error};
+use poise::serenity_prelude::{self as serenity, Color};
+use rusqlite::{OptionalExtension, params};
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    subcommands("add", "remove", "list"),
+    subcommand_required,
+    check = "crate::checks::check_is_moderator"
+)]
+
+/// Add, remove or list song tags
+pub async fn tag(_: Context<'_>) -> Result<(), Error> {
+    // This will never be called, because `subcommand_required` parameter is set
+    Ok(())
+}
+
+/// Tag a song
+#[poise::command(prefix_command, slash_command)]
+pub async fn add(
+    ctx: Context<'_>,
+    #[description = "The ID of the song to tag"] song_id: i32,
+    #[description = "The tag to add"] tag: String,
+) -> Result<(), Error> {
+    let tag = tag.trim().to_lowercase();
+    let tag_cloned = tag.clone();
+
+    let db = ctx.data().db.clone();
+
+    let title = tokio::task::spawn_blocking(move || -> Result<Option<String>, rusqlite::Error> {
+        let db_lock = db.blocking_lock();
+
+        let title: Option<String> = db_lock
+            .query_row(
+                "SELECT title FROM songs WHERE id = ?1",
+                params![song_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        if title.is_some() {
+            db_lock.execute(
+                "INSERT OR IGNORE INTO song_tags (song_id, tag) VALUES (?1, ?2)",
+                params![song_id, tag_cloned],
+            )?;
+        }
+
+        Ok(title)
+    })
+    .await??;
+
+    let embed = match title {
+        Some(title) => serenity::CreateEmbed::new()
+            .title("Success")
+            .color(Color::DARK_GREEN)
+            .description(format!("Tagged '{}' with `{}`.", title, tag)),
+        None => serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description(format!("No song found with ID: {}", song_id)),
+    };
+
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+    Ok(())
+}
+
+/// Remove a tag from a song
+#[poise::command(prefix_command, slash_command)]
+pub async fn remove(
+    ctx: Context<'_>,
+    #[description = "The ID of the song"] song_id: i32,
+    #[description = "The tag to remove"] tag: String,
+) -> Result<(), Error> {
+    let tag = tag.trim().to_lowercase();
+    let tag_cloned = tag.clone();
+
+    let db = ctx.data().db.clone();
+
+    let affected_rows = tokio::task::spawn_blocking(move || -> Result<usize, rusqlite::Error> {
+        let db_lock = db.blocking_lock();
+
+        db_lock.execute(
+            "DELETE FROM song_tags WHERE song_id = ?1 AND tag = ?2",
+            params![song_id, tag_cloned],
+        )
+    })
+    .await??;
+
+    let embed = if affected_rows == 0 {
+        serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description(format!("Song with ID: {} isn't tagged `{}`.", song_id, tag))
+    } else {
+        serenity::CreateEmbed::new()
+            .title("Success")
+            .color(Color::DARK_GREEN)
+            .description(format!("Removed `{}` from song with ID: {}.", tag, song_id))
+    };
+
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+    Ok(())
+}
+
+/// List all tags, or the songs with a tag
+#[poise::command(prefix_command, slash_command)]
+pub async fn list(
+    ctx: Context<'_>,
+    #[description = "Show the songs with this tag"] tag: Option<String>,
+) -> Result<(), Error> {
+    let tag = tag.map(|tag| tag.trim().to_lowercase());
+    let tag_cloned = tag.clone();
+
+    let db = ctx.data().db.clone();
+
+    let lines = tokio::task::spawn_blocking(move || -> Result<Vec<String>, rusqlite::Error> {
+        let db_lock = db.blocking_lock();
+
+        let mut lines = Vec::new();
+        match tag_cloned {
+            Some(tag) => {
+                let mut stmt = db_lock.prepare(
+                    "SELECT s.id, s.title, s.album FROM song_tags t
+                     JOIN songs s ON s.id = t.song_id
+                     WHERE t.tag = ?1 ORDER BY s.id",
+                )?;
+                let song_iter = stmt.query_map(params![tag], |row| {
+                    Ok(format!(
+                        "{} - {} ({})",
+                        row.get::<_, i32>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?
+                    ))
+                })?;
+                for song in song_iter {
+                    lines.push(song?);
+                }
+            }
+            None => {
+                let mut stmt = db_lock
+                    .prepare("SELECT tag, COUNT(*) FROM song_tags GROUP BY tag ORDER BY tag")?;
+                let tag_iter = stmt.query_map([], |row| {
+                    Ok(format!(
+                        "- `{}` ({} songs)",
+                        row.get::<_, String>(0)?,
+                        row.get::<_, i64>(1)?
+                    ))
+                })?;
+                for tag in tag_iter {
+                    lines.push(tag?);
+                }
+            }
+        }
+
+        Ok(lines)
+    })
+    .await??;
+
+    if lines.is_empty() {
+        let no_tags_embed = serenity::CreateEmbed::new()
+            .title("No Tags")
+            .description("No tagged songs were found in the database.");
+
+        ctx.send(poise::CreateReply::default().embed(no_tags_embed))
+            .await?;
+
+        return Ok(());
+    }
+
+    let title = match tag {
+        Some(tag) => format!("Songs tagged `{}`", tag),
+        None => "Tags".to_string(),
+    };
+
+    let tag_list_embed = serenity::CreateEmbed::new()
+        .title(title)
+        .color(Color::MAGENTA)
+        .description(lines.join("\n"));
+
+    ctx.send(poise::CreateReply::default().embed(tag_list_embed))
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,685 @@
+use std::{collections::HashMap, convert::Infallible, time::Duration};
+
+use embedded_graphics::{
+    mono_font::{MonoTextStyle, ascii::FONT_9X15, ascii::FONT_10X20},
+    pixelcolor::Rgb888,
+    prelude::*,
+    primitives::{PrimitiveStyle, Rectangle},
+    text::{Baseline, Text},
+};
+use futures::StreamExt;
+use poise::serenity_prelude::{self as serenity, Color};
+use rusqlite::{Connection, params};
+
+use crate::{Context, Error};
+
+/// Tiers from best to worst, with the colour of their label in rendered images
+const TIERS: [(&str, Rgb888); 5] = [
+    ("S", Rgb888::new(255, 127, 127)),
+    ("A", Rgb888::new(255, 191, 127)),
+    ("B", Rgb888::new(255, 223, 127)),
+    ("C", Rgb888::new(191, 255, 127)),
+    ("D", Rgb888::new(127, 191, 255)),
+];
+/// Songs per page of the editor, the most a select menu can hold
+const PAGE_SIZE: usize = 25;
+/// The editor closes after this long without a click
+const EDITOR_TIMEOUT: Duration = Duration::from_secs(300);
+
+const IMAGE_WIDTH: u32 = 960;
+const LABEL_WIDTH: u32 = 80;
+const ROW_PADDING: u32 = 8;
+const CHIP_HEIGHT: u32 = 24;
+const CHIP_GAP: u32 = 6;
+/// Longest song title drawn on a chip before it's cut off
+const MAX_CHIP_CHARS: usize = 32;
+const BACKGROUND: Rgb888 = Rgb888::new(26, 26, 30);
+const ROW_BACKGROUND: Rgb888 = Rgb888::new(44, 44, 52);
+const CHIP_BACKGROUND: Rgb888 = Rgb888::new(70, 70, 82);
+
+struct EditorSong {
+    id: i32,
+    title: String,
+    tier: Option<String>,
+}
+
+/// An RGB image that can be drawn on with `embedded-graphics`
+struct Canvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(width: u32, height: u32, background: Rgb888) -> Self {
+        let pixels =
+            [background.r(), background.g(), background.b()].repeat((width * height) as usize);
+
+        Canvas {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    fn encode_png(&self) -> Result<Vec<u8>, png::EncodingError> {
+        let mut png_bytes = Vec::new();
+
+        let mut encoder = png::Encoder::new(&mut png_bytes, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+        writer.finish()?;
+
+        Ok(png_bytes)
+    }
+}
+
+impl OriginDimensions for Canvas {
+    fn size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+}
+
+impl DrawTarget for Canvas {
+    type Color = Rgb888;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            if point.x < 0 || point.y < 0 {
+                continue;
+            }
+            let (x, y) = (point.x as u32, point.y as u32);
+            if x >= self.width || y >= self.height {
+                continue;
+            }
+
+            let offset = ((y * self.width + x) * 3) as usize;
+            self.pixels[offset..offset + 3].copy_from_slice(&[color.r(), color.g(), color.b()]);
+        }
+
+        Ok(())
+    }
+}
+
+/// Title as it's drawn on a chip; the built-in fonts only cover ASCII
+fn chip_label(title: &str) -> String {
+    let mut label: String = title
+        .chars()
+        .map(|c| if c.is_ascii() { c } else { '?' })
+        .take(MAX_CHIP_CHARS)
+        .collect();
+    if title.chars().count() > MAX_CHIP_CHARS {
+        label.pop();
+        label.push('~');
+    }
+    label
+}
+
+fn chip_width(label: &str) -> u32 {
+    label.len() as u32 * FONT_9X15.character_size.width + 2 * CHIP_GAP
+}
+
+/// Lays chips out left to right, wrapping onto new lines, and returns their positions
+/// relative to the row along with the height of the row
+fn layout_row(labels: &[String]) -> (Vec<Point>, u32) {
+    let max_x = IMAGE_WIDTH - ROW_PADDING;
+    let mut x = LABEL_WIDTH + ROW_PADDING;
+    let mut y = ROW_PADDING;
+    let mut positions = Vec::new();
+
+    for label in labels {
+        let width = chip_width(label);
+        if x + width > max_x && x > LABEL_WIDTH + ROW_PADDING {
+            x = LABEL_WIDTH + ROW_PADDING;
+            y += CHIP_HEIGHT + CHIP_GAP;
+        }
+        positions.push(Point::new(x as i32, y as i32));
+        x += width + CHIP_GAP;
+    }
+
+    let height = (y + CHIP_HEIGHT + ROW_PADDING).max(LABEL_WIDTH);
+    (positions, height)
+}
+
+/// Renders a tier list as a PNG, one row per tier with a chip for every song
+fn render_tier_list(title: &str, tiers: &HashMap<String, Vec<String>>) -> Result<Vec<u8>, Error> {
+    let header_height = 40;
+
+    let rows: Vec<(&str, Rgb888, Vec<String>)> = TIERS
+        .iter()
+        .map(|(tier, color)| {
+            let labels = tiers
+                .get(*tier)
+                .map(|titles| titles.iter().map(|title| chip_label(title)).collect())
+                .unwrap_or_default();
+            (*tier, *color, labels)
+        })
+        .collect();
+    let layouts: Vec<(Vec<Point>, u32)> = rows
+        .iter()
+        .map(|(_, _, labels)| layout_row(labels))
+        .collect();
+
+    let height = header_height + layouts.iter().map(|(_, height)| height + 2).sum::<u32>();
+    let mut canvas = Canvas::new(IMAGE_WIDTH, height, BACKGROUND);
+
+    let header_style = MonoTextStyle::new(&FONT_10X20, Rgb888::WHITE);
+    let label_style = MonoTextStyle::new(&FONT_10X20, Rgb888::BLACK);
+    let chip_style = MonoTextStyle::new(&FONT_9X15, Rgb888::WHITE);
+
+    Text::with_baseline(
+        &chip_label(title),
+        Point::new(ROW_PADDING as i32, 10),
+        header_style,
+        Baseline::Top,
+    )
+    .draw(&mut canvas)?;
+
+    let mut top = header_height as i32;
+    for ((tier, color, labels), (positions, row_height)) in rows.iter().zip(&layouts) {
+        Rectangle::new(Point::new(0, top), Size::new(IMAGE_WIDTH, *row_height))
+            .into_styled(PrimitiveStyle::with_fill(ROW_BACKGROUND))
+            .draw(&mut canvas)?;
+        Rectangle::new(Point::new(0, top), Size::new(LABEL_WIDTH, *row_height))
+            .into_styled(PrimitiveStyle::with_fill(*color))
+            .draw(&mut canvas)?;
+        Text::with_baseline(
+            tier,
+            Point::new(
+                (LABEL_WIDTH / 2 - 5) as i32,
+                top + (*row_height / 2) as i32 - 10,
+            ),
+            label_style,
+            Baseline::Top,
+        )
+        .draw(&mut canvas)?;
+
+        for (label, position) in labels.iter().zip(positions) {
+            let origin = *position + Point::new(0, top);
+            Rectangle::new(origin, Size::new(chip_width(label), CHIP_HEIGHT))
+                .into_styled(PrimitiveStyle::with_fill(CHIP_BACKGROUND))
+                .draw(&mut canvas)?;
+            Text::with_baseline(
+                label,
+                origin + Point::new(CHIP_GAP as i32, 4),
+                chip_style,
+                Baseline::Top,
+            )
+            .draw(&mut canvas)?;
+        }
+
+        top += *row_height as i32 + 2;
+    }
+
+    Ok(canvas.encode_png()?)
+}
+
+/// Titles per tier in one user's tier list
+fn load_user_tiers(
+    conn: &Connection,
+    user_id: i64,
+) -> Result<HashMap<String, Vec<String>>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT t.tier, s.title FROM tier_entries t
+         JOIN songs s ON s.id = t.song_id
+         WHERE t.user_id = ?1
+         ORDER BY s.album, s.title",
+    )?;
+    let entry_iter = stmt.query_map(params![user_id], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+    })?;
+
+    let mut tiers: HashMap<String, Vec<String>> = HashMap::new();
+    for entry in entry_iter {
+        let (tier, title) = entry?;
+        tiers.entry(tier).or_default().push(title);
+    }
+
+    Ok(tiers)
+}
+
+/// Titles per tier from everyone's placements, placing each song by its average tier
+fn load_community_tiers(
+    conn: &Connection,
+    album: Option<&str>,
+) -> Result<(HashMap<String, Vec<String>>, u32), rusqlite::Error> {
+    // S counts as 0 and D as 4, so the rounded average is an index into `TIERS`
+    let mut stmt = conn.prepare(
+        "SELECT s.title, AVG(CASE t.tier
+                WHEN 'S' THEN 0 WHEN 'A' THEN 1 WHEN 'B' THEN 2 WHEN 'C' THEN 3 ELSE 4
+            END) AS average
+         FROM tier_entries t
+         JOIN songs s ON s.id = t.song_id
+         WHERE ?1 IS NULL OR s.album = ?1
+         GROUP BY t.song_id
+         ORDER BY average, s.title",
+    )?;
+    let song_iter = stmt.query_map(params![album], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?))
+    })?;
+
+    let mut tiers: HashMap<String, Vec<String>> = HashMap::new();
+    for song in song_iter {
+        let (title, average) = song?;
+        let index = (average.round() as usize).min(TIERS.len() - 1);
+        tiers
+            .entry(TIERS[index].0.to_string())
+            .or_default()
+            .push(title);
+    }
+
+    let users: u32 = conn.query_row(
+        "SELECT COUNT(DISTINCT t.user_id) FROM tier_entries t
+         JOIN songs s ON s.id = t.song_id
+         WHERE ?1 IS NULL OR s.album = ?1",
+        params![album],
+        |row| row.get(0),
+    )?;
+
+    Ok((tiers, users))
+}
+
+fn load_editor_songs(
+    conn: &Connection,
+    user_id: i64,
+    album: Option<&str>,
+) -> Result<Vec<EditorSong>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT s.id, s.title, t.tier FROM songs s
+         LEFT JOIN tier_entries t ON t.song_id = s.id AND t.user_id = ?1
+         WHERE ?2 IS NULL OR s.album = ?2
+         ORDER BY s.album, s.title",
+    )?;
+    let song_iter = stmt.query_map(params![user_id, album], |row| {
+        Ok(EditorSong {
+            id: row.get(0)?,
+            title: row.get(1)?,
+            tier: row.get(2)?,
+        })
+    })?;
+
+    song_iter.collect()
+}
+
+fn editor_embed(songs: &[EditorSong], page: usize) -> serenity::CreateEmbed {
+    let pages = songs.len().div_ceil(PAGE_SIZE);
+    let placed = songs.iter().filter(|song| song.tier.is_some()).count();
+
+    let song_list = songs
+        .iter()
+        .skip(page * PAGE_SIZE)
+        .take(PAGE_SIZE)
+        .map(|song| format!("`{}` {}", song.tier.as_deref().unwrap_or("-"), song.title))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    serenity::CreateEmbed::new()
+        .title("Tier List Editor")
+        .color(Color::FABLED_PINK)
+        .description(format!(
+            "Select songs, then click a tier to place them.\n\n{}",
+            song_list
+        ))
+        .footer(serenity::CreateEmbedFooter::new(format!(
+            "Page {}/{} • {} of {} song(s) placed",
+            page + 1,
+            pages,
+            placed,
+            songs.len()
+        )))
+}
+
+fn editor_components(
+    songs: &[EditorSong],
+    page: usize,
+    prefix: &str,
+    closed: bool,
+) -> Vec<serenity::CreateActionRow> {
+    let page_songs: Vec<&EditorSong> = songs
+        .iter()
+        .skip(page * PAGE_SIZE)
+        .take(PAGE_SIZE)
+        .collect();
+    let pages = songs.len().div_ceil(PAGE_SIZE);
+
+    let options = page_songs
+        .iter()
+        .map(|song| {
+            let label = match &song.tier {
+                Some(tier) => format!("[{}] {}", tier, song.title),
+                None => song.title.clone(),
+            };
+            serenity::CreateSelectMenuOption::new(
+                label.chars().take(100).collect::<String>(),
+                song.id.to_string(),
+            )
+        })
+        .collect();
+
+    let song_menu = serenity::CreateSelectMenu::new(
+        format!("{}songs", prefix),
+        serenity::CreateSelectMenuKind::String { options },
+    )
+    .placeholder("Songs to place")
+    .min_values(1)
+    .max_values(page_songs.len() as u8)
+    .disabled(closed);
+
+    let tier_buttons = TIERS
+        .iter()
+        .map(|(tier, _)| {
+            serenity::CreateButton::new(format!("{}tier:{}", prefix, tier))
+                .label(*tier)
+                .style(serenity::ButtonStyle::Primary)
+                .disabled(closed)
+        })
+        .collect();
+
+    let navigation = vec![
+        serenity::CreateButton::new(format!("{}clear", prefix))
+            .label("Remove from tiers")
+            .style(serenity::ButtonStyle::Danger)
+            .disabled(closed),
+        serenity::CreateButton::new(format!("{}previous", prefix))
+            .label("Previous")
+            .style(serenity::ButtonStyle::Secondary)
+            .disabled(closed || page == 0),
+        serenity::CreateButton::new(format!("{}next", prefix))
+            .label("Next")
+            .style(serenity::ButtonStyle::Secondary)
+            .disabled(closed || page + 1 >= pages),
+        serenity::CreateButton::new(format!("{}done", prefix))
+            .label("Done")
+            .style(serenity::ButtonStyle::Success)
+            .disabled(closed),
+    ];
+
+    vec![
+        serenity::CreateActionRow::SelectMenu(song_menu),
+        serenity::CreateActionRow::Buttons(tier_buttons),
+        serenity::CreateActionRow::Buttons(navigation),
+    ]
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    subcommands("edit", "show", "community"),
+    subcommand_required
+)]
+
+/// Build and share tier lists of songs
+pub async fn tierlist(_: Context<'_>) -> Result<(), Error> {
+    // This will never be called, because `subcommand_required` parameter is set
+    Ok(())
+}
+
+/// Place songs into S/A/B/C/D tiers
+#[poise::command(prefix_command, slash_command)]
+pub async fn edit(
+    ctx: Context<'_>,
+    #[autocomplete = "crate::commands::new::autocomplete_album"]
+    #[description = "Only rank songs from this album"]
+    album: Option<String>,
+) -> Result<(), Error> {
+    let user_id = ctx.author().id.get() as i64;
+    let db = ctx.data().db.clone();
+    let album_cloned = album.clone();
+
+    let mut songs = tokio::task::spawn_blocking(move || {
+        let db_lock = db.blocking_lock();
+        load_editor_songs(&db_lock, user_id, album_cloned.as_deref())
+    })
+    .await??;
+
+    if songs.is_empty() {
+        let no_songs_embed = serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description("No songs found to rank.");
+        ctx.send(
+            poise::CreateReply::default()
+                .embed(no_songs_embed)
+                .ephemeral(true),
+        )
+        .await?;
+        return Ok(());
+    }
+
+    let prefix = format!("tierlist-{}-", ctx.id());
+    let mut page = 0;
+    let mut selected: Vec<i32> = Vec::new();
+
+    let reply = ctx
+        .send(
+            poise::CreateReply::default()
+                .embed(editor_embed(&songs, page))
+                .components(editor_components(&songs, page, &prefix, false))
+                .ephemeral(true),
+        )
+        .await?;
+
+    let author_id = ctx.author().id;
+    let prefix_cloned = prefix.clone();
+    let mut interactions = serenity::ComponentInteractionCollector::new(ctx)
+        .filter(move |interaction| {
+            interaction.user.id == author_id
+                && interaction.data.custom_id.starts_with(&prefix_cloned)
+        })
+        .timeout(EDITOR_TIMEOUT)
+        .stream();
+
+    while let Some(interaction) = interactions.next().await {
+        let action = &interaction.data.custom_id[prefix.len()..];
+
+        // New tier placements: `Some(tier)` places the selection, `None` removes it
+        let mut placement: Option<Option<&str>> = None;
+        match action {
+            "songs" => {
+                if let serenity::ComponentInteractionDataKind::StringSelect { values } =
+                    &interaction.data.kind
+                {
+                    selected = values
+                        .iter()
+                        .filter_map(|value| value.parse().ok())
+                        .collect();
+                }
+            }
+            "clear" => placement = Some(None),
+            "previous" => {
+                page = page.saturating_sub(1);
+                selected.clear();
+            }
+            "next" => {
+                page += 1;
+                selected.clear();
+            }
+            "done" => {
+                interaction
+                    .create_response(
+                        ctx,
+                        serenity::CreateInteractionResponse::UpdateMessage(
+                            serenity::CreateInteractionResponseMessage::new()
+                                .embed(editor_embed(&songs, page))
+                                .components(editor_components(&songs, page, &prefix, true)),
+                        ),
+                    )
+                    .await?;
+                return Ok(());
+            }
+            _ => {
+                if let Some(tier) = action.strip_prefix("tier:") {
+                    placement = TIERS
+                        .iter()
+                        .find(|(name, _)| *name == tier)
+                        .map(|(name, _)| Some(*name));
+                }
+            }
+        }
+
+        if let Some(tier) = placement
+            && !selected.is_empty()
+        {
+            let db = ctx.data().db.clone();
+            let song_ids = selected.clone();
+            let tier_cloned = tier.map(str::to_string);
+
+            tokio::task::spawn_blocking(move || -> Result<(), rusqlite::Error> {
+                let db_lock = db.blocking_lock();
+                let tx = db_lock.unchecked_transaction()?;
+
+                for song_id in song_ids {
+                    match &tier_cloned {
+                        Some(tier) => tx.execute(
+                            "INSERT INTO tier_entries (user_id, song_id, tier) VALUES (?1, ?2, ?3)
+                             ON CONFLICT(user_id, song_id) DO UPDATE SET tier = excluded.tier",
+                            params![user_id, song_id, tier],
+                        )?,
+                        None => tx.execute(
+                            "DELETE FROM tier_entries WHERE user_id = ?1 AND song_id = ?2",
+                            params![user_id, song_id],
+                        )?,
+                    };
+                }
+
+                tx.commit()
+            })
+            .await??;
+
+            for song in songs.iter_mut().filter(|song| selected.contains(&song.id)) {
+                song.tier = tier.map(str::to_string);
+            }
+            selected.clear();
+        }
+
+        // Selecting songs keeps the menu as it is; everything else redraws the editor
+        let response = if action == "songs" {
+            serenity::CreateInteractionResponse::Acknowledge
+        } else {
+            serenity::CreateInteractionResponse::UpdateMessage(
+                serenity::CreateInteractionResponseMessage::new()
+                    .embed(editor_embed(&songs, page))
+                    .components(editor_components(&songs, page, &prefix, false)),
+            )
+        };
+        interaction.create_response(ctx, response).await?;
+    }
+
+    reply
+        .edit(
+            ctx,
+            poise::CreateReply::default()
+                .embed(editor_embed(&songs, page))
+                .components(editor_components(&songs, page, &prefix, true)),
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Show someone's tier list as an image
+#[poise::command(prefix_command, slash_command)]
+pub async fn show(
+    ctx: Context<'_>,
+    #[description = "Whose tier list to show (yours by default)"] user: Option<serenity::User>,
+) -> Result<(), Error> {
+    let user = user.as_ref().unwrap_or_else(|| ctx.author());
+    let user_id = user.id.get() as i64;
+    let db = ctx.data().db.clone();
+
+    let tiers = tokio::task::spawn_blocking(move || {
+        let db_lock = db.blocking_lock();
+        load_user_tiers(&db_lock, user_id)
+    })
+    .await??;
+
+    if tiers.is_empty() {
+        let no_tiers_embed = serenity::CreateEmbed::new()
+            .title("No Tier List")
+            .description(format!(
+                "{} hasn't placed any songs yet. Use `/tierlist edit` to start.",
+                user.name
+            ));
+
+        ctx.send(poise::CreateReply::default().embed(no_tiers_embed))
+            .await?;
+
+        return Ok(());
+    }
+
+    let title = format!("{}'s tier list", user.name);
+    let image = render_tier_list(&title, &tiers)?;
+
+    let tier_list_embed = serenity::CreateEmbed::new()
+        .title(title)
+        .color(Color::FABLED_PINK)
+        .image("attachment://tierlist.png");
+
+    ctx.send(
+        poise::CreateReply::default()
+            .embed(tier_list_embed)
+            .attachment(serenity::CreateAttachment::bytes(image, "tierlist.png")),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Show the tier list of everyone's placements combined
+#[poise::command(prefix_command, slash_command)]
+pub async fn community(
+    ctx: Context<'_>,
+    #[autocomplete = "crate::commands::new::autocomplete_album"]
+    #[description = "Only include songs from this album"]
+    album: Option<String>,
+) -> Result<(), Error> {
+    let db = ctx.data().db.clone();
+    let album_cloned = album.clone();
+
+    let (tiers, users) = tokio::task::spawn_blocking(move || {
+        let db_lock = db.blocking_lock();
+        load_community_tiers(&db_lock, album_cloned.as_deref())
+    })
+    .await??;
+
+    if tiers.is_empty() {
+        let no_tiers_embed = serenity::CreateEmbed::new()
+            .title("No Tier Lists")
+            .description("Nobody has placed any songs yet. Use `/tierlist edit` to start.");
+
+        ctx.send(poise::CreateReply::default().embed(no_tiers_embed))
+            .await?;
+
+        return Ok(());
+    }
+
+    let title = match &album {
+        Some(album) => format!("Community tier list: {}", album),
+        None => "Community tier list".to_string(),
+    };
+    let image = render_tier_list(&title, &tiers)?;
+
+    let tier_list_embed = serenity::CreateEmbed::new()
+        .title(title)
+        .color(Color::FABLED_PINK)
+        .image("attachment://tierlist.png")
+        .footer(serenity::CreateEmbedFooter::new(format!(
+            "Songs are placed by their average tier across {} user(s)",
+            users
+        )));
+
+    ctx.send(
+        poise::CreateReply::default()
+            .embed(tier_list_embed)
+            .attachment(serenity::CreateAttachment::bytes(image, "tierlist.png")),
+    )
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,349 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
+use futures::StreamExt;
+use poise::serenity_prelude::{self as serenity, Color, Mentionable};
+use rand::seq::SliceRandom;
+use rusqlite::params;
+
+use crate::{
+    Context, Error,
+    commands::leaderboard::send_leaderboard,
+    points::{self, Game, Period},
+    sampling,
+};
+
+/// How long players have to answer a round
+const ROUND_TIMEOUT: Duration = Duration::from_secs(20);
+const MAX_ROUNDS: u32 = 20;
+
+struct Song {
+    title: String,
+    album: String,
+}
+
+struct Round {
+    song: Song,
+    choices: Vec<String>,
+    answer: usize,
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    subcommands("start", "leaderboard"),
+    subcommand_required
+)]
+
+/// Guess which album a song belongs to
+pub async fn trivia(_: Context<'_>) -> Result<(), Error> {
+    // This will never be called, because `subcommand_required` parameter is set
+    Ok(())
+}
+
+/// Picks the songs for a game and four album choices for each of them
+fn prepare_rounds(
+    conn: &rusqlite::Connection,
+    song_ids: &sampling::SongIdCache,
+    rounds: usize,
+) -> Result<Vec<Round>, rusqlite::Error> {
+    let mut rng = rand::thread_rng();
+
+    let mut stmt = conn.prepare(
+        "SELECT name FROM albums WHERE name IS NOT NULL
+         UNION SELECT album FROM songs WHERE album IS NOT NULL",
+    )?;
+    let album_names = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<Vec<String>, _>>()?;
+
+    let ids = song_ids.get(conn, "")?;
+    let picked = sampling::uniform_sample(&ids, rounds, &HashSet::new(), &mut rng);
+
+    let mut stmt = conn.prepare("SELECT title, album FROM songs WHERE id = ?1")?;
+
+    let mut prepared = Vec::new();
+    for id in picked {
+        let song = stmt.query_row(params![id], |row| {
+            Ok(Song {
+                title: row.get(0)?,
+                album: row.get(1)?,
+            })
+        })?;
+
+        let wrong_albums: Vec<&String> = album_names
+            .iter()
+            .filter(|name| !name.eq_ignore_ascii_case(&song.album))
+            .collect();
+
+        // A round needs at least one wrong answer to make sense
+        if wrong_albums.is_empty() {
+            continue;
+        }
+
+        let mut choices: Vec<String> = wrong_albums
+            .choose_multiple(&mut rng, 3)
+            .map(|name| name.to_string())
+            .collect();
+        choices.push(song.album.clone());
+        choices.shuffle(&mut rng);
+
+        let answer = choices
+            .iter()
+            .position(|choice| *choice == song.album)
+            .unwrap_or_default();
+
+        prepared.push(Round {
+            song,
+            choices,
+            answer,
+        });
+    }
+
+    Ok(prepared)
+}
+
+/// Album buttons for a round; once `revealed`, they're disabled and the answer is highlighted
+fn round_buttons(round: &Round, prefix: &str, revealed: bool) -> Vec<serenity::CreateActionRow> {
+    let buttons = round
+        .choices
+        .iter()
+        .enumerate()
+        .map(|(i, choice)| {
+            let style = if revealed && i == round.answer {
+                serenity::ButtonStyle::Success
+            } else {
+                serenity::ButtonStyle::Secondary
+            };
+
+            serenity::CreateButton::new(format!("{}{}", prefix, i))
+                .label(choice.chars().take(80).collect::<String>())
+                .style(style)
+                .disabled(revealed)
+        })
+        .collect();
+
+    vec![serenity::CreateActionRow::Buttons(buttons)]
+}
+
+/// Start a game of album trivia in this channel
+#[poise::command(prefix_command, slash_command)]
+pub async fn start(
+    ctx: Context<'_>,
+    #[description = "Amount of rounds to play (5 by default)"] rounds: Option<u32>,
+) -> Result<(), Error> {
+    let rounds = rounds.unwrap_or(5).clamp(1, MAX_ROUNDS) as usize;
+
+    let _game = match ctx.data().active_games.start(ctx.channel_id(), "trivia") {
+        Ok(game) => game,
+        Err(running) => {
+            let busy_embed = serenity::CreateEmbed::new()
+                .title("Error")
+                .color(Color::RED)
+                .description(format!("A game of {} is already running here.", running));
+            ctx.send(
+                poise::CreateReply::default()
+                    .embed(busy_embed)
+                    .ephemeral(true),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    let db = ctx.data().db.clone();
+    let song_ids = ctx.data().song_ids.clone();
+
+    let prepared = tokio::task::spawn_blocking(move || {
+        let db_lock = db.blocking_lock();
+        prepare_rounds(&db_lock, &song_ids, rounds)
+    })
+    .await??;
+
+    if prepared.is_empty() {
+        let no_rounds_embed = serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description("Trivia needs songs in the database and at least two different albums.");
+        ctx.send(poise::CreateReply::default().embed(no_rounds_embed))
+            .await?;
+        return Ok(());
+    }
+
+    let start_embed = serenity::CreateEmbed::new()
+        .title("Album Trivia")
+        .color(Color::PURPLE)
+        .description(format!(
+            "{} round(s), {} seconds each. The first correct answer scores a point!",
+            prepared.len(),
+            ROUND_TIMEOUT.as_secs()
+        ));
+    ctx.send(poise::CreateReply::default().embed(start_embed))
+        .await?;
+
+    let mut scores: HashMap<serenity::UserId, u32> = HashMap::new();
+    let mut players: HashSet<serenity::UserId> = HashSet::new();
+    let mut guessed_albums: HashMap<serenity::UserId, Vec<String>> = HashMap::new();
+
+    for (round_index, round) in prepared.iter().enumerate() {
+        let prefix = format!("trivia-{}-{}-", ctx.id(), round_index);
+
+        let round_embed = serenity::CreateEmbed::new()
+            .title(format!("Round {}/{}", round_index + 1, prepared.len()))
+            .color(Color::PURPLE)
+            .description(format!("Which album is **{}** on?", round.song.title));
+
+        let mut message = ctx
+            .channel_id()
+            .send_message(
+                ctx,
+                serenity::CreateMessage::new()
+                    .embed(round_embed)
+                    .components(round_buttons(round, &prefix, false)),
+            )
+            .await?;
+
+        let mut answered = HashSet::new();
+        let mut winner = None;
+
+        let mut interactions = serenity::ComponentInteractionCollector::new(ctx)
+            .message_id(message.id)
+            .timeout(ROUND_TIMEOUT)
+            .stream();
+
+        while let Some(interaction) = interactions.next().await {
+            let Some(choice) = interaction
+                .data
+                .custom_id
+                .strip_prefix(&prefix)
+                .and_then(|choice| choice.parse::<usize>().ok())
+            else {
+                continue;
+            };
+
+            players.insert(interaction.user.id);
+
+            // Only the first answer of each player counts
+            if !answered.insert(interaction.user.id) {
+                interaction
+                    .create_response(
+                        ctx,
+                        serenity::CreateInteractionResponse::Message(
+                            serenity::CreateInteractionResponseMessage::new()
+                                .content("You already answered this round.")
+                                .ephemeral(true),
+                        ),
+                    )
+                    .await?;
+                continue;
+            }
+
+            if choice == round.answer {
+                interaction
+                    .create_response(ctx, serenity::CreateInteractionResponse::Acknowledge)
+                    .await?;
+                winner = Some(interaction.user.id);
+                break;
+            }
+
+            interaction
+                .create_response(
+                    ctx,
+                    serenity::CreateInteractionResponse::Message(
+                        serenity::CreateInteractionResponseMessage::new()
+                            .content("Wrong answer!")
+                            .ephemeral(true),
+                    ),
+                )
+                .await?;
+        }
+
+        let outcome = match winner {
+            Some(user_id) => {
+                *scores.entry(user_id).or_default() += 1;
+                guessed_albums
+                    .entry(user_id)
+                    .or_default()
+                    .push(round.song.album.clone());
+                format!("{} got it!", user_id.mention())
+            }
+            None => "Nobody got it.".to_string(),
+        };
+
+        let result_embed = serenity::CreateEmbed::new()
+            .title(format!("Round {}/{}", round_index + 1, prepared.len()))
+            .color(if winner.is_some() {
+                Color::DARK_GREEN
+            } else {
+                Color::RED
+            })
+            .description(format!(
+                "**{}** is on **{}**. {}",
+                round.song.title, round.song.album, outcome
+            ));
+
+        message
+            .edit(
+                ctx,
+                serenity::EditMessage::new()
+                    .embed(result_embed)
+                    .components(round_buttons(round, &prefix, true)),
+            )
+            .await?;
+    }
+
+    // Everyone who answered took part, even without scoring
+    for player in &players {
+        scores.entry(*player).or_default();
+    }
+
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get() as i64);
+    let db = ctx.data().db.clone();
+    let awards = points::ranked_awards(&scores, guessed_albums);
+
+    let unlocked = tokio::task::spawn_blocking(move || {
+        let db_lock = db.blocking_lock();
+        points::record_game(&db_lock, guild_id, Game::Trivia, &awards)
+    })
+    .await??;
+
+    let mut ranking: Vec<(serenity::UserId, u32)> = scores
+        .into_iter()
+        .filter(|(_, points)| *points > 0)
+        .collect();
+    ranking.sort_by_key(|(_, points)| std::cmp::Reverse(*points));
+
+    let results = if ranking.is_empty() {
+        "Nobody scored this time.".to_string()
+    } else {
+        ranking
+            .iter()
+            .enumerate()
+            .map(|(i, (user_id, points))| {
+                format!("{}. {} — {} point(s)", i + 1, user_id.mention(), points)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let results_embed = serenity::CreateEmbed::new()
+        .title("Trivia Results")
+        .color(Color::GOLD)
+        .description(results);
+
+    ctx.channel_id()
+        .send_message(ctx, serenity::CreateMessage::new().embed(results_embed))
+        .await?;
+
+    points::announce(ctx, ctx.channel_id(), &unlocked).await?;
+
+    Ok(())
+}
+
+/// Show the players with the most trivia points
+#[poise::command(prefix_command, slash_command)]
+pub async fn leaderboard(ctx: Context<'_>) -> Result<(), Error> {
+    send_leaderboard(ctx, Some(Game::Trivia), Period::AllTime).await
+}
@@ -0,0 +1,368 @@
+use poise::serenity_prelude::{self as serenity, Color};
+use rusqlite::{Connection, OptionalExtension, params};
+
+use crate::{Context, Data, Error, sampling};
+
+/// Prefix of the custom ID of vote buttons, followed by `<song a>:<song b>:<a|b>:<album>`
+const VOTE_PREFIX: &str = "versus-vote:";
+/// Longest custom ID Discord accepts on a button
+const MAX_CUSTOM_ID_LEN: usize = 100;
+/// Rating of songs that haven't been compared yet
+const INITIAL_RATING: f64 = 1500.0;
+/// How far a single vote moves the ratings
+const K_FACTOR: f64 = 32.0;
+/// Songs need this many comparisons before they show up in the rankings
+const MIN_COMPARISONS: u32 = 1;
+/// Longest title shown in `/rankings`, so ten entries fit in one embed field
+const MAX_RANKED_TITLE_CHARS: usize = 50;
+
+type Matchup = (Contender, Contender);
+/// Summary of a counted vote, with the next matchup for the voter
+type VoteOutcome = (String, Option<Matchup>);
+
+struct Contender {
+    id: i32,
+    title: String,
+    album: String,
+    rating: f64,
+}
+
+/// Probability of a song rated `rating` beating one rated `opponent`
+fn expected_score(rating: f64, opponent: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((opponent - rating) / 400.0))
+}
+
+/// Picks two songs, favouring the ones with the fewest comparisons so the ranking converges
+fn draw_matchup(
+    conn: &Connection,
+    album: Option<&str>,
+) -> Result<Option<Matchup>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT s.id, COALESCE(e.comparisons, 0) FROM songs s
+         LEFT JOIN song_elo e ON e.song_id = s.id
+         WHERE ?1 IS NULL OR s.album = ?1",
+    )?;
+    let candidates = stmt
+        .query_map(params![album], |row| {
+            Ok((row.get::<_, i32>(0)?, row.get::<_, u32>(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let weights: Vec<f64> = candidates
+        .iter()
+        .map(|(_, comparisons)| 1.0 / (1.0 + *comparisons as f64))
+        .collect();
+    let picked = sampling::weighted_sample(&weights, 2, &mut rand::thread_rng());
+
+    let [a, b] = picked[..] else {
+        return Ok(None);
+    };
+
+    Ok(Some((
+        load_contender(conn, candidates[a].0)?,
+        load_contender(conn, candidates[b].0)?,
+    )))
+}
+
+fn load_contender(conn: &Connection, song_id: i32) -> Result<Contender, rusqlite::Error> {
+    conn.query_row(
+        "SELECT s.id, s.title, s.album, COALESCE(e.rating, ?2) FROM songs s
+         LEFT JOIN song_elo e ON e.song_id = s.id
+         WHERE s.id = ?1",
+        params![song_id, INITIAL_RATING],
+        |row| {
+            Ok(Contender {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                album: row.get(2)?,
+                rating: row.get(3)?,
+            })
+        },
+    )
+}
+
+fn matchup_embed(a: &Contender, b: &Contender) -> serenity::CreateEmbed {
+    serenity::CreateEmbed::new()
+        .title("Which song is better?")
+        .color(Color::ORANGE)
+        .description(format!(
+            "🅰️ **{}** ({})\n\n🅱️ **{}** ({})",
+            a.title, a.album, b.title, b.album
+        ))
+}
+
+/// Custom ID of a vote button; the album filter goes last so it can contain `:`
+fn vote_custom_id(a: i32, b: i32, side: &str, album: Option<&str>) -> String {
+    format!(
+        "{}{}:{}:{}:{}",
+        VOTE_PREFIX,
+        a,
+        b,
+        side,
+        album.unwrap_or_default()
+    )
+}
+
+/// Vote buttons carry the album filter, so the next matchup sticks to the same album
+fn matchup_buttons(
+    a: &Contender,
+    b: &Contender,
+    album: Option<&str>,
+) -> Vec<serenity::CreateActionRow> {
+    let button = |side: &str, contender: &Contender| {
+        serenity::CreateButton::new(vote_custom_id(a.id, b.id, side, album))
+            .label(contender.title.chars().take(80).collect::<String>())
+            .style(serenity::ButtonStyle::Primary)
+    };
+
+    vec![serenity::CreateActionRow::Buttons(vec![
+        button("a", a),
+        button("b", b),
+    ])]
+}
+
+/// Show two random songs and vote for the better one
+#[poise::command(prefix_command, slash_command)]
+pub async fn versus(
+    ctx: Context<'_>,
+    #[autocomplete = "crate::commands::new::autocomplete_album"]
+    #[description = "Only compare songs from this album"]
+    album: Option<String>,
+) -> Result<(), Error> {
+    // Song IDs can take up to 10 digits each
+    let longest_id = vote_custom_id(i32::MAX, i32::MAX, "a", album.as_deref());
+    if longest_id.len() > MAX_CUSTOM_ID_LEN {
+        let long_album_embed = serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description("That album name is too long to compare songs from.");
+        ctx.send(poise::CreateReply::default().embed(long_album_embed))
+            .await?;
+        return Ok(());
+    }
+
+    let db = ctx.data().db.clone();
+    let album_cloned = album.clone();
+
+    let matchup = tokio::task::spawn_blocking(move || {
+        let db_lock = db.blocking_lock();
+        draw_matchup(&db_lock, album_cloned.as_deref())
+    })
+    .await??;
+
+    let Some((a, b)) = matchup else {
+        let not_enough_embed = serenity::CreateEmbed::new()
+            .title("Error")
+            .color(Color::RED)
+            .description("At least two songs are needed for a matchup.");
+        ctx.send(poise::CreateReply::default().embed(not_enough_embed))
+            .await?;
+        return Ok(());
+    };
+
+    ctx.send(
+        poise::CreateReply::default()
+            .embed(matchup_embed(&a, &b))
+            .components(matchup_buttons(&a, &b, album.as_deref())),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Records a versus vote, updates both ratings and offers the voter a fresh matchup.
+///
+/// Every user gets one vote per matchup message.
+pub async fn handle_vote(
+    ctx: &serenity::Context,
+    interaction: &serenity::ComponentInteraction,
+    data: &Data,
+) -> Result<(), Error> {
+    let parts: Vec<&str> = interaction
+        .data
+        .custom_id
+        .strip_prefix(VOTE_PREFIX)
+        .unwrap_or_default()
+        .splitn(4, ':')
+        .collect();
+    let [song_a, song_b, side, album] = parts[..] else {
+        return Ok(());
+    };
+    let album = Some(album)
+        .filter(|album| !album.is_empty())
+        .map(str::to_string);
+    let (Ok(song_a), Ok(song_b)) = (song_a.parse::<i32>(), song_b.parse::<i32>()) else {
+        return Ok(());
+    };
+    let (winner, loser) = if side == "b" {
+        (song_b, song_a)
+    } else {
+        (song_a, song_b)
+    };
+    let message_id = interaction.message.id.get() as i64;
+    let user_id = interaction.user.id.get() as i64;
+
+    let db = data.db.clone();
+    let album_cloned = album.clone();
+    let outcome = tokio::task::spawn_blocking(
+        move || -> Result<Result<VoteOutcome, String>, rusqlite::Error> {
+            let db_lock = db.blocking_lock();
+
+            let Some(winner) = load_contender(&db_lock, winner).optional()? else {
+                return Ok(Err("One of these songs has been deleted.".to_string()));
+            };
+            let Some(loser) = load_contender(&db_lock, loser).optional()? else {
+                return Ok(Err("One of these songs has been deleted.".to_string()));
+            };
+
+            let tx = db_lock.unchecked_transaction()?;
+
+            let first_vote = tx.execute(
+                "INSERT OR IGNORE INTO versus_votes (message_id, user_id) VALUES (?1, ?2)",
+                params![message_id, user_id],
+            )? > 0;
+            if !first_vote {
+                return Ok(Err("You already voted on this matchup.".to_string()));
+            }
+
+            let change = K_FACTOR * (1.0 - expected_score(winner.rating, loser.rating));
+            let mut stmt = tx.prepare(
+                "INSERT INTO song_elo (song_id, rating, comparisons) VALUES (?1, ?2, 1)
+                 ON CONFLICT(song_id) DO UPDATE SET
+                    rating = excluded.rating,
+                    comparisons = comparisons + 1",
+            )?;
+            stmt.execute(params![winner.id, winner.rating + change])?;
+            stmt.execute(params![loser.id, loser.rating - change])?;
+            drop(stmt);
+            tx.commit()?;
+
+            let summary = format!(
+                "You picked **{}** over **{}** (+{:.0} / -{:.0} rating).",
+                winner.title, loser.title, change, change
+            );
+
+            Ok(Ok((
+                summary,
+                draw_matchup(&db_lock, album_cloned.as_deref())?,
+            )))
+        },
+    )
+    .await??;
+
+    let response = match outcome {
+        Ok((summary, Some((a, b)))) => serenity::CreateInteractionResponseMessage::new()
+            .content(format!("{} Here's another one:", summary))
+            .embed(matchup_embed(&a, &b))
+            .components(matchup_buttons(&a, &b, album.as_deref())),
+        Ok((summary, None)) => serenity::CreateInteractionResponseMessage::new().content(summary),
+        Err(message) => serenity::CreateInteractionResponseMessage::new().content(message),
+    };
+
+    interaction
+        .create_response(
+            ctx,
+            serenity::CreateInteractionResponse::Message(response.ephemeral(true)),
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Show the community's highest and lowest rated songs from /versus votes
+#[poise::command(prefix_command, slash_command)]
+pub async fn rankings(
+    ctx: Context<'_>,
+    #[autocomplete = "crate::commands::new::autocomplete_album"]
+    #[description = "Only rank songs from this album"]
+    album: Option<String>,
+) -> Result<(), Error> {
+    let db = ctx.data().db.clone();
+    let album_cloned = album.clone();
+
+    let ranked = tokio::task::spawn_blocking(
+        move || -> Result<Vec<(String, f64, u32)>, rusqlite::Error> {
+            let db_lock = db.blocking_lock();
+
+            let mut stmt = db_lock.prepare(
+                "SELECT s.title, e.rating, e.comparisons FROM song_elo e
+                 JOIN songs s ON s.id = e.song_id
+                 WHERE e.comparisons >= ?2 AND (?1 IS NULL OR s.album = ?1)
+                 ORDER BY e.rating DESC",
+            )?;
+            let song_iter = stmt.query_map(params![album_cloned, MIN_COMPARISONS], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })?;
+
+            song_iter.collect()
+        },
+    )
+    .await??;
+
+    if ranked.is_empty() {
+        let no_rankings_embed = serenity::CreateEmbed::new()
+            .title("No Rankings")
+            .description("No songs have been compared yet. Vote with `/versus`.");
+
+        ctx.send(poise::CreateReply::default().embed(no_rankings_embed))
+            .await?;
+
+        return Ok(());
+    }
+
+    let format_entry = |(rank, (title, rating, comparisons)): (usize, &(String, f64, u32))| {
+        let title = if title.chars().count() > MAX_RANKED_TITLE_CHARS {
+            format!(
+                "{}…",
+                title
+                    .chars()
+                    .take(MAX_RANKED_TITLE_CHARS)
+                    .collect::<String>()
+            )
+        } else {
+            title.clone()
+        };
+
+        format!(
+            "{}. **{}** — {:.0} ({} comparison(s))",
+            rank + 1,
+            title,
+            rating,
+            comparisons
+        )
+    };
+
+    let top = ranked
+        .iter()
+        .enumerate()
+        .take(10)
+        .map(format_entry)
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    // The bottom list only shows songs that aren't already in the top list
+    let bottom = ranked
+        .iter()
+        .enumerate()
+        .skip(10.max(ranked.len().saturating_sub(10)))
+        .map(format_entry)
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut rankings_embed = serenity::CreateEmbed::new()
+        .title(match &album {
+            Some(album) => format!("Rankings: {}", album),
+            None => "Rankings".to_string(),
+        })
+        .color(Color::ORANGE)
+        .field("Top", top, false);
+
+    if !bottom.is_empty() {
+        rankings_embed = rankings_embed.field("Bottom", bottom, false);
+    }
+
+    ctx.send(poise::CreateReply::default().embed(rankings_embed))
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,64 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use poise::serenity_prelude as serenity;
+
+/// Channels with a game in progress, so only one game runs per channel at a time
+#[derive(Default)]
+pub struct ActiveGames {
+    channels: Mutex<HashMap<serenity::ChannelId, &'static str>>,
+}
+
+/// Marks a channel as busy until dropped
+pub struct GameGuard<'a> {
+    games: &'a ActiveGames,
+    channel_id: serenity::ChannelId,
+}
+
+impl ActiveGames {
+    /// Claims `channel_id` for `game`, or returns the name of the game already running there
+    pub fn start(
+        &self,
+        channel_id: serenity::ChannelId,
+        game: &'static str,
+    ) -> Result<GameGuard<'_>, &'static str> {
+        let mut channels = self.channels.lock().unwrap();
+
+        if let Some(running) = channels.get(&channel_id) {
+            return Err(running);
+        }
+
+        channels.insert(channel_id, game);
+
+        Ok(GameGuard {
+            games: self,
+            channel_id,
+        })
+    }
+}
+
+impl Drop for GameGuard<'_> {
+    fn drop(&mut self) {
+        self.games.channels.lock().unwrap().remove(&self.channel_id);
+    }
+}
+
+/// Lowercases `text` and keeps only letters and digits, with single spaces between words
+pub fn normalize(text: &str) -> String {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Whether a typed guess is close enough to the answer to count, allowing for small typos
+pub fn is_close_match(guess: &str, answer: &str) -> bool {
+    let guess = normalize(guess);
+    let answer = normalize(answer);
+
+    if guess.is_empty() || answer.is_empty() {
+        return false;
+    }
+
+    guess == answer || strsim::normalized_levenshtein(&guess, &answer) >= 0.85
+}
@@ -6,12 +6,21 @@ use dotenv::dotenv;
 use poise::serenity_prelude::{self as serenity, GuildId};
 use rusqlite::Connection;
 
+mod autocomplete;
 mod checks;
 mod commands;
+mod games;
+mod points;
+mod positions;
+mod review;
+mod sampling;
 
 struct Data {
     db: Arc<Mutex<Connection>>, // Wrap Connection in Mutex for thread safety
     mod_role_id: serenity::RoleId, // ID of the moderator role
+    song_ids: Arc<sampling::SongIdCache>, // Song IDs used for random draws, cleared on writes
+    active_games: games::ActiveGames,     // Channels with a game in progress
+    drafts: commands::draft::Drafts,      // Drafts waiting for `/draft pick`
 }
 
 type Error = Box<dyn std::error::Error + Send + Sync>;
@@ -31,6 +40,37 @@ async fn on_error(error: poise::FrameworkError<'_, Data, Error>) {
     }
 }
 
+async fn event_handler(
+    ctx: &serenity::Context,
+    event: &serenity::FullEvent,
+    _framework: poise::FrameworkContext<'_, Data, Error>,
+    data: &Data,
+) -> Result<(), Error> {
+    // Buttons that have to keep working across restarts are routed by their custom ID
+    if let serenity::FullEvent::InteractionCreate {
+        interaction: serenity::Interaction::Component(interaction),
+    } = event
+    {
+        let custom_id = interaction.data.custom_id.as_str();
+
+        if custom_id.starts_with("bracket-vote:") {
+            commands::bracket::handle_vote(ctx, interaction, data).await?;
+        } else if custom_id.starts_with("poll-vote:") {
+            commands::poll::handle_vote(ctx, interaction, data).await?;
+        } else if custom_id.starts_with("versus-vote:") {
+            commands::versus::handle_vote(ctx, interaction, data).await?;
+        } else if custom_id.starts_with("rate:") {
+            commands::rate::handle_rating(ctx, interaction, data).await?;
+        } else if custom_id.starts_with("suggestion-review:") {
+            commands::suggest::handle_review(ctx, interaction, data).await?;
+        } else if custom_id.starts_with("edit-review:") {
+            commands::propose::handle_review(ctx, interaction, data).await?;
+        }
+    }
+
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() {
     dotenv().ok();
@@ -43,12 +83,48 @@ async fn main() {
             "CREATE TABLE IF NOT EXISTS songs (
                 id INTEGER PRIMARY KEY AUTOINCREMENT,
                 title TEXT,
-                album TEXT 
+                album TEXT,
+                duration INTEGER
             )",
             [],
         )
         .unwrap();
 
+    // Databases created before songs had a duration need the column added
+    let has_duration: bool = connection
+        .query_row(
+            "SELECT COUNT(*) FROM pragma_table_info('songs') WHERE name = 'duration'",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap();
+    if !has_duration {
+        connection
+            .execute("ALTER TABLE songs ADD COLUMN duration INTEGER", [])
+            .unwrap();
+    }
+
+    // Same for the moderator who added a song, which is unknown for older songs
+    let has_added_by: bool = connection
+        .query_row(
+            "SELECT COUNT(*) FROM pragma_table_info('songs') WHERE name = 'added_by'",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap();
+    if !has_added_by {
+        connection
+            .execute("ALTER TABLE songs ADD COLUMN added_by INTEGER", [])
+            .unwrap();
+    }
+
+    connection
+        .execute(
+            "CREATE INDEX IF NOT EXISTS songs_album ON songs (album)",
+            [],
+        )
+        .unwrap();
+
     connection
         .execute(
             "CREATE TABLE IF NOT EXISTS albums (
@@ -59,11 +135,532 @@ async fn main() {
         )
         .unwrap();
 
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS random_history (
+              id INTEGER PRIMARY KEY AUTOINCREMENT,
+              guild_id INTEGER NOT NULL,
+              channel_id INTEGER NOT NULL,
+              song_id INTEGER NOT NULL,
+              picked_at INTEGER NOT NULL
+            )",
+            [],
+        )
+        .unwrap();
+
+    connection
+        .execute(
+            "CREATE INDEX IF NOT EXISTS random_history_guild
+              ON random_history (guild_id, channel_id, song_id)",
+            [],
+        )
+        .unwrap();
+
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS random_settings (
+              guild_id INTEGER PRIMARY KEY,
+              per_channel INTEGER NOT NULL DEFAULT 0,
+              repeat_picks INTEGER NOT NULL DEFAULT 10,
+              repeat_days INTEGER NOT NULL DEFAULT 0
+            )",
+            [],
+        )
+        .unwrap();
+
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS favorites (
+              user_id INTEGER NOT NULL,
+              song_id INTEGER NOT NULL,
+              created_at INTEGER NOT NULL,
+              PRIMARY KEY (user_id, song_id)
+            )",
+            [],
+        )
+        .unwrap();
+
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS ratings (
+              user_id INTEGER NOT NULL,
+              song_id INTEGER NOT NULL,
+              score INTEGER NOT NULL,
+              rated_at INTEGER NOT NULL,
+              PRIMARY KEY (user_id, song_id)
+            )",
+            [],
+        )
+        .unwrap();
+
+    // Options of every seeded draw, so a shown seed can recompute the same picks
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS random_draws (
+              guild_id INTEGER NOT NULL,
+              seed TEXT NOT NULL,
+              mode TEXT NOT NULL,
+              amount INTEGER NOT NULL,
+              album TEXT NOT NULL,
+              tag TEXT NOT NULL,
+              user_id INTEGER NOT NULL,
+              channel_filter INTEGER NOT NULL,
+              repeat_picks INTEGER NOT NULL,
+              repeat_days INTEGER NOT NULL,
+              history_id INTEGER NOT NULL,
+              fingerprint TEXT NOT NULL,
+              drawn_at INTEGER NOT NULL,
+              PRIMARY KEY (guild_id, seed)
+            )",
+            [],
+        )
+        .unwrap();
+
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS song_tags (
+              song_id INTEGER NOT NULL,
+              tag TEXT NOT NULL,
+              PRIMARY KEY (song_id, tag)
+            )",
+            [],
+        )
+        .unwrap();
+
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS sotd_config (
+              guild_id INTEGER PRIMARY KEY,
+              channel_id INTEGER NOT NULL,
+              post_time TEXT NOT NULL,
+              utc_offset INTEGER NOT NULL DEFAULT 0,
+              album TEXT,
+              tag TEXT,
+              enabled INTEGER NOT NULL DEFAULT 1,
+              last_posted TEXT
+            )",
+            [],
+        )
+        .unwrap();
+
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS sotd_history (
+              id INTEGER PRIMARY KEY AUTOINCREMENT,
+              guild_id INTEGER NOT NULL,
+              song_id INTEGER NOT NULL,
+              posted_on TEXT NOT NULL,
+              posted_at INTEGER NOT NULL
+            )",
+            [],
+        )
+        .unwrap();
+
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS lyrics (
+              song_id INTEGER PRIMARY KEY,
+              body TEXT NOT NULL
+            )",
+            [],
+        )
+        .unwrap();
+
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS brackets (
+              id INTEGER PRIMARY KEY AUTOINCREMENT,
+              guild_id INTEGER NOT NULL,
+              channel_id INTEGER NOT NULL,
+              name TEXT NOT NULL,
+              size INTEGER NOT NULL,
+              round INTEGER NOT NULL DEFAULT 1,
+              round_minutes INTEGER NOT NULL,
+              round_ends_at INTEGER NOT NULL,
+              status TEXT NOT NULL DEFAULT 'active',
+              winner_song_id INTEGER
+            )",
+            [],
+        )
+        .unwrap();
+
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS bracket_matches (
+              id INTEGER PRIMARY KEY AUTOINCREMENT,
+              bracket_id INTEGER NOT NULL,
+              round INTEGER NOT NULL,
+              position INTEGER NOT NULL,
+              song_a INTEGER NOT NULL,
+              song_b INTEGER NOT NULL,
+              seed_a INTEGER NOT NULL,
+              seed_b INTEGER NOT NULL,
+              message_id INTEGER,
+              winner_song_id INTEGER
+            )",
+            [],
+        )
+        .unwrap();
+
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS bracket_votes (
+              match_id INTEGER NOT NULL,
+              user_id INTEGER NOT NULL,
+              song_id INTEGER NOT NULL,
+              PRIMARY KEY (match_id, user_id)
+            )",
+            [],
+        )
+        .unwrap();
+
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS polls (
+              id INTEGER PRIMARY KEY AUTOINCREMENT,
+              guild_id INTEGER NOT NULL,
+              channel_id INTEGER NOT NULL,
+              message_id INTEGER,
+              creator_id INTEGER NOT NULL,
+              question TEXT NOT NULL,
+              ends_at INTEGER NOT NULL,
+              closed INTEGER NOT NULL DEFAULT 0,
+              winner_song_id INTEGER
+            )",
+            [],
+        )
+        .unwrap();
+
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS poll_options (
+              poll_id INTEGER NOT NULL,
+              position INTEGER NOT NULL,
+              song_id INTEGER NOT NULL,
+              title TEXT NOT NULL,
+              PRIMARY KEY (poll_id, position)
+            )",
+            [],
+        )
+        .unwrap();
+
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS poll_votes (
+              poll_id INTEGER NOT NULL,
+              user_id INTEGER NOT NULL,
+              position INTEGER NOT NULL,
+              PRIMARY KEY (poll_id, user_id)
+            )",
+            [],
+        )
+        .unwrap();
+
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS song_elo (
+              song_id INTEGER PRIMARY KEY,
+              rating REAL NOT NULL,
+              comparisons INTEGER NOT NULL DEFAULT 0
+            )",
+            [],
+        )
+        .unwrap();
+
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS versus_votes (
+              message_id INTEGER NOT NULL,
+              user_id INTEGER NOT NULL,
+              PRIMARY KEY (message_id, user_id)
+            )",
+            [],
+        )
+        .unwrap();
+
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS setlists (
+              id INTEGER PRIMARY KEY AUTOINCREMENT,
+              guild_id INTEGER NOT NULL,
+              name TEXT NOT NULL,
+              seed TEXT NOT NULL,
+              created_by INTEGER NOT NULL,
+              created_at INTEGER NOT NULL
+            )",
+            [],
+        )
+        .unwrap();
+
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS setlist_songs (
+              setlist_id INTEGER NOT NULL,
+              position INTEGER NOT NULL,
+              song_id INTEGER NOT NULL,
+              PRIMARY KEY (setlist_id, position)
+            )",
+            [],
+        )
+        .unwrap();
+
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS tier_entries (
+              user_id INTEGER NOT NULL,
+              song_id INTEGER NOT NULL,
+              tier TEXT NOT NULL,
+              PRIMARY KEY (user_id, song_id)
+            )",
+            [],
+        )
+        .unwrap();
+
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS songle_puzzles (
+              day TEXT PRIMARY KEY,
+              song_id INTEGER NOT NULL,
+              answer TEXT NOT NULL
+            )",
+            [],
+        )
+        .unwrap();
+
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS songle_guesses (
+              day TEXT NOT NULL,
+              user_id INTEGER NOT NULL,
+              guess_number INTEGER NOT NULL,
+              guess TEXT NOT NULL,
+              PRIMARY KEY (day, user_id, guess_number)
+            )",
+            [],
+        )
+        .unwrap();
+
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS songle_results (
+              day TEXT NOT NULL,
+              user_id INTEGER NOT NULL,
+              solved INTEGER NOT NULL,
+              guesses INTEGER NOT NULL,
+              PRIMARY KEY (day, user_id)
+            )",
+            [],
+        )
+        .unwrap();
+
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS songle_streaks (
+              user_id INTEGER PRIMARY KEY,
+              current_streak INTEGER NOT NULL DEFAULT 0,
+              max_streak INTEGER NOT NULL DEFAULT 0,
+              last_win_day TEXT
+            )",
+            [],
+        )
+        .unwrap();
+
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS emoji_clues (
+              song_id INTEGER PRIMARY KEY,
+              clue TEXT NOT NULL
+            )",
+            [],
+        )
+        .unwrap();
+
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS points_ledger (
+              id INTEGER PRIMARY KEY AUTOINCREMENT,
+              guild_id INTEGER NOT NULL,
+              user_id INTEGER NOT NULL,
+              game TEXT NOT NULL,
+              points INTEGER NOT NULL,
+              won INTEGER NOT NULL,
+              earned_at INTEGER NOT NULL,
+              games INTEGER NOT NULL DEFAULT 1
+            )",
+            [],
+        )
+        .unwrap();
+
+    connection
+        .execute(
+            "CREATE INDEX IF NOT EXISTS points_ledger_user ON points_ledger (guild_id, user_id)",
+            [],
+        )
+        .unwrap();
+
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS achievements (
+              guild_id INTEGER NOT NULL,
+              user_id INTEGER NOT NULL,
+              achievement TEXT NOT NULL,
+              unlocked_at INTEGER NOT NULL,
+              PRIMARY KEY (guild_id, user_id, achievement)
+            )",
+            [],
+        )
+        .unwrap();
+
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS guessed_albums (
+              guild_id INTEGER NOT NULL,
+              user_id INTEGER NOT NULL,
+              album TEXT NOT NULL,
+              PRIMARY KEY (guild_id, user_id, album)
+            )",
+            [],
+        )
+        .unwrap();
+
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS lyric_streaks (
+              guild_id INTEGER NOT NULL,
+              user_id INTEGER NOT NULL,
+              best_streak INTEGER NOT NULL DEFAULT 0,
+              PRIMARY KEY (guild_id, user_id)
+            )",
+            [],
+        )
+        .unwrap();
+
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS playlists (
+              id INTEGER PRIMARY KEY AUTOINCREMENT,
+              guild_id INTEGER NOT NULL,
+              owner_id INTEGER NOT NULL,
+              name TEXT NOT NULL,
+              visibility TEXT NOT NULL DEFAULT 'private',
+              created_at INTEGER NOT NULL,
+              UNIQUE (guild_id, owner_id, name)
+            )",
+            [],
+        )
+        .unwrap();
+
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS playlist_songs (
+              id INTEGER PRIMARY KEY AUTOINCREMENT,
+              playlist_id INTEGER NOT NULL,
+              position INTEGER NOT NULL,
+              song_id INTEGER NOT NULL,
+              added_by INTEGER NOT NULL
+            )",
+            [],
+        )
+        .unwrap();
+
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS song_suggestions (
+              id INTEGER PRIMARY KEY AUTOINCREMENT,
+              guild_id INTEGER NOT NULL,
+              user_id INTEGER NOT NULL,
+              channel_id INTEGER NOT NULL,
+              title TEXT NOT NULL,
+              album TEXT NOT NULL,
+              status TEXT NOT NULL DEFAULT 'pending',
+              created_at INTEGER NOT NULL,
+              reviewed_by INTEGER,
+              reviewed_at INTEGER,
+              song_id INTEGER
+            )",
+            [],
+        )
+        .unwrap();
+
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS edit_proposals (
+              id INTEGER PRIMARY KEY AUTOINCREMENT,
+              guild_id INTEGER NOT NULL,
+              song_id INTEGER NOT NULL,
+              user_id INTEGER NOT NULL,
+              channel_id INTEGER NOT NULL,
+              old_title TEXT NOT NULL,
+              old_album TEXT NOT NULL,
+              new_title TEXT,
+              new_album TEXT,
+              reason TEXT,
+              status TEXT NOT NULL DEFAULT 'pending',
+              created_at INTEGER NOT NULL,
+              reviewed_by INTEGER,
+              reviewed_at INTEGER
+            )",
+            [],
+        )
+        .unwrap();
+
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS request_queue (
+              id INTEGER PRIMARY KEY AUTOINCREMENT,
+              guild_id INTEGER NOT NULL,
+              song_id INTEGER NOT NULL,
+              user_id INTEGER NOT NULL,
+              position INTEGER NOT NULL,
+              requested_at INTEGER NOT NULL
+            )",
+            [],
+        )
+        .unwrap();
+
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS request_state (
+              guild_id INTEGER PRIMARY KEY,
+              song_id INTEGER,
+              user_id INTEGER,
+              channel_id INTEGER,
+              message_id INTEGER
+            )",
+            [],
+        )
+        .unwrap();
+
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS listens (
+              id INTEGER PRIMARY KEY AUTOINCREMENT,
+              user_id INTEGER NOT NULL,
+              song_id INTEGER NOT NULL,
+              listened_at INTEGER NOT NULL
+            )",
+            [],
+        )
+        .unwrap();
+
+    connection
+        .execute(
+            "CREATE INDEX IF NOT EXISTS listens_user ON listens (user_id, song_id)",
+            [],
+        )
+        .unwrap();
+
+    // Move scores from the old per-game tables into the shared ledger
+    points::migrate_legacy_scores(&connection).unwrap();
+
     // Wrap the connection in Arc<Mutex<Connection>> for thread safety
     let shared_db = Arc::new(Mutex::new(connection));
+    let song_ids = Arc::new(sampling::SongIdCache::default());
 
     let token = std::env::var("DISCORD_TOKEN").expect("missing DISCORD_TOKEN");
-    let intents = serenity::GatewayIntents::non_privileged();
+    // Message content is needed for games that are answered by typing in chat
+    let intents =
+        serenity::GatewayIntents::non_privileged() | serenity::GatewayIntents::MESSAGE_CONTENT;
 
     let guild_id_num = std::env::var("GUILD_ID").expect("`GUILD_ID` env variable is not set!");
     let guild_id = guild_id_num
@@ -86,6 +683,37 @@ async fn main() {
                 commands::random::random(),
                 commands::delete::delete(),
                 commands::album::album(),
+                commands::tag::tag(),
+                commands::sotd::sotd(),
+                commands::trivia::trivia(),
+                commands::lyrics::lyrics(),
+                commands::lyricgame::lyricgame(),
+                commands::bracket::bracket(),
+                commands::poll::poll(),
+                commands::versus::versus(),
+                commands::versus::rankings(),
+                commands::setlist::setlist(),
+                commands::draft::draft(),
+                commands::tierlist::tierlist(),
+                commands::songle::songle(),
+                commands::emoji::emoji(),
+                commands::emojiguess::emojiguess(),
+                commands::leaderboard::leaderboard(),
+                commands::leaderboard::achievements(),
+                commands::fav::fav(),
+                commands::rate::rate(),
+                commands::rate::toprated(),
+                commands::song::song(),
+                commands::playlist::playlist(),
+                commands::suggest::suggest(),
+                commands::suggest::suggestions(),
+                commands::propose::propose_edit(),
+                commands::propose::edit_proposals(),
+                commands::queue::request(),
+                commands::queue::queue(),
+                commands::profile::profile(),
+                commands::listened::listened(),
+                commands::compare::compare(),
             ],
             prefix_options: poise::PrefixFrameworkOptions {
                 prefix: Some("~".into()),
@@ -95,6 +723,9 @@ async fn main() {
                 ..Default::default()
             },
             on_error: |error| Box::pin(on_error(error)), // on_error must be Send
+            event_handler: |ctx, event, framework, data| {
+                Box::pin(event_handler(ctx, event, framework, data))
+            },
             pre_command: |ctx| {
                 Box::pin(async move {
                     println!("Executing command {}...", ctx.command().qualified_name);
@@ -112,9 +743,32 @@ async fn main() {
                 println!("Logged in as {}", ready.user.name);
                 poise::builtins::register_in_guild(ctx, &framework.options().commands, guild_id)
                     .await?;
+
+                // Post the song of the day in the background
+                tokio::spawn(commands::sotd::scheduler(
+                    ctx.http.clone(),
+                    shared_db.clone(),
+                    song_ids.clone(),
+                ));
+
+                // Close bracket rounds once their voting time is up
+                tokio::spawn(commands::bracket::scheduler(
+                    ctx.http.clone(),
+                    shared_db.clone(),
+                ));
+
+                // Close polls once their deadline has passed
+                tokio::spawn(commands::poll::scheduler(
+                    ctx.http.clone(),
+                    shared_db.clone(),
+                ));
+
                 Ok(Data {
                     db: shared_db.clone(),
                     mod_role_id,
+                    song_ids: song_ids.clone(),
+                    active_games: games::ActiveGames::default(),
+                    drafts: commands::draft::Drafts::default(),
                 })
             })
         })
@@ -0,0 +1,334 @@
+use std::collections::HashMap;
+
+use poise::serenity_prelude::{self as serenity, Color, Mentionable};
+use rusqlite::{Connection, params};
+
+/// Games that write to the shared points ledger
+#[derive(Debug, poise::ChoiceParameter, Clone, Copy, PartialEq)]
+pub enum Game {
+    #[name = "Trivia"]
+    Trivia,
+    #[name = "Finish the Lyric"]
+    Lyrics,
+    #[name = "Emoji Guess"]
+    Emoji,
+    #[name = "Songle"]
+    Songle,
+    #[name = "Draft"]
+    Draft,
+}
+
+impl Game {
+    /// Name stored in the ledger
+    pub fn key(self) -> &'static str {
+        match self {
+            Game::Trivia => "trivia",
+            Game::Lyrics => "lyricgame",
+            Game::Emoji => "emojiguess",
+            Game::Songle => "songle",
+            Game::Draft => "draft",
+        }
+    }
+}
+
+#[derive(Debug, poise::ChoiceParameter, Clone, Copy, PartialEq)]
+pub enum Period {
+    #[name = "This week"]
+    Weekly,
+    #[name = "This month"]
+    Monthly,
+    #[name = "All time"]
+    AllTime,
+}
+
+impl Period {
+    /// Length of the window in seconds, or `None` for all time
+    pub fn seconds(self) -> Option<i64> {
+        match self {
+            Period::Weekly => Some(7 * 24 * 60 * 60),
+            Period::Monthly => Some(30 * 24 * 60 * 60),
+            Period::AllTime => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Achievement {
+    FirstWin,
+    WinStreak,
+    EveryAlbum,
+}
+
+/// Wins in a row needed for [`Achievement::WinStreak`]
+const WIN_STREAK: u32 = 10;
+
+impl Achievement {
+    pub const ALL: [Achievement; 3] = [
+        Achievement::FirstWin,
+        Achievement::WinStreak,
+        Achievement::EveryAlbum,
+    ];
+
+    fn key(self) -> &'static str {
+        match self {
+            Achievement::FirstWin => "first_win",
+            Achievement::WinStreak => "win_streak",
+            Achievement::EveryAlbum => "every_album",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Achievement::FirstWin => "First Win",
+            Achievement::WinStreak => "On Fire",
+            Achievement::EveryAlbum => "Completionist",
+        }
+    }
+
+    pub fn description(self) -> String {
+        match self {
+            Achievement::FirstWin => "Win any game".to_string(),
+            Achievement::WinStreak => format!("Win {} games in a row", WIN_STREAK),
+            Achievement::EveryAlbum => "Correctly guess a song from every album".to_string(),
+        }
+    }
+
+    fn from_key(key: &str) -> Option<Achievement> {
+        Achievement::ALL
+            .into_iter()
+            .find(|achievement| achievement.key() == key)
+    }
+}
+
+/// One player's result in a finished game
+pub struct Award {
+    pub user_id: serenity::UserId,
+    pub points: u32,
+    pub won: bool,
+    /// Albums of the songs the player guessed correctly
+    pub albums: Vec<String>,
+}
+
+/// Awards for a game where the highest score wins; everyone tied at the top wins.
+///
+/// Players who took part without scoring should be in `scores` with 0 points.
+pub fn ranked_awards(
+    scores: &HashMap<serenity::UserId, u32>,
+    mut albums: HashMap<serenity::UserId, Vec<String>>,
+) -> Vec<Award> {
+    let top_score = scores.values().copied().max().unwrap_or_default();
+
+    scores
+        .iter()
+        .map(|(user_id, points)| Award {
+            user_id: *user_id,
+            points: *points,
+            won: top_score > 0 && *points == top_score,
+            albums: albums.remove(user_id).unwrap_or_default(),
+        })
+        .collect()
+}
+
+/// Writes the results of a game to the ledger and returns the achievements it unlocked
+pub fn record_game(
+    conn: &Connection,
+    guild_id: i64,
+    game: Game,
+    awards: &[Award],
+) -> Result<Vec<(serenity::UserId, Achievement)>, rusqlite::Error> {
+    let tx = conn.unchecked_transaction()?;
+    let mut unlocked = Vec::new();
+
+    for award in awards {
+        let user_id = award.user_id.get() as i64;
+
+        tx.execute(
+            "INSERT INTO points_ledger (guild_id, user_id, game, points, won, earned_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, unixepoch())",
+            params![guild_id, user_id, game.key(), award.points, award.won],
+        )?;
+
+        for album in &award.albums {
+            tx.execute(
+                "INSERT OR IGNORE INTO guessed_albums (guild_id, user_id, album) VALUES (?1, ?2, ?3)",
+                params![guild_id, user_id, album],
+            )?;
+        }
+
+        let recent_wins: Vec<bool> = tx
+            .prepare(
+                "SELECT won FROM points_ledger
+                 WHERE guild_id = ?1 AND user_id = ?2
+                 ORDER BY id DESC LIMIT ?3",
+            )?
+            .query_map(params![guild_id, user_id, WIN_STREAK], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+
+        let (guessed_albums, total_albums): (u32, u32) = tx.query_row(
+            "SELECT
+                (SELECT COUNT(*) FROM guessed_albums g
+                 WHERE g.guild_id = ?1 AND g.user_id = ?2
+                    AND g.album IN (SELECT DISTINCT album FROM songs)),
+                (SELECT COUNT(DISTINCT album) FROM songs)",
+            params![guild_id, user_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+
+        let earned = [
+            (Achievement::FirstWin, award.won),
+            (
+                Achievement::WinStreak,
+                recent_wins.len() == WIN_STREAK as usize && recent_wins.iter().all(|won| *won),
+            ),
+            (
+                Achievement::EveryAlbum,
+                total_albums > 0 && guessed_albums >= total_albums,
+            ),
+        ];
+
+        for (achievement, earned) in earned {
+            if !earned {
+                continue;
+            }
+
+            let newly_unlocked = tx.execute(
+                "INSERT OR IGNORE INTO achievements (guild_id, user_id, achievement, unlocked_at)
+                 VALUES (?1, ?2, ?3, unixepoch())",
+                params![guild_id, user_id, achievement.key()],
+            )? > 0;
+            if newly_unlocked {
+                unlocked.push((award.user_id, achievement));
+            }
+        }
+    }
+
+    tx.commit()?;
+
+    Ok(unlocked)
+}
+
+/// Players with the most points as `(user ID, points, games played)`, best first
+pub fn top_players(
+    conn: &Connection,
+    guild_id: i64,
+    game: Option<Game>,
+    period: Period,
+) -> Result<Vec<(u64, u32, u32)>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT user_id, SUM(points) AS total, SUM(games) FROM points_ledger
+         WHERE guild_id = ?1
+            AND (?2 IS NULL OR game = ?2)
+            AND (?3 IS NULL OR earned_at >= unixepoch() - ?3)
+         GROUP BY user_id
+         HAVING total > 0
+         ORDER BY total DESC, SUM(games) ASC
+         LIMIT 10",
+    )?;
+
+    let entry_iter = stmt.query_map(
+        params![guild_id, game.map(Game::key), period.seconds()],
+        |row| Ok((row.get::<_, i64>(0)? as u64, row.get(1)?, row.get(2)?)),
+    )?;
+
+    entry_iter.collect()
+}
+
+/// Achievements a player has unlocked, with their unlock time
+pub fn unlocked_achievements(
+    conn: &Connection,
+    guild_id: i64,
+    user_id: i64,
+) -> Result<Vec<(Achievement, i64)>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT achievement, unlocked_at FROM achievements
+         WHERE guild_id = ?1 AND user_id = ?2
+         ORDER BY unlocked_at",
+    )?;
+    let achievement_iter = stmt.query_map(params![guild_id, user_id], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+    })?;
+
+    let mut unlocked = Vec::new();
+    for achievement in achievement_iter {
+        let (key, unlocked_at) = achievement?;
+        if let Some(achievement) = Achievement::from_key(&key) {
+            unlocked.push((achievement, unlocked_at));
+        }
+    }
+
+    Ok(unlocked)
+}
+
+/// Posts a message for every freshly unlocked achievement
+pub async fn announce(
+    http: impl serenity::CacheHttp,
+    channel_id: serenity::ChannelId,
+    unlocked: &[(serenity::UserId, Achievement)],
+) -> Result<(), serenity::Error> {
+    for (user_id, achievement) in unlocked {
+        let achievement_embed = serenity::CreateEmbed::new()
+            .title("Achievement Unlocked")
+            .color(Color::GOLD)
+            .description(format!(
+                "🏆 {} unlocked **{}**: {}",
+                user_id.mention(),
+                achievement.name(),
+                achievement.description()
+            ));
+
+        channel_id
+            .send_message(
+                &http,
+                serenity::CreateMessage::new().embed(achievement_embed),
+            )
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Moves scores from the per-game tables used before the shared ledger into it.
+///
+/// Each player gets one ledger row holding all their old points and games. Moved points count
+/// towards all-time standings only, since it's unknown when they were earned. Old tables are
+/// renamed to `<table>_legacy` rather than dropped, so they can be checked and removed by hand.
+pub fn migrate_legacy_scores(conn: &Connection) -> Result<(), rusqlite::Error> {
+    let tx = conn.unchecked_transaction()?;
+
+    // Only trivia counted games; a row in the other tables means at least one was played
+    for (table, game, games) in [
+        ("trivia_scores", Game::Trivia, "games_played"),
+        ("lyric_scores", Game::Lyrics, "1"),
+        ("emoji_scores", Game::Emoji, "1"),
+    ] {
+        let exists: bool = tx.query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?1",
+            params![table],
+            |row| row.get(0),
+        )?;
+        if !exists {
+            continue;
+        }
+
+        tx.execute(
+            &format!(
+                "INSERT INTO points_ledger (guild_id, user_id, game, points, won, earned_at, games)
+                 SELECT guild_id, user_id, ?1, points, 0, 0, {games} FROM {table}
+                 WHERE points > 0 OR {games} > 0",
+            ),
+            params![game.key()],
+        )?;
+
+        if game == Game::Lyrics {
+            tx.execute(
+                "INSERT OR IGNORE INTO lyric_streaks (guild_id, user_id, best_streak)
+                 SELECT guild_id, user_id, best_streak FROM lyric_scores",
+                [],
+            )?;
+        }
+
+        tx.execute(&format!("ALTER TABLE {table} RENAME TO {table}_legacy"), [])?;
+    }
+
+    tx.commit()
+}
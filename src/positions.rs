@@ -0,0 +1,50 @@
+use rusqlite::{Connection, params};
+
+/// Moves the row at position `from` to position `to` among the rows of `table` whose
+/// `scope_column` is `scope_id`, shifting the rows in between.
+///
+/// Both positions must exist; run it inside a transaction so the positions stay unique.
+pub fn move_position(
+    conn: &Connection,
+    table: &str,
+    scope_column: &str,
+    scope_id: i64,
+    from: u32,
+    to: u32,
+) -> Result<(), rusqlite::Error> {
+    let row_id: i64 = conn.query_row(
+        &format!(
+            "SELECT id FROM {} WHERE {} = ?1 AND position = ?2",
+            table, scope_column
+        ),
+        params![scope_id, from],
+        |row| row.get(0),
+    )?;
+
+    // Close the gap left behind and open one at the destination
+    if from < to {
+        conn.execute(
+            &format!(
+                "UPDATE {} SET position = position - 1
+                 WHERE {} = ?1 AND position > ?2 AND position <= ?3",
+                table, scope_column
+            ),
+            params![scope_id, from, to],
+        )?;
+    } else {
+        conn.execute(
+            &format!(
+                "UPDATE {} SET position = position + 1
+                 WHERE {} = ?1 AND position >= ?3 AND position < ?2",
+                table, scope_column
+            ),
+            params![scope_id, from, to],
+        )?;
+    }
+    conn.execute(
+        &format!("UPDATE {} SET position = ?2 WHERE id = ?1", table),
+        params![row_id, to],
+    )?;
+
+    Ok(())
+}
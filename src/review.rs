@@ -0,0 +1,98 @@
+use poise::serenity_prelude::{self as serenity, Mentionable};
+use rusqlite::{Connection, OptionalExtension, Row, params};
+
+/// Reviewed item with its outcome, the next pending item and the number pending
+pub type ReviewOutcome<T, R> = (Option<(T, R)>, Option<T>, u32);
+
+/// Oldest pending item of a review queue table and the number of pending items.
+///
+/// `columns` are selected in the order `from_row` reads them.
+pub fn next_pending<T>(
+    conn: &Connection,
+    table: &str,
+    columns: &str,
+    guild_id: i64,
+    from_row: fn(&Row) -> Result<T, rusqlite::Error>,
+) -> Result<(Option<T>, u32), rusqlite::Error> {
+    let item = conn
+        .query_row(
+            &format!(
+                "SELECT {} FROM {} WHERE guild_id = ?1 AND status = 'pending' ORDER BY id LIMIT 1",
+                columns, table
+            ),
+            params![guild_id],
+            from_row,
+        )
+        .optional()?;
+
+    let pending = conn.query_row(
+        &format!(
+            "SELECT COUNT(*) FROM {} WHERE guild_id = ?1 AND status = 'pending'",
+            table
+        ),
+        params![guild_id],
+        |row| row.get(0),
+    )?;
+
+    Ok((item, pending))
+}
+
+/// Accept and Reject buttons for the item with `id`, or none once the queue is empty.
+///
+/// The custom IDs are `<prefix><id>:<action>`, where the accept action is `accept_label`
+/// in lowercase.
+pub fn review_buttons(
+    prefix: &str,
+    id: Option<i64>,
+    accept_label: &str,
+) -> Vec<serenity::CreateActionRow> {
+    let Some(id) = id else {
+        return Vec::new();
+    };
+
+    vec![serenity::CreateActionRow::Buttons(vec![
+        serenity::CreateButton::new(format!("{}{}:{}", prefix, id, accept_label.to_lowercase()))
+            .label(accept_label)
+            .style(serenity::ButtonStyle::Success),
+        serenity::CreateButton::new(format!("{}{}:reject", prefix, id))
+            .label("Reject")
+            .style(serenity::ButtonStyle::Danger),
+    ])]
+}
+
+/// Reads the item ID and action from a review button's custom ID
+pub fn parse_review_button<'a>(custom_id: &'a str, prefix: &str) -> Option<(i64, &'a str)> {
+    let (id, action) = custom_id.strip_prefix(prefix)?.split_once(':')?;
+
+    Some((id.parse().ok()?, action))
+}
+
+/// Tells a member how their submission was reviewed, by DM or in the channel they submitted from
+pub async fn notify_member(
+    ctx: &serenity::Context,
+    user_id: u64,
+    channel_id: u64,
+    outcome_embed: serenity::CreateEmbed,
+) -> Result<(), serenity::Error> {
+    let user_id = serenity::UserId::new(user_id);
+    let dm = user_id
+        .direct_message(
+            ctx,
+            serenity::CreateMessage::new().embed(outcome_embed.clone()),
+        )
+        .await;
+
+    // Members can have DMs from server members turned off
+    if dm.is_err() {
+        serenity::ChannelId::new(channel_id)
+            .send_message(
+                ctx,
+                serenity::CreateMessage::new()
+                    .content(user_id.mention().to_string())
+                    .embed(outcome_embed),
+            )
+            .await?;
+    }
+
+    Ok(())
+}
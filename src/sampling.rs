@@ -0,0 +1,229 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+};
+
+use rand::{Rng, seq::index};
+use rusqlite::{Connection, params};
+
+/// Sorted song IDs per album filter, so draws don't have to scan the `songs` table.
+///
+/// The empty album name holds the IDs of the whole catalog. Anything that inserts,
+/// deletes or moves songs must call [`SongIdCache::invalidate`].
+#[derive(Default)]
+pub struct SongIdCache {
+    ids: Mutex<HashMap<String, Arc<Vec<i32>>>>,
+}
+
+impl SongIdCache {
+    /// Returns the cached IDs for `album`, loading them on first use
+    pub fn get(&self, conn: &Connection, album: &str) -> Result<Arc<Vec<i32>>, rusqlite::Error> {
+        if let Some(ids) = self.ids.lock().unwrap().get(album) {
+            return Ok(ids.clone());
+        }
+
+        let mut stmt =
+            conn.prepare("SELECT id FROM songs WHERE ?1 = '' OR album = ?1 ORDER BY id")?;
+        let id_iter = stmt.query_map(params![album], |row| row.get(0))?;
+
+        let mut ids = Vec::new();
+        for id in id_iter {
+            ids.push(id?);
+        }
+
+        let ids = Arc::new(ids);
+        self.ids
+            .lock()
+            .unwrap()
+            .insert(album.to_string(), ids.clone());
+
+        Ok(ids)
+    }
+
+    /// Drops every cached list; the next draw reloads from the database
+    pub fn invalidate(&self) {
+        self.ids.lock().unwrap().clear();
+    }
+}
+
+/// Picks up to `amount` distinct IDs uniformly, only falling back to `excluded` IDs
+/// when there aren't enough others.
+///
+/// Only draws `amount + excluded.len()` positions, so the cost doesn't grow with `ids`.
+pub fn uniform_sample<R: Rng + ?Sized>(
+    ids: &[i32],
+    amount: usize,
+    excluded: &HashSet<i32>,
+    rng: &mut R,
+) -> Vec<i32> {
+    // At most `excluded.len()` of the drawn positions can be rejected
+    let wanted = (amount + excluded.len()).min(ids.len());
+
+    let mut fresh = Vec::new();
+    let mut repeats = Vec::new();
+    for position in index::sample(rng, ids.len(), wanted) {
+        let id = ids[position];
+        if excluded.contains(&id) {
+            repeats.push(id);
+        } else {
+            fresh.push(id);
+        }
+    }
+
+    fresh.extend(repeats);
+    fresh.truncate(amount);
+    fresh
+}
+
+/// Picks up to `amount` distinct indices from `weights`, favouring heavier entries.
+///
+/// Uses the Efraimidis-Spirakis key `u^(1/w)`, so the result only depends on the weights
+/// and the values drawn from `rng`. Entries with a weight of zero or less are never picked.
+pub fn weighted_sample<R: Rng + ?Sized>(weights: &[f64], amount: usize, rng: &mut R) -> Vec<usize> {
+    let mut keyed: Vec<(f64, usize)> = weights
+        .iter()
+        .enumerate()
+        .filter(|(_, weight)| **weight > 0.0 && weight.is_finite())
+        .map(|(index, weight)| {
+            let u: f64 = rng.r#gen();
+            (u.powf(1.0 / weight), index)
+        })
+        .collect();
+
+    // Highest keys win; ties are broken by index to keep the order stable
+    let by_key = |a: &(f64, usize), b: &(f64, usize)| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1));
+
+    // Only the winners need sorting, which keeps large catalogs cheap
+    if keyed.len() > amount {
+        keyed.select_nth_unstable_by(amount, by_key);
+        keyed.truncate(amount);
+    }
+    keyed.sort_by(by_key);
+
+    keyed.into_iter().map(|(_, index)| index).collect()
+}
+
+/// Picks up to `amount` distinct IDs weighted by the matching entry of `weights`, only
+/// falling back to `excluded` IDs when there aren't enough others.
+pub fn weighted_sample_excluding<R: Rng + ?Sized>(
+    ids: &[i32],
+    weights: &[f64],
+    amount: usize,
+    excluded: &HashSet<i32>,
+    rng: &mut R,
+) -> Vec<i32> {
+    // Fresh IDs first; excluded IDs only fill up what's left
+    let mut picked = Vec::new();
+    for in_excluded in [false, true] {
+        let pool_weights: Vec<f64> = ids
+            .iter()
+            .zip(weights)
+            .map(|(id, weight)| {
+                if excluded.contains(id) == in_excluded {
+                    *weight
+                } else {
+                    0.0
+                }
+            })
+            .collect();
+
+        let indices = weighted_sample(&pool_weights, amount - picked.len(), rng);
+        picked.extend(indices.into_iter().map(|index| ids[index]));
+
+        if picked.len() >= amount {
+            break;
+        }
+    }
+
+    picked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn rng(seed: u64) -> ChaCha8Rng {
+        ChaCha8Rng::seed_from_u64(seed)
+    }
+
+    #[test]
+    fn same_seed_gives_same_picks() {
+        let ids: Vec<i32> = (1..=100).collect();
+        let weights: Vec<f64> = ids.iter().map(|id| f64::from(*id)).collect();
+
+        assert_eq!(
+            uniform_sample(&ids, 10, &HashSet::new(), &mut rng(42)),
+            uniform_sample(&ids, 10, &HashSet::new(), &mut rng(42))
+        );
+        assert_eq!(
+            weighted_sample(&weights, 10, &mut rng(42)),
+            weighted_sample(&weights, 10, &mut rng(42))
+        );
+        assert_ne!(
+            weighted_sample(&weights, 10, &mut rng(42)),
+            weighted_sample(&weights, 10, &mut rng(43))
+        );
+    }
+
+    #[test]
+    fn zero_and_invalid_weights_are_never_picked() {
+        let weights = [0.0, 2.0, -1.0, f64::NAN, 1.0, f64::INFINITY];
+
+        for seed in 0..50 {
+            let mut picked = weighted_sample(&weights, 6, &mut rng(seed));
+            picked.sort_unstable();
+            assert_eq!(picked, vec![1, 4]);
+        }
+    }
+
+    #[test]
+    fn empty_inputs_pick_nothing() {
+        assert!(weighted_sample(&[], 3, &mut rng(1)).is_empty());
+        assert!(weighted_sample(&[0.0, 0.0], 3, &mut rng(1)).is_empty());
+        assert!(uniform_sample(&[], 3, &HashSet::new(), &mut rng(1)).is_empty());
+        assert!(weighted_sample(&[1.0, 2.0], 0, &mut rng(1)).is_empty());
+    }
+
+    #[test]
+    fn amount_larger_than_candidates_returns_each_once() {
+        let ids = [4, 8, 15, 16];
+
+        let mut uniform = uniform_sample(&ids, 10, &HashSet::new(), &mut rng(7));
+        uniform.sort_unstable();
+        assert_eq!(uniform, ids);
+
+        let mut weighted =
+            weighted_sample_excluding(&ids, &[1.0; 4], 10, &HashSet::new(), &mut rng(7));
+        weighted.sort_unstable();
+        assert_eq!(weighted, ids);
+    }
+
+    #[test]
+    fn excluded_ids_only_fill_up_what_is_left() {
+        let ids = [1, 2, 3, 4, 5];
+        let excluded: HashSet<i32> = [1, 2, 3].into_iter().collect();
+
+        for seed in 0..50 {
+            // Enough fresh IDs, so none of the excluded ones show up
+            let mut picked = uniform_sample(&ids, 2, &excluded, &mut rng(seed));
+            picked.sort_unstable();
+            assert_eq!(picked, vec![4, 5]);
+
+            let mut picked =
+                weighted_sample_excluding(&ids, &[1.0; 5], 2, &excluded, &mut rng(seed));
+            picked.sort_unstable();
+            assert_eq!(picked, vec![4, 5]);
+
+            // Fresh IDs come first, then the draw falls back to excluded ones
+            let picked = uniform_sample(&ids, 4, &excluded, &mut rng(seed));
+            assert_eq!(picked.len(), 4);
+            assert!(!excluded.contains(&picked[0]) && !excluded.contains(&picked[1]));
+
+            let picked = weighted_sample_excluding(&ids, &[1.0; 5], 4, &excluded, &mut rng(seed));
+            assert_eq!(picked.len(), 4);
+            assert!(!excluded.contains(&picked[0]) && !excluded.contains(&picked[1]));
+        }
+    }
+}